@@ -8,8 +8,19 @@ pub enum Error {
     TrailingBytes(usize),
     TlvParameterLengthInvalid(u16),
     InvalidType(u16),
+    UnexpectedType { expected: u16, found: u16 },
     InvalidVariant(u32),
+    InvalidString(std::str::Utf8Error),
     UnknownMessageId(u32),
+    DurationOutOfRange(std::time::Duration),
+    MissingParameter { parent: &'static str, missing: &'static str },
+    BufferTooSmall { needed: usize, available: usize },
+    StringTooLong(usize),
+    ArrayTooLong(usize),
+    ParameterTooLong(usize),
+    ValueOutOfRange { value: u32, num_bits: u8 },
+    VersionMismatch { expected: u8, found: u8 },
+    ErrorStatus(crate::enumerations::StatusCode),
 }
 
 impl fmt::Display for Error {
@@ -27,13 +38,125 @@ impl fmt::Display for Error {
                 write!(f, "Invalid length for TLV parameter: {}", len)
             }
             Error::InvalidType(type_id) => write!(f, "Invalid type num: {}", type_id),
+            Error::UnexpectedType { expected, found } => {
+                write!(f, "Unexpected type num: expected {}, found {}", expected, found)
+            }
             Error::InvalidVariant(value) => write!(f, "Invalid variant: {}", value),
+            Error::InvalidString(e) => write!(f, "Invalid string: {}", e),
             Error::UnknownMessageId(id) => write!(f, "Unknown message id: {}", id),
+            Error::DurationOutOfRange(duration) => {
+                write!(f, "Duration out of range: {:?}", duration)
+            }
+            Error::MissingParameter { parent, missing } => {
+                write!(f, "Missing required parameter {} in {}", missing, parent)
+            }
+            Error::BufferTooSmall { needed, available } => write!(
+                f,
+                "Buffer too small: {} bytes needed, but only {} available",
+                needed, available
+            ),
+            Error::StringTooLong(len) => write!(f, "String too long: {} bytes", len),
+            Error::ArrayTooLong(len) => write!(f, "Array too long: {} items", len),
+            Error::ParameterTooLong(len) => write!(f, "Parameter too long: {} bytes", len),
+            Error::ValueOutOfRange { value, num_bits } => {
+                write!(f, "Value out of range: {} does not fit in {} bits", value, num_bits)
+            }
+            Error::VersionMismatch { expected, found } => {
+                write!(f, "Protocol version mismatch: expected {}, found {}", expected, found)
+            }
+            Error::ErrorStatus(status_code) => write!(f, "Reader returned status {:?}", status_code),
         }
     }
 }
 impl std::error::Error for Error {}
 
+/// I/O errors are equal if they are of the same kind, all other errors are compared by value
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        use Error::*;
+
+        match (self, other) {
+            (IoError(a), IoError(b)) => a.kind() == b.kind(),
+            (
+                InsufficientData { needed, remaining },
+                InsufficientData { needed: other_needed, remaining: other_remaining },
+            ) => needed == other_needed && remaining == other_remaining,
+            (TrailingBits(a), TrailingBits(b)) => a == b,
+            (TrailingBytes(a), TrailingBytes(b)) => a == b,
+            (TlvParameterLengthInvalid(a), TlvParameterLengthInvalid(b)) => a == b,
+            (InvalidType(a), InvalidType(b)) => a == b,
+            (
+                UnexpectedType { expected, found },
+                UnexpectedType { expected: other_expected, found: other_found },
+            ) => expected == other_expected && found == other_found,
+            (InvalidVariant(a), InvalidVariant(b)) => a == b,
+            (InvalidString(a), InvalidString(b)) => a == b,
+            (UnknownMessageId(a), UnknownMessageId(b)) => a == b,
+            (DurationOutOfRange(a), DurationOutOfRange(b)) => a == b,
+            (
+                MissingParameter { parent, missing },
+                MissingParameter { parent: other_parent, missing: other_missing },
+            ) => parent == other_parent && missing == other_missing,
+            (
+                BufferTooSmall { needed, available },
+                BufferTooSmall { needed: other_needed, available: other_available },
+            ) => needed == other_needed && available == other_available,
+            (StringTooLong(a), StringTooLong(b)) => a == b,
+            (ArrayTooLong(a), ArrayTooLong(b)) => a == b,
+            (ParameterTooLong(a), ParameterTooLong(b)) => a == b,
+            (
+                ValueOutOfRange { value, num_bits },
+                ValueOutOfRange { value: other_value, num_bits: other_num_bits },
+            ) => value == other_value && num_bits == other_num_bits,
+            (
+                VersionMismatch { expected, found },
+                VersionMismatch { expected: other_expected, found: other_found },
+            ) => expected == other_expected && found == other_found,
+            (ErrorStatus(a), ErrorStatus(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Note: I/O errors are cloned by kind and message, since `io::Error` is not `Clone`
+impl Clone for Error {
+    fn clone(&self) -> Self {
+        match self {
+            Error::IoError(e) => Error::IoError(io::Error::new(e.kind(), e.to_string())),
+            Error::InsufficientData { needed, remaining } => {
+                Error::InsufficientData { needed: *needed, remaining: *remaining }
+            }
+            Error::TrailingBits(len) => Error::TrailingBits(*len),
+            Error::TrailingBytes(len) => Error::TrailingBytes(*len),
+            Error::TlvParameterLengthInvalid(len) => Error::TlvParameterLengthInvalid(*len),
+            Error::InvalidType(type_id) => Error::InvalidType(*type_id),
+            Error::UnexpectedType { expected, found } => {
+                Error::UnexpectedType { expected: *expected, found: *found }
+            }
+            Error::InvalidVariant(value) => Error::InvalidVariant(*value),
+            Error::InvalidString(e) => Error::InvalidString(*e),
+            Error::UnknownMessageId(id) => Error::UnknownMessageId(*id),
+            Error::DurationOutOfRange(duration) => Error::DurationOutOfRange(*duration),
+            Error::MissingParameter { parent, missing } => {
+                Error::MissingParameter { parent, missing }
+            }
+            Error::BufferTooSmall { needed, available } => {
+                Error::BufferTooSmall { needed: *needed, available: *available }
+            }
+            Error::StringTooLong(len) => Error::StringTooLong(*len),
+            Error::ArrayTooLong(len) => Error::ArrayTooLong(*len),
+            Error::ParameterTooLong(len) => Error::ParameterTooLong(*len),
+            Error::ValueOutOfRange { value, num_bits } => {
+                Error::ValueOutOfRange { value: *value, num_bits: *num_bits }
+            }
+            Error::VersionMismatch { expected, found } => {
+                Error::VersionMismatch { expected: *expected, found: *found }
+            }
+            Error::ErrorStatus(status_code) => Error::ErrorStatus(status_code.clone()),
+        }
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Self {
         Error::IoError(err)
@@ -56,27 +179,89 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub trait LLRPMessage: Sized {
     const ID: u16;
 
-    fn decode(data: &[u8]) -> Result<(Self, &[u8])>;
-    fn encode(&self, buffer: &mut Vec<u8>);
+    /// Decodes the fields of the message from `decoder`, leaving any data that follows them
+    fn decode_from(decoder: &mut Decoder) -> Result<Self>;
+    fn encode_with(&self, encoder: &mut Encoder);
+
+    /// Decodes a message, returning it along with any bytes that follow it in `data`
+    fn decode(data: &[u8]) -> Result<(Self, &[u8])> {
+        let mut decoder = Decoder::new(data);
+        let message = Self::decode_from(&mut decoder)?;
+        Ok((message, decoder.bytes))
+    }
+
+    fn encode(&self, buffer: &mut Vec<u8>) {
+        self.encode_with(&mut Encoder::new(buffer))
+    }
+
+    /// Encodes the message, returning an error if a value does not fit in its encoded field (e.g. a
+    /// string longer than 65535 bytes), instead of silently truncating it
+    fn encode_checked(&self, buffer: &mut Vec<u8>) -> Result<usize> {
+        let mut encoder = Encoder::new(buffer);
+        self.encode_with(&mut encoder);
+        encoder.finish()
+    }
+
+    /// Encodes the message into a fixed size buffer, returning the number of bytes written
+    fn encode_to_slice(&self, buffer: &mut [u8]) -> Result<usize> {
+        let mut encoder = Encoder::from_slice(buffer);
+        self.encode_with(&mut encoder);
+        encoder.finish()
+    }
 
     fn id(&self) -> u16 {
         Self::ID
     }
+
+    /// Checks that the message contains all the parameters required by the LLRP specification
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
+/// A message sent by a client that the reader answers with a message of type `Response`
+pub trait LLRPRequest: LLRPMessage {
+    type Response: LLRPResponse;
+}
+
+/// A message sent by the reader that reports the status of a request
+pub trait LLRPResponse: LLRPMessage {
+    fn status(&self) -> &crate::parameters::LLRPStatus;
+
+    /// Returns whether the reader reported that the request succeeded
+    fn is_success(&self) -> bool {
+        self.status().status_code == crate::enumerations::StatusCode::M_Success
+    }
+}
+
+/// A parameter encoded with a TLV header.
+///
+/// Parameters defined outside of this crate (e.g. vendor extensions) can implement this trait
+/// together with `LLRPValue`, using `Decoder::tlv_param` and `Encoder::tlv_param` to handle the
+/// header.
 pub trait TlvParameter: Sized {
     const ID: u16;
 }
 
+/// A value that can be encoded and decoded as part of an LLRP message
 pub trait LLRPValue: Sized {
+    /// Returns whether a parameter with type num `type_num` can be decoded as this value
     fn can_decode_type(_: u16) -> bool {
         false
     }
 
     fn decode(decoder: &mut Decoder) -> Result<Self>;
 
+    /// Decodes a parameter with type num `type_num`, which the caller has already peeked from the
+    /// start of the decoder, allowing implementations to skip checking the type again
+    fn decode_type(decoder: &mut Decoder, _type_num: u16) -> Result<Self> {
+        Self::decode(decoder)
+    }
+
     fn decode_tv(decoder: &mut Decoder, tv_id: u8) -> Result<Self> {
         decoder.check_param_type(tv_id as u16)?;
+        #[cfg(feature = "decode_profile")]
+        let _timer = crate::profile::Timer::start(ParameterType::Tv(tv_id));
         Self::decode(decoder)
     }
 
@@ -86,6 +271,11 @@ pub trait LLRPValue: Sized {
         encoder.write_param_type(ParameterType::Tv(tv_id));
         self.encode(encoder)
     }
+
+    /// Checks that the value contains all the sub-parameters required by the LLRP specification
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
 macro_rules! impl_llrp_value_primitive {
@@ -119,15 +309,186 @@ impl LLRPValue for [u8; 12] {
     }
 }
 
+/// A string in a message or parameter. It dereferences to `str`, converts from `String` and
+/// `&str`, and compares equal to them.
+///
+/// With the `intern_strings` feature the string is reference counted, and identical strings decoded
+/// on the same thread (e.g. error descriptions and firmware versions) share a single allocation.
+/// The API is the same with and without the feature.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+pub struct LLRPString(StringRepr);
+
+#[cfg(not(feature = "intern_strings"))]
+type StringRepr = String;
+
+#[cfg(feature = "intern_strings")]
+type StringRepr = std::sync::Arc<str>;
+
+impl LLRPString {
+    /// Returns the contents of the string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for LLRPString {
+    fn default() -> Self {
+        LLRPString::from("")
+    }
+}
+
+impl std::ops::Deref for LLRPString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for LLRPString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::borrow::Borrow<str> for LLRPString {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for LLRPString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for LLRPString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl From<&str> for LLRPString {
+    fn from(value: &str) -> Self {
+        LLRPString(value.into())
+    }
+}
+
+impl From<String> for LLRPString {
+    #[cfg(not(feature = "intern_strings"))]
+    fn from(value: String) -> Self {
+        LLRPString(value)
+    }
+
+    #[cfg(feature = "intern_strings")]
+    fn from(value: String) -> Self {
+        LLRPString(value.into())
+    }
+}
+
+impl From<LLRPString> for String {
+    #[cfg(not(feature = "intern_strings"))]
+    fn from(value: LLRPString) -> Self {
+        value.0
+    }
+
+    #[cfg(feature = "intern_strings")]
+    fn from(value: LLRPString) -> Self {
+        value.0.to_string()
+    }
+}
+
+impl PartialEq<str> for LLRPString {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for LLRPString {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for LLRPString {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<LLRPString> for str {
+    fn eq(&self, other: &LLRPString) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<LLRPString> for &str {
+    fn eq(&self, other: &LLRPString) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl PartialEq<LLRPString> for String {
+    fn eq(&self, other: &LLRPString) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl LLRPValue for LLRPString {
+    fn decode(decoder: &mut Decoder) -> Result<Self> {
+        let len = decoder.read::<u16>()? as usize;
+        let value = std::str::from_utf8(decoder.read_bytes(len)?).map_err(Error::InvalidString)?;
+
+        #[cfg(feature = "intern_strings")]
+        return Ok(LLRPString(intern(value)));
+
+        #[cfg(not(feature = "intern_strings"))]
+        Ok(value.into())
+    }
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write_len(self.len(), Error::StringTooLong);
+        encoder.write_bytes(self.as_bytes());
+    }
+}
+
+/// Returns a shared copy of `value`, reusing a previously decoded string if possible
+#[cfg(feature = "intern_strings")]
+fn intern(value: &str) -> std::sync::Arc<str> {
+    use std::{cell::RefCell, collections::HashSet, sync::Arc};
+
+    // Limits the number of distinct strings kept alive by the cache
+    const MAX_INTERNED: usize = 4096;
+
+    thread_local! {
+        static STRINGS: RefCell<HashSet<Arc<str>>> = RefCell::new(HashSet::new());
+    }
+
+    STRINGS.with(|strings| {
+        let mut strings = strings.borrow_mut();
+        if let Some(existing) = strings.get(value) {
+            return existing.clone();
+        }
+        if strings.len() >= MAX_INTERNED {
+            strings.clear();
+        }
+        let value: Arc<str> = value.into();
+        strings.insert(value.clone());
+        value
+    })
+}
+
 impl LLRPValue for String {
     fn decode(decoder: &mut Decoder) -> Result<Self> {
         let len = decoder.read::<u16>()? as usize;
-        Ok(String::from_utf8(decoder.read_bytes(len)?.into())
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?)
+        String::from_utf8(decoder.read_bytes(len)?.into())
+            .map_err(|e| Error::InvalidString(e.utf8_error()))
     }
 
     fn encode(&self, encoder: &mut Encoder) {
-        encoder.write_bytes(&(self.len() as u16).to_be_bytes());
+        encoder.write_len(self.len(), Error::StringTooLong);
         encoder.write_bytes(self.as_bytes());
     }
 }
@@ -159,6 +520,110 @@ impl LLRPValue for BitArray {
     }
 }
 
+/// Values that can be formatted as a hex string
+pub trait FormatHex {
+    fn to_hex(&self) -> String;
+}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Appends `bytes` to `out` formatted as lowercase hex
+pub fn write_hex(out: &mut String, bytes: &[u8]) {
+    out.reserve(bytes.len() * 2);
+    for &byte in bytes {
+        out.push(HEX_DIGITS[(byte >> 4) as usize] as char);
+        out.push(HEX_DIGITS[(byte & 0xf) as usize] as char);
+    }
+}
+
+/// Parses a hex string (in either case) into bytes. Returns `None` if the string has an odd
+/// length or contains a character that is not a hex digit.
+pub fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    fn digit(c: u8) -> Option<u8> {
+        match c {
+            b'0'..=b'9' => Some(c - b'0'),
+            b'a'..=b'f' => Some(c - b'a' + 10),
+            b'A'..=b'F' => Some(c - b'A' + 10),
+            _ => None,
+        }
+    }
+
+    let hex = hex.as_bytes();
+    if hex.len() % 2 == 1 {
+        return None;
+    }
+    hex.chunks(2).map(|pair| Some((digit(pair[0])? << 4) | digit(pair[1])?)).collect()
+}
+
+/// Formats a number of microseconds since the Unix epoch as an RFC 3339 timestamp in UTC, e.g.
+/// `2019-09-30T04:48:08.123456Z`
+pub fn format_datetime(microseconds: u64) -> String {
+    let secs = microseconds / 1_000_000;
+    let (days, time) = (secs / 86_400, secs % 86_400);
+
+    // Converts the number of days since the epoch to a date in the proleptic Gregorian calendar,
+    // using the algorithm from http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as u64;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+        microseconds % 1_000_000
+    )
+}
+
+impl FormatHex for [u8] {
+    fn to_hex(&self) -> String {
+        let mut out = String::new();
+        write_hex(&mut out, self);
+        out
+    }
+}
+
+impl FormatHex for [u16] {
+    fn to_hex(&self) -> String {
+        let mut out = String::with_capacity(self.len() * 4);
+        for word in self {
+            write_hex(&mut out, &word.to_be_bytes());
+        }
+        out
+    }
+}
+
+impl<T> FormatHex for Vec<T>
+where
+    [T]: FormatHex,
+{
+    fn to_hex(&self) -> String {
+        self.as_slice().to_hex()
+    }
+}
+
+impl FormatHex for [u8; 12] {
+    fn to_hex(&self) -> String {
+        self[..].to_hex()
+    }
+}
+
+impl FormatHex for BitArray {
+    fn to_hex(&self) -> String {
+        self.bytes.to_hex()
+    }
+}
+
 pub(crate) struct BytesToEnd<'a>(std::borrow::Cow<'a, [u8]>);
 
 impl<'a> BytesToEnd<'a> {
@@ -173,9 +638,7 @@ impl<'a> BytesToEnd<'a> {
 
 impl<'a> LLRPValue for BytesToEnd<'a> {
     fn decode(decoder: &mut Decoder) -> Result<Self> {
-        let bytes = decoder.bytes;
-        decoder.bytes = &[];
-        Ok(Self(bytes.to_vec().into()))
+        Ok(Self(decoder.read_to_end().to_vec().into()))
     }
 
     fn encode(&self, encoder: &mut Encoder) {
@@ -186,7 +649,9 @@ impl<'a> LLRPValue for BytesToEnd<'a> {
 impl<T: LLRPValue> LLRPValue for Option<T> {
     fn decode(decoder: &mut Decoder) -> Result<Self> {
         match decoder.peek_param_type() {
-            Ok(ref ty) if T::can_decode_type(ty.as_u16()) => Ok(Some(decoder.read()?)),
+            Ok(ty) if !T::can_decode_type(ty.as_u16()) => Ok(None),
+            Ok(ty) if decoder.is_lenient() => decoder.read_recoverable(ty.as_u16()),
+            Ok(ty) => Ok(Some(T::decode_type(decoder, ty.as_u16())?)),
             _ => Ok(None),
         }
     }
@@ -210,6 +675,13 @@ impl<T: LLRPValue> LLRPValue for Option<T> {
         }
     }
 
+    fn validate(&self) -> Result<()> {
+        match self {
+            Some(value) => value.validate(),
+            None => Ok(()),
+        }
+    }
+
     fn can_decode_type(type_num: u16) -> bool {
         T::can_decode_type(type_num)
     }
@@ -220,10 +692,18 @@ impl<T: LLRPValue> LLRPValue for Box<T> {
         Ok(Box::new(T::decode(decoder)?))
     }
 
+    fn decode_type(decoder: &mut Decoder, type_num: u16) -> Result<Self> {
+        Ok(Box::new(T::decode_type(decoder, type_num)?))
+    }
+
     fn encode(&self, encoder: &mut Encoder) {
         self.as_ref().encode(encoder)
     }
 
+    fn validate(&self) -> Result<()> {
+        self.as_ref().validate()
+    }
+
     fn can_decode_type(type_num: u16) -> bool {
         T::can_decode_type(type_num)
     }
@@ -238,8 +718,10 @@ impl<T: LLRPValue> LLRPValue for Vec<T> {
 
         loop {
             match decoder.get_message_type() {
-                Ok(ty) if T::can_decode_type(ty) => output.push(T::decode(decoder)?),
-                _ => break,
+                Ok(ty) if !T::can_decode_type(ty) => break,
+                Ok(ty) if decoder.is_lenient() => output.extend(decoder.read_recoverable(ty)?),
+                Ok(ty) => output.push(T::decode_type(decoder, ty)?),
+                Err(_) => break,
             }
         }
 
@@ -252,6 +734,10 @@ impl<T: LLRPValue> LLRPValue for Vec<T> {
         }
     }
 
+    fn validate(&self) -> Result<()> {
+        self.iter().try_for_each(|value| value.validate())
+    }
+
     fn can_decode_type(type_num: u16) -> bool {
         T::can_decode_type(type_num)
     }
@@ -308,13 +794,14 @@ impl<E: LLRPEnumeration> crate::Bits for E {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ParameterType {
     Tv(u8),
     Tlv(u16),
 }
 
 impl ParameterType {
-    fn as_u16(&self) -> u16 {
+    pub fn as_u16(&self) -> u16 {
         match *self {
             ParameterType::Tv(id) => id as u16,
             ParameterType::Tlv(id) => id,
@@ -322,35 +809,201 @@ impl ParameterType {
     }
 }
 
+/// The number of bytes in the header of a TLV parameter (type num and length)
+pub const TLV_HEADER_LEN: usize = 4;
+
+/// Parses the type of the parameter starting at the beginning of `bytes`
+pub fn parse_param_type(bytes: &[u8]) -> Result<ParameterType> {
+    if bytes.len() < 2 {
+        return Err(Error::InsufficientData { needed: 2, remaining: bytes.len() });
+    }
+
+    if bytes[0] & 0b1000_0000 != 0 {
+        return Ok(ParameterType::Tv(bytes[0] & 0b0111_1111));
+    }
+
+    // [6-bit resv, 10-bit message type]
+    Ok(ParameterType::Tlv(u16::from_be_bytes([bytes[0], bytes[1]]) & 0b11_1111_1111))
+}
+
+/// Parses the header of a TLV parameter, returning the type num and the length of the parameter.
+///
+/// Note: The length covers the entire parameter including the header
+pub fn parse_tlv_header(bytes: &[u8]) -> Result<(u16, u16)> {
+    if bytes.len() < TLV_HEADER_LEN {
+        return Err(Error::InsufficientData { needed: TLV_HEADER_LEN, remaining: bytes.len() });
+    }
+
+    let type_num = match parse_param_type(bytes)? {
+        ParameterType::Tlv(id) => id,
+        other => return Err(Error::InvalidType(other.as_u16())),
+    };
+
+    let len = u16::from_be_bytes([bytes[2], bytes[3]]);
+    if (len as usize) < TLV_HEADER_LEN {
+        return Err(Error::TlvParameterLengthInvalid(len));
+    }
+
+    Ok((type_num, len))
+}
+
+/// Writes the header of a TLV parameter
+pub fn write_tlv_header(buffer: &mut Vec<u8>, type_num: u16, len: u16) {
+    buffer.extend_from_slice(&(type_num & 0b11_1111_1111).to_be_bytes());
+    buffer.extend_from_slice(&len.to_be_bytes());
+}
+
+/// Writes the type prefix of a TV parameter
+pub fn write_tv_type(buffer: &mut Vec<u8>, type_num: u8) {
+    buffer.push(type_num | 0b1000_0000);
+}
+
+/// The maximum width of a bit field read with `Decoder::read_bits` or written with
+/// `Encoder::write_bits`
+pub const MAX_BITS: u8 = 32;
+
+/// A saved position of a [`Decoder`] that can be restored with [`Decoder::rollback`]
+#[derive(Clone, Copy)]
+pub struct Checkpoint<'a> {
+    bytes: &'a [u8],
+    bits: u64,
+    valid_bits: u8,
+}
+
 #[derive(Default, Clone)]
 pub struct Decoder<'a> {
     bytes: &'a [u8],
-    bits: u32,
+    bits: u64,
     valid_bits: u8,
+
+    /// The type num of the message or parameter being decoded, used to report warnings
+    parent: u16,
+
+    /// The warnings recorded in lenient mode, shared with the decoders of nested parameters
+    warnings: Option<std::sync::Arc<std::sync::Mutex<Vec<DecodeWarning>>>>,
 }
 
 impl<'a> Decoder<'a> {
     pub fn new(bytes: &'a [u8]) -> Decoder<'a> {
-        Decoder { bytes, bits: 0, valid_bits: 0 }
+        Decoder { bytes, bits: 0, valid_bits: 0, parent: 0, warnings: None }
+    }
+
+    /// Creates a decoder that skips data it cannot decode instead of failing, recording a warning
+    /// that describes what was skipped. The warnings are returned by `take_warnings`.
+    ///
+    /// The following problems are recoverable:
+    ///
+    /// - Trailing data at the end of a parameter (e.g. a field added by a newer version of the
+    ///   specification).
+    /// - Parameters that are not expected at their position (e.g. a vendor extension). Known
+    ///   parameters that follow them are still decoded.
+    /// - Optional or repeated parameters containing an unknown enumeration value, which are skipped.
+    ///   An unknown value in a required parameter is still an error.
+    /// - Reserved bits that are set, which are otherwise ignored.
+    pub fn lenient(bytes: &'a [u8]) -> Decoder<'a> {
+        Decoder { warnings: Some(Default::default()), ..Decoder::new(bytes) }
+    }
+
+    /// Returns whether the decoder was created with `lenient`
+    pub fn is_lenient(&self) -> bool {
+        self.warnings.is_some()
+    }
+
+    /// Removes and returns the warnings recorded so far
+    pub fn take_warnings(&self) -> Vec<DecodeWarning> {
+        match self.warnings() {
+            Some(mut warnings) => warnings.drain(..).collect(),
+            None => vec![],
+        }
+    }
+
+    /// Records `warning` if the decoder is lenient, returning whether it was recorded
+    pub fn warn(&self, warning: DecodeWarning) -> bool {
+        match self.warnings() {
+            Some(mut warnings) => {
+                warnings.push(warning);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the warnings recorded so far if the decoder is lenient
+    fn warnings(&self) -> Option<std::sync::MutexGuard<'_, Vec<DecodeWarning>>> {
+        let warnings = self.warnings.as_ref()?;
+        // A panic while a warning is recorded cannot leave the list in an invalid state
+        Some(warnings.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Saves the current position of the decoder
+    pub fn checkpoint(&self) -> Checkpoint<'a> {
+        Checkpoint { bytes: self.bytes, bits: self.bits, valid_bits: self.valid_bits }
+    }
+
+    /// Restores the decoder to a position previously saved with `checkpoint`
+    pub fn rollback(&mut self, checkpoint: Checkpoint<'a>) {
+        self.bytes = checkpoint.bytes;
+        self.bits = checkpoint.bits;
+        self.valid_bits = checkpoint.valid_bits;
+    }
+
+    /// Attempts to decode a value using `decode`, restoring the position of the decoder if it
+    /// fails
+    pub fn try_decode<T, F>(&mut self, decode: F) -> Result<T>
+    where
+        F: FnOnce(&mut Decoder<'a>) -> Result<T>,
+    {
+        let checkpoint = self.checkpoint();
+        let result = decode(self);
+        if result.is_err() {
+            self.rollback(checkpoint);
+        }
+        result
+    }
+
+    /// Returns the number of bytes that have not yet been consumed
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
     }
 
     pub fn tlv_param<T, F>(&mut self, tlv_id: u16, decode: F) -> Result<T>
+    where
+        F: FnOnce(&mut Decoder<'a>) -> Result<T>,
+    {
+        self.expect_tlv_type(tlv_id)?;
+        self.tlv_param_unchecked(tlv_id, decode)
+    }
+
+    /// Checks that the next parameter is a TLV parameter with type num `tlv_id`
+    pub fn expect_tlv_type(&self, tlv_id: u16) -> Result<()> {
+        match self.peek_param_type()? {
+            ParameterType::Tlv(id) if id == tlv_id => Ok(()),
+            other => Err(Error::UnexpectedType { expected: tlv_id, found: other.as_u16() }),
+        }
+    }
+
+    /// Decodes a TLV parameter with type num `tlv_id` without checking the type, for callers that
+    /// have already peeked it
+    pub fn tlv_param_unchecked<T, F>(&mut self, tlv_id: u16, decode: F) -> Result<T>
     where
         F: FnOnce(&mut Decoder<'a>) -> Result<T>,
     {
         let mut decoder = self.clone();
-        decoder.check_param_type(tlv_id)?;
+        decoder.bytes = &decoder.bytes[2..];
+        #[cfg(feature = "decode_profile")]
+        let _timer = crate::profile::Timer::start(ParameterType::Tlv(tlv_id));
 
         // Decode the parameter length field.
         // Note: The length field covers the entire parameter including the header
         let param_len = decoder.read::<u16>()? as usize;
-        if param_len < 4 || param_len > self.bytes.len() {
+        if param_len < TLV_HEADER_LEN || param_len > self.bytes.len() {
             return Err(Error::TlvParameterLengthInvalid(param_len as u16));
         }
-        decoder.bytes = &self.bytes[4..param_len];
+        decoder.bytes = &self.bytes[TLV_HEADER_LEN..param_len];
+        decoder.parent = tlv_id;
 
         let result = decode(&mut decoder)?;
-        decoder.validate_consumed()?;
+        decoder.validate_consumed_in(tlv_id)?;
 
         self.bytes = &self.bytes[param_len..];
 
@@ -398,7 +1051,7 @@ impl<'a> Decoder<'a> {
                 self.bytes = &self.bytes[2..];
                 Ok(())
             }
-            other => Err(Error::InvalidType(other.as_u16())),
+            other => Err(Error::UnexpectedType { expected: type_id, found: other.as_u16() }),
         }
     }
 
@@ -410,16 +1063,7 @@ impl<'a> Decoder<'a> {
     }
 
     pub fn peek_param_type(&self) -> Result<ParameterType> {
-        if self.bytes.len() < 2 {
-            return Err(Error::InsufficientData { needed: 2, remaining: self.bytes.len() });
-        }
-
-        if self.bytes[0] & 0b1000_0000 != 0 {
-            return Ok(ParameterType::Tv(self.bytes[0] & 0b0111_1111));
-        }
-
-        // [6-bit resv, 10-bit message type]
-        Ok(ParameterType::Tlv(u16::from_be_bytes([self.bytes[0], self.bytes[1]]) & 0b11_1111_1111))
+        parse_param_type(self.bytes)
     }
 
     pub fn read<T: LLRPValue>(&mut self) -> Result<T> {
@@ -431,16 +1075,38 @@ impl<'a> Decoder<'a> {
     }
 
     pub fn read_bits<T: Bits>(&mut self, num_bits: u8) -> Result<T> {
+        // The accumulator holds at most 7 leftover bits in addition to the bits being read
+        assert!(num_bits <= MAX_BITS, "bit fields are limited to {} bits", MAX_BITS);
+
         while self.valid_bits < num_bits {
-            self.bits = (self.bits << 8) | self.read::<u8>()? as u32;
+            self.bits = (self.bits << 8) | self.read::<u8>()? as u64;
             self.valid_bits += 8;
         }
         self.valid_bits -= num_bits;
         let mask = (1 << num_bits) - 1;
-        Ok(Bits::from_bits((self.bits >> self.valid_bits) & mask))
+        Ok(Bits::from_bits(((self.bits >> self.valid_bits) & mask) as u32))
     }
 
-    pub(crate) fn read_bytes(&mut self, num_bytes: usize) -> Result<&'a [u8]> {
+    /// Checks that the next parameter is of type `T`, returning `Error::MissingParameter` if it is
+    /// not
+    pub fn require<T: LLRPValue>(&self, parent: &'static str, missing: &'static str) -> Result<()> {
+        match self.peek_param_type() {
+            Ok(ty) if T::can_decode_type(ty.as_u16()) => Ok(()),
+            _ => Err(Error::MissingParameter { parent, missing }),
+        }
+    }
+
+    /// Checks that the next parameter is a TV parameter with type `tv_id`, returning
+    /// `Error::MissingParameter` if it is not
+    pub fn require_tv(&self, tv_id: u8, parent: &'static str, missing: &'static str) -> Result<()> {
+        match self.peek_param_type() {
+            Ok(ParameterType::Tv(id)) if id == tv_id => Ok(()),
+            _ => Err(Error::MissingParameter { parent, missing }),
+        }
+    }
+
+    /// Reads the next `num_bytes` bytes from the input
+    pub fn read_bytes(&mut self, num_bytes: usize) -> Result<&'a [u8]> {
         if self.bytes.len() < num_bytes {
             return Err(Error::InsufficientData { needed: num_bytes, remaining: self.bytes.len() });
         }
@@ -451,44 +1117,240 @@ impl<'a> Decoder<'a> {
         Ok(result)
     }
 
+    /// Reads all of the remaining bytes from the input
+    pub fn read_to_end(&mut self) -> &'a [u8] {
+        let result = self.bytes;
+        self.bytes = &[];
+        result
+    }
+
     /// Ensures that all bytes were consumed when parsing the struct fields
-    /// TODO: consider adding a feature to run in `relaxed` mode where this error is ignored
-    pub(crate) fn validate_consumed(&self) -> Result<()> {
+    pub fn validate_consumed(&self) -> Result<()> {
         if !self.bytes.is_empty() {
             return Err(Error::TrailingBytes(self.bytes.len()));
         }
         Ok(())
     }
+
+    /// Ensures that all bytes were consumed when parsing the fields of `parent`, unless the
+    /// decoder is lenient, in which case the remaining bytes are reported as a warning
+    pub fn validate_consumed_in(&self, parent: u16) -> Result<()> {
+        if !self.bytes.is_empty() && self.warn(DecodeWarning::new(parent, self.bytes)) {
+            return Ok(());
+        }
+        self.validate_consumed()
+    }
+
+    /// In lenient mode, skips any parameters at the current position that are unexpected.
+    /// `expected` checks the type nums accepted by each of the fields that remain in the parent,
+    /// starting with the field about to be decoded.
+    ///
+    /// A parameter is unexpected if none of the remaining fields accept it, or if it is out of
+    /// order: it belongs to a later field, but a parameter for an earlier field follows it (e.g. a
+    /// vendor `Custom` parameter placed before `AntennaID`). Skipping stops at data that is not a
+    /// parameter whose length is known; it is reported as trailing data once the parent has been
+    /// decoded.
+    pub fn skip_unexpected(&mut self, expected: &[fn(u16) -> bool]) {
+        if !self.is_lenient() {
+            return;
+        }
+
+        while let Ok(ty) = self.peek_param_type() {
+            let type_num = ty.as_u16();
+            let unexpected = match expected.iter().position(|expected| expected(type_num)) {
+                Some(0) => false,
+                Some(field) => self.followed_by(&expected[..field]),
+                None => true,
+            };
+            if !unexpected {
+                return;
+            }
+
+            let len = match param_len(self.bytes, ty) {
+                Some(len) => len,
+                None => return,
+            };
+            self.warn(DecodeWarning::UnexpectedParameter { parent: self.parent, type_num, len });
+            self.bytes = &self.bytes[len..];
+        }
+    }
+
+    /// Returns whether any of the parameters after the next one are accepted by `earlier`
+    fn followed_by(&self, earlier: &[fn(u16) -> bool]) -> bool {
+        let mut bytes = self.bytes;
+        let mut first = true;
+        while let Ok(ty) = parse_param_type(bytes) {
+            if !first && earlier.iter().any(|expected| expected(ty.as_u16())) {
+                return true;
+            }
+            first = false;
+            match param_len(bytes, ty) {
+                Some(len) => bytes = &bytes[len..],
+                None => return false,
+            }
+        }
+        false
+    }
+
+    /// In lenient mode, reports reserved bits that are set
+    pub fn check_reserved<T: Bits>(&self, value: &T) {
+        let value = value.to_bits();
+        if value != 0 {
+            self.warn(DecodeWarning::ReservedBitsSet { parent: self.parent, value });
+        }
+    }
+
+    /// Decodes an optional or repeated parameter. In lenient mode, a TLV parameter containing an
+    /// unknown enumeration value is skipped with a warning, and `None` is returned.
+    fn read_recoverable<T: LLRPValue>(&mut self, type_num: u16) -> Result<Option<T>> {
+        let checkpoint = self.checkpoint();
+        let num_warnings = self.warnings().map_or(0, |warnings| warnings.len());
+
+        let value = match T::decode_type(self, type_num) {
+            Err(Error::InvalidVariant(value)) if self.is_lenient() => value,
+            result => return result.map(Some),
+        };
+
+        self.rollback(checkpoint);
+        let (type_num, len) = match self.peek_param_type()? {
+            ty @ ParameterType::Tlv(type_num) => match param_len(self.bytes, ty) {
+                Some(len) => (type_num, len),
+                None => return Err(Error::InvalidVariant(value)),
+            },
+            ParameterType::Tv(_) => return Err(Error::InvalidVariant(value)),
+        };
+
+        // Drop any warnings for the contents of the skipped parameter
+        if let Some(mut warnings) = self.warnings() {
+            warnings.truncate(num_warnings);
+        }
+        self.warn(DecodeWarning::UnknownEnumValue { parent: self.parent, type_num, value });
+        self.bytes = &self.bytes[len..];
+        Ok(None)
+    }
+}
+
+/// Returns the length of the parameter of type `ty` at the start of `bytes`, if it is known and
+/// the parameter fits in `bytes`
+fn param_len(bytes: &[u8], ty: ParameterType) -> Option<usize> {
+    let len = match ty {
+        ParameterType::Tv(id) => crate::layout::tv_param_len(id)?,
+        ParameterType::Tlv(_) => parse_tlv_header(bytes).ok()?.1 as usize,
+    };
+    match len <= bytes.len() {
+        true => Some(len),
+        false => None,
+    }
+}
+
+/// A recoverable problem that was skipped by a lenient [`Decoder`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeWarning {
+    /// A parameter of type `type_num` was found where none was expected in `parent` (a message or
+    /// parameter type num). It was skipped, along with any data that followed it if the warning
+    /// is for the end of `parent`, for a total of `len` bytes.
+    UnexpectedParameter { parent: u16, type_num: u16, len: usize },
+
+    /// `len` bytes at the end of `parent` (a message or parameter type num) did not contain a
+    /// parameter and were skipped
+    TrailingBytes { parent: u16, len: usize },
+
+    /// The parameter of type `type_num` in `parent` contained the unknown enumeration value
+    /// `value`, and was skipped
+    UnknownEnumValue { parent: u16, type_num: u16, value: u32 },
+
+    /// A reserved field of `parent` (a message or parameter type num) was set to `value` instead
+    /// of 0. The value is ignored.
+    ReservedBitsSet { parent: u16, value: u32 },
+}
+
+impl DecodeWarning {
+    /// Creates a warning for the unused `bytes` at the end of `parent`
+    pub fn new(parent: u16, bytes: &[u8]) -> Self {
+        let len = bytes.len();
+        match parse_param_type(bytes) {
+            Ok(ty) => DecodeWarning::UnexpectedParameter { parent, type_num: ty.as_u16(), len },
+            Err(_) => DecodeWarning::TrailingBytes { parent, len },
+        }
+    }
+}
+
+enum Output<'a> {
+    Vec(&'a mut Vec<u8>),
+
+    /// A fixed size buffer. `len` keeps counting past the end of `buffer` so that the size
+    /// required to encode the value can be reported.
+    Slice { buffer: &'a mut [u8], len: usize },
 }
 
 pub struct Encoder<'a> {
-    buffer: &'a mut Vec<u8>,
-    bits: u32,
+    output: Output<'a>,
+    bits: u64,
     valid_bits: u8,
+
+    /// The first value that could not be encoded correctly
+    error: Option<Error>,
 }
 
 impl<'a> Encoder<'a> {
     pub fn new(buffer: &'a mut Vec<u8>) -> Encoder<'a> {
-        Encoder { buffer, bits: 0, valid_bits: 0 }
+        Encoder { output: Output::Vec(buffer), bits: 0, valid_bits: 0, error: None }
+    }
+
+    /// Creates an encoder that writes to a fixed size buffer. Writes past the end of the buffer
+    /// are discarded and reported as an error by `finish`.
+    pub fn from_slice(buffer: &'a mut [u8]) -> Encoder<'a> {
+        Encoder { output: Output::Slice { buffer, len: 0 }, bits: 0, valid_bits: 0, error: None }
+    }
+
+    /// Returns the number of bytes written so far
+    pub fn len(&self) -> usize {
+        match &self.output {
+            Output::Vec(buffer) => buffer.len(),
+            Output::Slice { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of bytes written, or an error if the output buffer was too small or a
+    /// value did not fit in its encoded field
+    pub fn finish(self) -> Result<usize> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        match self.output {
+            Output::Slice { buffer, len } if len > buffer.len() => {
+                Err(Error::BufferTooSmall { needed: len, available: buffer.len() })
+            }
+            Output::Vec(buffer) => Ok(buffer.len()),
+            Output::Slice { len, .. } => Ok(len),
+        }
     }
 
     pub fn tlv_param(&mut self, tlv_id: u16, encode: impl FnOnce(&mut Encoder<'a>)) {
         self.write_param_type(ParameterType::Tlv(tlv_id));
 
-        let offset = self.buffer.len();
+        let offset = self.len();
         self.write_bytes(&[0, 0]);
 
         encode(self);
 
-        let param_len = (self.buffer.len() - offset + 2) as u16;
-        self.buffer[offset..offset + 2].copy_from_slice(&param_len.to_be_bytes());
+        let param_len = self.len() - offset + 2;
+        if param_len > u16::MAX as usize {
+            self.fail(Error::ParameterTooLong(param_len));
+        }
+        self.overwrite_bytes(offset, &(param_len as u16).to_be_bytes());
     }
 
     pub fn array<T>(&mut self, items: &[T], mut encode: impl FnMut(&mut Encoder<'a>, &T))
     where
         T: LLRPValue,
     {
-        self.write_bytes(&(items.len() as u16).to_be_bytes());
+        self.write_len(items.len(), Error::ArrayTooLong);
         for item in items {
             encode(self, item)
         }
@@ -515,7 +1377,7 @@ impl<'a> Encoder<'a> {
         T: LLRPEnumeration,
         U: LLRPValue + Bits,
     {
-        self.write_bytes(&(items.len() as u16).to_be_bytes());
+        self.write_len(items.len(), Error::ArrayTooLong);
         for item in items {
             self.write_enum::<T, U>(item)
         }
@@ -523,12 +1385,8 @@ impl<'a> Encoder<'a> {
 
     fn write_param_type(&mut self, type_num: ParameterType) {
         match type_num {
-            ParameterType::Tv(id) => {
-                self.write_bytes(&[id | 0b1000_0000]);
-            }
-            ParameterType::Tlv(id) => {
-                self.write_bytes(&id.to_be_bytes());
-            }
+            ParameterType::Tv(id) => self.write_bytes(&[id | 0b1000_0000]),
+            ParameterType::Tlv(id) => self.write_bytes(&id.to_be_bytes()),
         }
     }
 
@@ -541,7 +1399,15 @@ impl<'a> Encoder<'a> {
     }
 
     pub fn write_bits<T: Bits>(&mut self, value: &T, num_bits: u8) {
-        let bits = value.to_bits();
+        assert!(num_bits <= MAX_BITS, "bit fields are limited to {} bits", MAX_BITS);
+
+        // Bits outside of the field are dropped so that they don't corrupt the previous field
+        let mask = (1 << num_bits) - 1;
+        let value = value.to_bits();
+        if value as u64 & !mask != 0 {
+            self.fail(Error::ValueOutOfRange { value, num_bits });
+        }
+        let bits = value as u64 & mask;
 
         self.bits = (self.bits << num_bits) | bits;
         self.valid_bits += num_bits;
@@ -552,7 +1418,42 @@ impl<'a> Encoder<'a> {
         }
     }
 
-    pub(crate) fn write_bytes(&mut self, bytes: &[u8]) {
-        self.buffer.extend_from_slice(bytes);
+    /// Writes the 16-bit length prefix of a string or array, recording `error` if `len` does not fit
+    fn write_len(&mut self, len: usize, error: fn(usize) -> Error) {
+        if len > u16::MAX as usize {
+            self.fail(error(len));
+        }
+        self.write_bytes(&(len as u16).to_be_bytes());
+    }
+
+    /// Records an error to be returned by `finish`, keeping only the first one
+    fn fail(&mut self, error: Error) {
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+    }
+
+    /// Writes raw bytes to the output
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        match &mut self.output {
+            Output::Vec(buffer) => buffer.extend_from_slice(bytes),
+            Output::Slice { buffer, len } => {
+                let end = *len + bytes.len();
+                if end <= buffer.len() {
+                    buffer[*len..end].copy_from_slice(bytes);
+                }
+                *len = end;
+            }
+        }
+    }
+
+    fn overwrite_bytes(&mut self, offset: usize, bytes: &[u8]) {
+        let buffer: &mut [u8] = match &mut self.output {
+            Output::Vec(buffer) => buffer,
+            Output::Slice { buffer, .. } => buffer,
+        };
+        if let Some(dst) = buffer.get_mut(offset..offset + bytes.len()) {
+            dst.copy_from_slice(bytes);
+        }
     }
 }
@@ -0,0 +1,222 @@
+//! Consistency checks for parsed definitions, run before generating code
+
+use std::collections::{HashMap, HashSet};
+
+use crate::repr::{type_kinds, Container, Definition, Encoding, Field};
+
+/// Checks `definitions` for problems that would result in incorrect or uncompilable generated
+/// code, returning a description of each problem found.
+pub fn check_definitions(definitions: &[Definition]) -> Vec<String> {
+    let mut errors = vec![];
+
+    let kinds = type_kinds(definitions);
+    let enums: HashMap<String, u16> = definitions
+        .iter()
+        .filter_map(|d| match d {
+            Definition::Enum { ident, variants } => {
+                Some((ident.to_string(), variants.iter().map(|x| x.value).max().unwrap_or(0)))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut message_ids = HashSet::new();
+    let mut parameter_ids = HashSet::new();
+    let mut tv_ids = HashSet::new();
+
+    for d in definitions {
+        let (name, fields) = match d {
+            Definition::Message { id, ident, fields } => {
+                if !message_ids.insert(*id) {
+                    errors.push(format!("{}: duplicate message type number {}", ident, id));
+                }
+                (ident, fields)
+            }
+            Definition::Parameter { id, ident, fields } => {
+                if !parameter_ids.insert(*id) {
+                    errors.push(format!("{}: duplicate parameter type number {}", ident, id));
+                }
+                (ident, fields)
+            }
+            Definition::TvParameter { id, ident, fields } => {
+                if *id > 127 {
+                    errors.push(format!("{}: TV parameter type number {} above 127", ident, id));
+                }
+                if !tv_ids.insert(*id) {
+                    errors.push(format!("{}: duplicate TV parameter type number {}", ident, id));
+                }
+                (ident, fields)
+            }
+            Definition::Choice { ident, choices } => (ident, choices),
+            Definition::Enum { .. } | Definition::Namespace { .. } | Definition::Vendor { .. } => {
+                continue
+            }
+        };
+
+        for field in fields {
+            let context = format!("{}.{}", name, field.ident);
+            check_field(&context, field, &kinds, &enums, &mut errors);
+        }
+    }
+
+    errors
+}
+
+fn check_field(
+    context: &str,
+    field: &Field,
+    kinds: &HashMap<String, crate::repr::Kind>,
+    enums: &HashMap<String, u16>,
+    errors: &mut Vec<String>,
+) {
+    let ty = field.ty.inner().to_string();
+    match &field.encoding {
+        Encoding::TlvParameter | Encoding::TvParameter { .. } if !kinds.contains_key(&ty) => {
+            errors.push(format!("{}: reference to undefined parameter `{}`", context, ty));
+        }
+        Encoding::Enum { inner } => {
+            let max_value = match enums.get(&ty) {
+                Some(max_value) => *max_value,
+                None => {
+                    errors
+                        .push(format!("{}: reference to undefined enumeration `{}`", context, ty));
+                    return;
+                }
+            };
+
+            let element = match &inner.encoding {
+                Encoding::ArrayOfT { inner: element } => element,
+                _ => inner,
+            };
+            if let Some(num_bits) = bit_width(element) {
+                if num_bits < 16 && max_value >= 1 << num_bits {
+                    errors.push(format!(
+                        "{}: enumeration `{}` has values that do not fit in {} bits",
+                        context, ty, num_bits
+                    ));
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns the number of bits used to encode a numeric field
+fn bit_width(field: &Field) -> Option<u8> {
+    match (&field.encoding, &field.ty) {
+        (Encoding::RawBits { num_bits }, _) => Some(*num_bits),
+        (Encoding::Primitive, Container::Raw(ty)) => match ty.to_string().as_str() {
+            "u8" | "i8" => Some(8),
+            "u16" | "i16" => Some(16),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::{Span, TokenStream};
+    use quote::quote;
+    use syn::Ident;
+
+    use super::check_definitions;
+    use crate::repr::{Container, Definition, Encoding, EnumVariant, Field};
+
+    fn ident(name: &str) -> Ident {
+        Ident::new(name, Span::call_site())
+    }
+
+    fn field(name: &str, ty: TokenStream, encoding: Encoding) -> Field {
+        Field { ident: ident(name), ty: Container::Raw(ty), encoding, format: None }
+    }
+
+    fn parameter(id: u16, name: &str, fields: Vec<Field>) -> Definition {
+        Definition::Parameter { id, ident: ident(name), fields }
+    }
+
+    #[test]
+    fn valid_definitions() {
+        let definitions = vec![
+            parameter(200, "Inner", vec![field("value", quote!(u8), Encoding::Primitive)]),
+            parameter(201, "Outer", vec![field("inner", quote!(Inner), Encoding::TlvParameter)]),
+            Definition::TvParameter {
+                id: 127,
+                ident: ident("Tv"),
+                fields: vec![field("value", quote!(u16), Encoding::Primitive)],
+            },
+        ];
+        assert_eq!(check_definitions(&definitions), Vec::<String>::new());
+    }
+
+    #[test]
+    fn duplicate_type_numbers() {
+        let definitions = vec![
+            Definition::Message { id: 1, ident: ident("First"), fields: vec![] },
+            Definition::Message { id: 1, ident: ident("Second"), fields: vec![] },
+            parameter(200, "Third", vec![]),
+            parameter(200, "Fourth", vec![]),
+            Definition::TvParameter { id: 1, ident: ident("Fifth"), fields: vec![] },
+            Definition::TvParameter { id: 1, ident: ident("Sixth"), fields: vec![] },
+        ];
+        assert_eq!(
+            check_definitions(&definitions),
+            vec![
+                "Second: duplicate message type number 1",
+                "Fourth: duplicate parameter type number 200",
+                "Sixth: duplicate TV parameter type number 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn tv_type_number_above_127() {
+        let definitions =
+            vec![Definition::TvParameter { id: 128, ident: ident("Tv"), fields: vec![] }];
+        assert_eq!(
+            check_definitions(&definitions),
+            vec!["Tv: TV parameter type number 128 above 127"]
+        );
+    }
+
+    #[test]
+    fn enum_value_wider_than_field() {
+        let variants = vec![
+            EnumVariant { ident: ident("Zero"), value: 0 },
+            EnumVariant { ident: ident("Four"), value: 4 },
+        ];
+        let inner = field("mode", quote!(u8), Encoding::RawBits { num_bits: 2 });
+        let definitions = vec![
+            Definition::Enum { ident: ident("Mode"), variants },
+            parameter(
+                200,
+                "Config",
+                vec![field("mode", quote!(Mode), Encoding::Enum { inner: Box::new(inner) })],
+            ),
+        ];
+        assert_eq!(
+            check_definitions(&definitions),
+            vec!["Config.mode: enumeration `Mode` has values that do not fit in 2 bits"]
+        );
+    }
+
+    #[test]
+    fn dangling_references() {
+        let inner = field("mode", quote!(u8), Encoding::Primitive);
+        let definitions = vec![parameter(
+            200,
+            "Config",
+            vec![
+                field("missing", quote!(Missing), Encoding::TlvParameter),
+                field("mode", quote!(Mode), Encoding::Enum { inner: Box::new(inner) }),
+            ],
+        )];
+        assert_eq!(
+            check_definitions(&definitions),
+            vec![
+                "Config.missing: reference to undefined parameter `Missing`",
+                "Config.mode: reference to undefined enumeration `Mode`",
+            ]
+        );
+    }
+}
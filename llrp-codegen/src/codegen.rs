@@ -1,7 +1,30 @@
+use std::collections::HashMap;
+
+use heck::{ShoutySnakeCase, SnakeCase};
 use proc_macro2::{Ident, Literal, Span, TokenStream};
 use quote::quote;
 
-use crate::repr::{Container, Definition, EnumVariant, Field};
+use crate::repr::{Container, Definition, EnumVariant, Field, Format};
+
+/// Options controlling the shape of the generated code
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Mark generated message and parameter structs as `#[non_exhaustive]`, so that optional
+    /// parameters added by newer definition files are not breaking changes. This prevents the
+    /// structs from being constructed with struct literals outside of the crate containing the
+    /// generated code, so it should be combined with `accessors`. Like `private_fields`, it is
+    /// only meant for crates that vendor or regenerate the code.
+    pub non_exhaustive: bool,
+
+    /// Generate a constructor taking the required fields of generated structs, and getters,
+    /// setters and `with_*` methods for each field.
+    pub accessors: bool,
+
+    /// Make the fields of generated structs private to the crate. This breaks code that uses the
+    /// fields directly, so it is only meant for crates that vendor or regenerate the code, and
+    /// should be combined with `accessors`.
+    pub private_fields: bool,
+}
 
 pub struct GeneratedCode {
     pub(crate) messages: Vec<TokenStream>,
@@ -9,6 +32,13 @@ pub struct GeneratedCode {
     pub(crate) parameters: Vec<TokenStream>,
     pub(crate) enumerations: Vec<TokenStream>,
     pub(crate) choices: Vec<TokenStream>,
+    pub(crate) visitor: TokenStream,
+    pub(crate) diff: TokenStream,
+    pub(crate) namespaces: Vec<TokenStream>,
+    pub(crate) vendors: Vec<TokenStream>,
+    pub(crate) names: TokenStream,
+    pub(crate) layout: TokenStream,
+    pub(crate) roundtrip_tests: TokenStream,
 }
 
 impl std::fmt::Display for GeneratedCode {
@@ -20,6 +50,13 @@ impl std::fmt::Display for GeneratedCode {
         let parameters = &self.parameters;
         let enumerations = &self.enumerations;
         let choices = &self.choices;
+        let visitor = &self.visitor;
+        let diff = &self.diff;
+        let namespaces = &self.namespaces;
+        let vendors = &self.vendors;
+        let names = &self.names;
+        let layout = &self.layout;
+        let roundtrip_tests = &self.roundtrip_tests;
 
         let body = quote! {
             #[allow(bad_style, unused_imports, unused_mut, unused_variables)]
@@ -47,6 +84,47 @@ impl std::fmt::Display for GeneratedCode {
                 use super::{*, parameters::*, enumerations::*};
                 #(#choices)*
             }
+
+            #[allow(bad_style, unused_imports, unused_mut, unused_variables)]
+            pub mod visit {
+                use std::any::Any;
+                use super::{*, messages::*, parameters::*, choices::*};
+                #visitor
+            }
+
+            #[allow(bad_style, unused_imports, unused_mut, unused_variables)]
+            pub mod diff {
+                use super::{*, messages::*, parameters::*, choices::*};
+                #diff
+            }
+
+            /// The XML namespace URIs of the definitions, named by their prefix
+            pub mod namespaces {
+                #(#namespaces)*
+            }
+
+            /// The IANA Private Enterprise Numbers of the vendors with definitions, used as the
+            /// vendor identifier of `Custom` parameters and messages
+            pub mod vendors {
+                #(#vendors)*
+            }
+
+            /// Lookups between the type numbers and names of messages and parameters
+            pub mod names {
+                #names
+            }
+
+            /// The encoded sizes and layout of fields, used to skip over and dissect parameters
+            pub(crate) mod layout {
+                #layout
+            }
+
+            #[cfg(test)]
+            #[allow(bad_style, unused_imports)]
+            mod roundtrip_tests {
+                use super::{*, messages::*, parameters::*, enumerations::*, choices::*};
+                #roundtrip_tests
+            }
         };
 
         write!(f, "{}", body)?;
@@ -55,15 +133,23 @@ impl std::fmt::Display for GeneratedCode {
     }
 }
 
-pub fn generate(definitions: Vec<Definition>) -> GeneratedCode {
+pub fn generate(definitions: Vec<Definition>, options: &Options) -> GeneratedCode {
     let mut message_names = vec![];
+    let mut message_type_names = vec![];
+    let mut message_ids = vec![];
     let mut message_matches = vec![];
+    let mut status_messages = vec![];
     for d in &definitions {
         match d {
-            Definition::Message { id, ident, .. } => {
+            Definition::Message { id, ident, fields } => {
                 message_names.push(ident);
+                message_ids.push(id);
+                message_type_names.push(ident.to_string().to_shouty_snake_case());
+                if status_field(fields).is_some() {
+                    status_messages.push(ident);
+                }
                 message_matches.push(quote! {
-                    #id => Ok(Self::#ident(#ident::decode(payload)?.0))
+                    #id => Ok(Self::#ident(#ident::decode_from(decoder)?))
                 });
             }
             _ => (),
@@ -79,6 +165,21 @@ pub fn generate(definitions: Vec<Definition>) -> GeneratedCode {
 
         impl Message {
             pub fn decode(message_id: u32, payload: &[u8]) -> crate::Result<Message> {
+                Ok(Self::decode_partial(message_id, payload)?.0)
+            }
+
+            /// Decodes a message, returning it along with any bytes that follow it in `payload`
+            pub fn decode_partial(
+                message_id: u32,
+                payload: &[u8],
+            ) -> crate::Result<(Message, &[u8])> {
+                let mut decoder = Decoder::new(payload);
+                let message = Self::decode_from(message_id, &mut decoder)?;
+                Ok((message, decoder.bytes))
+            }
+
+            /// Decodes a message from `decoder`, leaving any data that follows it
+            pub fn decode_from(message_id: u32, decoder: &mut Decoder) -> crate::Result<Message> {
                 match message_id as u16 {
                     #(#message_matches,)*
                     _ => Err(crate::Error::UnknownMessageId(message_id))
@@ -91,11 +192,53 @@ pub fn generate(definitions: Vec<Definition>) -> GeneratedCode {
                 }
             }
 
+            /// Encodes the message, returning an error if a value does not fit in its encoded field
+            pub fn encode_checked(&self, buffer: &mut Vec<u8>) -> crate::Result<usize> {
+                match self {
+                    #(Self::#message_names(msg) => msg.encode_checked(buffer),)*
+                }
+            }
+
+            /// Encodes the message into a fixed size buffer, returning the number of bytes written
+            pub fn encode_to_slice(&self, buffer: &mut [u8]) -> crate::Result<usize> {
+                match self {
+                    #(Self::#message_names(msg) => msg.encode_to_slice(buffer),)*
+                }
+            }
+
             pub fn message_type(&self) -> u16 {
                 match self {
                     #(Self::#message_names(_) => #message_names::ID,)*
                 }
             }
+
+            /// Returns whether `message_type` is the type num of a known message
+            pub fn is_known_type(message_type: u16) -> bool {
+                matches!(message_type, #(#message_ids)|*)
+            }
+
+            /// Returns the name of the message type in the LLRP specification, e.g. `ADD_ROSPEC`
+            pub fn name(&self) -> &'static str {
+                match self {
+                    #(Self::#message_names(_) => #message_type_names,)*
+                }
+            }
+
+            /// Returns the status reported by the reader if this is a response message
+            pub fn status(&self) -> Option<&LLRPStatus> {
+                match self {
+                    #(Self::#status_messages(msg) => Some(crate::LLRPResponse::status(msg)),)*
+                    _ => None,
+                }
+            }
+
+            /// Checks that the message contains all the parameters required by the LLRP
+            /// specification
+            pub fn validate(&self) -> crate::Result<()> {
+                match self {
+                    #(Self::#message_names(msg) => msg.validate(),)*
+                }
+            }
         }
 
         #(
@@ -107,42 +250,136 @@ pub fn generate(definitions: Vec<Definition>) -> GeneratedCode {
         )*
     };
 
+    let exchanges = define_exchanges(&definitions);
+
+    let visitor = crate::visit::generate(&definitions);
+    let diff = crate::diff::generate(&definitions);
+    let names = crate::names::generate(&definitions);
+    let layout = crate::layout::generate(&definitions);
+    let roundtrip_tests = crate::roundtrip::generate(&definitions);
+
     let mut messages = vec![];
     let mut parameters = vec![];
     let mut enumerations = vec![];
     let mut choices = vec![];
+    let mut namespaces = vec![];
+    let mut vendors = vec![];
+
+    let tlv_ids: HashMap<String, u16> = definitions
+        .iter()
+        .filter_map(|d| match d {
+            Definition::Parameter { id, ident, .. } => Some((ident.to_string(), *id)),
+            _ => None,
+        })
+        .collect();
 
     for d in definitions {
         match d {
             Definition::Message { id, ident, fields } => {
-                messages.push(define_message(id, ident, &fields));
+                messages.push(define_message(id, ident, &fields, options));
             }
             Definition::Parameter { id, ident, fields } => {
-                parameters.push(define_parameter(id, ident, &fields));
+                parameters.push(define_parameter(id, ident, &fields, options));
             }
             Definition::TvParameter { id, ident, fields } => {
-                parameters.push(define_tv_parameter(id, ident, &fields));
+                parameters.push(define_tv_parameter(id, ident, &fields, options));
             }
             Definition::Enum { ident, variants } => {
                 enumerations.push(define_enum(ident, &variants));
             }
             Definition::Choice { ident, choices: entries } => {
-                choices.push(define_choice(ident, &entries));
+                choices.push(define_choice(ident, &entries, &tlv_ids));
+            }
+            Definition::Namespace { prefix, uri } => {
+                let ident = Ident::new(&prefix.to_shouty_snake_case(), Span::call_site());
+                namespaces.push(quote!(pub const #ident: &str = #uri;));
+            }
+            Definition::Vendor { name, id } => {
+                let ident = Ident::new(&name.to_shouty_snake_case(), Span::call_site());
+                vendors.push(quote!(pub const #ident: u32 = #id;));
             }
         }
     }
-    GeneratedCode { messages, message_enum, parameters, enumerations, choices }
+    messages.extend(exchanges);
+
+    GeneratedCode {
+        messages,
+        message_enum,
+        parameters,
+        enumerations,
+        choices,
+        visitor,
+        diff,
+        namespaces,
+        vendors,
+        names,
+        layout,
+        roundtrip_tests,
+    }
 }
 
-fn define_message(id: u16, ident: Ident, fields: &[Field]) -> TokenStream {
-    let field_defs = fields.iter().map(define_field);
+/// Pairs request messages with the response message that the reader replies with (`X` and
+/// `X_RESPONSE` in the definitions), and marks messages containing an `LLRPStatus` as responses
+fn define_exchanges(definitions: &[Definition]) -> Vec<TokenStream> {
+    let messages: Vec<_> = definitions
+        .iter()
+        .filter_map(|d| match d {
+            Definition::Message { ident, fields, .. } => Some((ident, fields)),
+            _ => None,
+        })
+        .collect();
+
+    let mut output = vec![];
+    for (ident, fields) in &messages {
+        let response_name = format!("{}Response", ident);
+        if let Some((response, _)) = messages.iter().find(|(x, _)| *x == &response_name) {
+            output.push(quote! {
+                impl crate::LLRPRequest for #ident {
+                    type Response = #response;
+                }
+            });
+        }
+
+        if let Some(field) = status_field(fields) {
+            let field_ident = &field.ident;
+            output.push(quote! {
+                impl crate::LLRPResponse for #ident {
+                    fn status(&self) -> &LLRPStatus {
+                        &self.#field_ident
+                    }
+                }
+            });
+        }
+    }
+
+    output
+}
+
+/// Returns the `LLRPStatus` field of a message, if it has one
+fn status_field(fields: &[Field]) -> Option<&Field> {
+    fields.iter().find(|field| match &field.ty {
+        Container::Raw(ty) => ty.to_string() == "LLRPStatus",
+        _ => false,
+    })
+}
+
+fn define_message(id: u16, ident: Ident, fields: &[Field], options: &Options) -> TokenStream {
+    let attrs = struct_attrs(options);
+    let field_defs = fields.iter().map(|field| define_field(field, options));
+    let accessors = define_accessors(&ident, fields, options);
+    let format_helpers = define_format_helpers(&ident, fields);
+    let validate = define_validate(&ident, fields);
     let field_names = fields.iter().map(|field| &field.ident);
 
     let decoder = Ident::new("decoder", Span::call_site());
-    let decode_fields = fields.iter().map(|field| {
+    let expected = expected_params(fields);
+    let decode_fields = fields.iter().enumerate().map(|(i, field)| {
+        let skip = skip_unexpected(fields, i, &decoder);
+        let require = require_field(&ident, field, &decoder);
         let ident = &field.ident;
         let decode = decode_field(field, &decoder);
-        quote!(let #ident = #decode?;)
+        let check = check_reserved(field, &decoder);
+        quote!(#skip #require let #ident = #decode?; #check)
     });
 
     let encoder = Ident::new("encoder", Span::call_site());
@@ -158,42 +395,54 @@ fn define_message(id: u16, ident: Ident, fields: &[Field]) -> TokenStream {
     quote! {
         #[derive(Debug, Clone, Eq, PartialEq)]
         #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        #attrs
         pub struct #ident {
             #(#field_defs,)*
         }
 
+        #accessors
+        #format_helpers
+
         impl crate::LLRPMessage for #ident {
             const ID: u16 = #id;
 
-            fn decode(data: &[u8]) -> crate::Result<(Self, &[u8])> {
-                let mut #decoder = Decoder::new(data);
+            fn decode_from(#decoder: &mut Decoder) -> crate::Result<Self> {
+                #expected
+                #decoder.parent = #id;
 
                 #(#decode_fields)*
 
-                let __result = #ident {
+                Ok(#ident {
                     #(#field_names,)*
-                };
-
-                Ok((__result, #decoder.bytes))
+                })
             }
 
-            fn encode(&self, buffer: &mut Vec<u8>) {
-                let mut #encoder = Encoder::new(buffer);
+            fn encode_with(&self, #encoder: &mut Encoder) {
                 #(#encode_fields)*
             }
+
+            #validate
         }
     }
 }
 
-fn define_parameter(id: u16, ident: Ident, fields: &[Field]) -> TokenStream {
-    let field_defs = fields.iter().map(define_field);
+fn define_parameter(id: u16, ident: Ident, fields: &[Field], options: &Options) -> TokenStream {
+    let attrs = struct_attrs(options);
+    let field_defs = fields.iter().map(|field| define_field(field, options));
+    let accessors = define_accessors(&ident, fields, options);
+    let format_helpers = define_format_helpers(&ident, fields);
+    let validate = define_validate(&ident, fields);
     let field_names = fields.iter().map(|field| &field.ident);
 
     let decoder = Ident::new("decoder", Span::call_site());
-    let decode_fields = fields.iter().map(|field| {
+    let expected = expected_params(fields);
+    let decode_fields = fields.iter().enumerate().map(|(i, field)| {
+        let skip = skip_unexpected(fields, i, &decoder);
+        let require = require_field(&ident, field, &decoder);
         let ident = &field.ident;
-        let encode = decode_field(field, &decoder);
-        quote!(let #ident = #encode?;)
+        let decode = decode_field(field, &decoder);
+        let check = check_reserved(field, &decoder);
+        quote!(#skip #require let #ident = #decode?; #check)
     });
 
     let encoder = Ident::new("encoder", Span::call_site());
@@ -209,17 +458,27 @@ fn define_parameter(id: u16, ident: Ident, fields: &[Field]) -> TokenStream {
     quote! {
         #[derive(Debug, Clone, Eq, PartialEq)]
         #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        #attrs
         pub struct #ident {
             #(#field_defs,)*
         }
 
+        #accessors
+        #format_helpers
+
         impl crate::TlvParameter for #ident {
             const ID: u16 = #id;
         }
 
         impl crate::LLRPValue for #ident {
             fn decode(decoder: &mut Decoder) -> crate::Result<Self> {
-                decoder.tlv_param(#id, |decoder| {
+                decoder.expect_tlv_type(#id)?;
+                Self::decode_type(decoder, #id)
+            }
+
+            fn decode_type(decoder: &mut Decoder, _: u16) -> crate::Result<Self> {
+                #expected
+                decoder.tlv_param_unchecked(#id, |decoder| {
                     #(#decode_fields)*
 
                     Ok(#ident {
@@ -234,6 +493,8 @@ fn define_parameter(id: u16, ident: Ident, fields: &[Field]) -> TokenStream {
                 });
             }
 
+            #validate
+
             fn can_decode_type(type_num: u16) -> bool {
                 type_num == #id
             }
@@ -241,15 +502,22 @@ fn define_parameter(id: u16, ident: Ident, fields: &[Field]) -> TokenStream {
     }
 }
 
-fn define_tv_parameter(id: u8, ident: Ident, fields: &[Field]) -> TokenStream {
-    if let [Field { ty, .. }] = fields {
+fn define_tv_parameter(id: u8, ident: Ident, fields: &[Field], options: &Options) -> TokenStream {
+    if let [field] = fields {
         // If there is only one field, then just use a typedef
-        return quote!(pub type #ident = #ty;);
+        let ty = &field.ty;
+        let doc = format_doc(field.format);
+        return quote!(#doc pub type #ident = #ty;);
     }
 
+    let attrs = struct_attrs(options);
+
     // Otherwise define a new struct
     let decoder = Ident::new("decoder", Span::call_site());
-    let field_defs = fields.iter().map(define_field);
+    let field_defs = fields.iter().map(|field| define_field(field, options));
+    let accessors = define_accessors(&ident, fields, options);
+    let format_helpers = define_format_helpers(&ident, fields);
+    let validate = define_validate(&ident, fields);
     let decode_fields = fields.iter().map(|field| {
         let ident = &field.ident;
         let decoded = decode_field(field, &decoder);
@@ -269,10 +537,14 @@ fn define_tv_parameter(id: u8, ident: Ident, fields: &[Field]) -> TokenStream {
     quote! {
         #[derive(Debug, Clone, Eq, PartialEq)]
         #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        #attrs
         pub struct #ident {
             #(#field_defs,)*
         }
 
+        #accessors
+        #format_helpers
+
         impl crate::LLRPValue for #ident {
             fn decode(decoder: &mut Decoder) -> crate::Result<Self> {
                 Ok(#ident {
@@ -284,6 +556,8 @@ fn define_tv_parameter(id: u8, ident: Ident, fields: &[Field]) -> TokenStream {
                 #(#encode_fields)*
             }
 
+            #validate
+
             fn can_decode_type(type_num: u16) -> bool {
                 type_num == #id as u16
             }
@@ -333,7 +607,7 @@ fn define_enum(ident: Ident, variants: &[EnumVariant]) -> TokenStream {
     }
 }
 
-fn define_choice(ident: Ident, choices: &[Field]) -> TokenStream {
+fn define_choice(ident: Ident, choices: &[Field], tlv_ids: &HashMap<String, u16>) -> TokenStream {
     let ident = &ident;
 
     let mut tv_variants = vec![];
@@ -342,6 +616,8 @@ fn define_choice(ident: Ident, choices: &[Field]) -> TokenStream {
     let mut encode_tv_params = vec![];
 
     let mut tlv_variants = vec![];
+    let mut tlv_variant_ids = vec![];
+    let mut accessors = vec![];
 
     for choice in choices {
         let ty = match &choice.ty {
@@ -363,8 +639,31 @@ fn define_choice(ident: Ident, choices: &[Field]) -> TokenStream {
                 tv_variants.push(ty);
                 tv_ids.push(tv_id_u16);
             }
-            _ => tlv_variants.push(ty),
+            _ => {
+                let id = tlv_ids.get(&ty.to_string()).expect("Unknown choice parameter");
+                tlv_variants.push(ty);
+                tlv_variant_ids.push(Literal::u16_suffixed(*id));
+            }
         }
+
+        let name = ty.to_string().to_snake_case();
+        let as_name = Ident::new(&format!("as_{}", name), Span::call_site());
+        let into_name = Ident::new(&format!("into_{}", name), Span::call_site());
+        accessors.push(quote! {
+            pub fn #as_name(&self) -> Option<&#ty> {
+                match self {
+                    Self::#ty(value) => Some(value),
+                    _ => None,
+                }
+            }
+
+            pub fn #into_name(self) -> Option<#ty> {
+                match self {
+                    Self::#ty(value) => Some(value),
+                    _ => None,
+                }
+            }
+        });
     }
 
     quote! {
@@ -377,19 +676,21 @@ fn define_choice(ident: Ident, choices: &[Field]) -> TokenStream {
 
         impl crate::LLRPValue for #ident {
             fn can_decode_type(type_num: u16) -> bool {
-                [#(#tlv_variants::ID,)* #(#tv_ids,)*].contains(&type_num)
+                matches!(type_num, #(#tlv_variant_ids)|* #(| #tv_ids)*)
             }
 
             fn decode(decoder: &mut Decoder) -> Result<Self> {
                 let type_num = decoder.peek_param_type()?.as_u16();
+                Self::decode_type(decoder, type_num)
+            }
+
+            fn decode_type(decoder: &mut Decoder, type_num: u16) -> Result<Self> {
                 match type_num {
                     #(#decode_tv_params,)*
 
-                    #(
-                        type_num if type_num == #tlv_variants::ID => {
-                            Ok(Self::#tlv_variants(decoder.read()?))
-                        },
-                    )*
+                    #(#tlv_variant_ids => {
+                        Ok(Self::#tlv_variants(crate::LLRPValue::decode_type(decoder, type_num)?))
+                    })*
                     _ => Err(crate::Error::InvalidType(type_num)),
                 }
             }
@@ -400,6 +701,26 @@ fn define_choice(ident: Ident, choices: &[Field]) -> TokenStream {
                     #(Self::#tlv_variants(value) => value.encode(encoder),)*
                 }
             }
+
+            fn validate(&self) -> Result<()> {
+                match self {
+                    #(Self::#tlv_variants(value) => value.validate(),)*
+                    #(Self::#tv_variants(value) => value.validate(),)*
+                }
+            }
+        }
+
+        #[allow(unreachable_patterns)]
+        impl #ident {
+            /// Returns the type num of the parameter stored in this choice
+            pub fn type_num(&self) -> u16 {
+                match self {
+                    #(Self::#tlv_variants(_) => #tlv_variants::ID,)*
+                    #(Self::#tv_variants(_) => #tv_ids,)*
+                }
+            }
+
+            #(#accessors)*
         }
 
         #(
@@ -408,14 +729,302 @@ fn define_choice(ident: Ident, choices: &[Field]) -> TokenStream {
                     #ident::#tlv_variants(value)
                 }
             }
+
+            impl std::convert::TryFrom<#ident> for #tlv_variants {
+                type Error = #ident;
+
+                #[allow(unreachable_patterns)]
+                fn try_from(value: #ident) -> std::result::Result<Self, #ident> {
+                    match value {
+                        #ident::#tlv_variants(value) => Ok(value),
+                        other => Err(other),
+                    }
+                }
+            }
         )*
     }
 }
 
-fn define_field(field: &Field) -> TokenStream {
+/// Generates a `validate` method that checks that all required sub-parameters are present, returns
+/// nothing if the struct has no sub-parameters to check.
+fn define_validate(ident: &Ident, fields: &[Field]) -> TokenStream {
+    use crate::repr::Encoding;
+
+    let checks: Vec<_> = fields
+        .iter()
+        .filter(|field| match field.encoding {
+            Encoding::TlvParameter | Encoding::TvParameter { .. } => true,
+            _ => false,
+        })
+        .map(|field| {
+            let field_ident = &field.ident;
+            match field.ty {
+                // Required repeated parameters are stored in a `Vec` so they may be missing
+                Container::Vec1(ref ty) => {
+                    let parent = ident.to_string();
+                    let missing = ty.to_string();
+                    quote! {
+                        if self.#field_ident.is_empty() {
+                            return Err(crate::Error::MissingParameter {
+                                parent: #parent,
+                                missing: #missing,
+                            });
+                        }
+                        self.#field_ident.validate()?;
+                    }
+                }
+                _ => quote!(self.#field_ident.validate()?;),
+            }
+        })
+        .collect();
+
+    if checks.is_empty() {
+        return quote!();
+    }
+
+    quote! {
+        fn validate(&self) -> crate::Result<()> {
+            #(#checks)*
+            Ok(())
+        }
+    }
+}
+
+/// Returns the additional attributes to add to generated structs
+fn struct_attrs(options: &Options) -> TokenStream {
+    match options.non_exhaustive {
+        true => quote!(#[non_exhaustive]),
+        false => quote!(),
+    }
+}
+
+fn define_field(field: &Field, options: &Options) -> TokenStream {
     let ident = &field.ident;
     let ty = &field.ty;
-    quote!(pub #ident: #ty)
+    let doc = format_doc(field.format);
+    match options.private_fields {
+        true => quote!(#doc pub(crate) #ident: #ty),
+        false => quote!(#doc pub #ident: #ty),
+    }
+}
+
+/// Generates a doc comment describing how a value with `format` should be presented
+fn format_doc(format: Option<Format>) -> TokenStream {
+    let doc = match format {
+        Some(Format::Hex) => "Formatted as hex",
+        Some(Format::Datetime) => "Microseconds since the Unix epoch (UTC)",
+        Some(Format::Utf8) => "UTF-8 text",
+        None => return quote!(),
+    };
+    quote!(#[doc = #doc])
+}
+
+/// Generates helper methods and a `Display` implementation for presenting the fields of a struct
+/// according to their format
+fn define_format_helpers(ident: &Ident, fields: &[Field]) -> TokenStream {
+    let helpers: Vec<_> = fields
+        .iter()
+        .filter_map(|field| {
+            let field_ident = &field.ident;
+            match field.format? {
+                Format::Hex => {
+                    let name = Ident::new(&format!("{}_hex", field_ident), Span::call_site());
+                    Some(quote! {
+                        /// Returns the value of the field formatted as hex
+                        pub fn #name(&self) -> String {
+                            crate::FormatHex::to_hex(&self.#field_ident)
+                        }
+                    })
+                }
+                Format::Datetime => {
+                    let name = Ident::new(&format!("{}_time", field_ident), Span::call_site());
+                    Some(quote! {
+                        /// Returns the value of the field as a `SystemTime`
+                        pub fn #name(&self) -> std::time::SystemTime {
+                            std::time::UNIX_EPOCH
+                                + std::time::Duration::from_micros(self.#field_ident)
+                        }
+                    })
+                }
+                Format::Utf8 => None,
+            }
+        })
+        .collect();
+
+    if fields.iter().all(|field| field.format.is_none()) {
+        return quote!();
+    }
+    let display = define_display(ident, fields);
+
+    quote! {
+        impl #ident {
+            #(#helpers)*
+        }
+
+        #display
+    }
+}
+
+/// Generates a `Display` implementation for a struct with formatted fields, which presents the
+/// formatted fields according to their format and the remaining fields as they are debug printed
+fn define_display(ident: &Ident, fields: &[Field]) -> TokenStream {
+    let name = ident.to_string();
+    let field_formats = fields.iter().enumerate().map(|(i, field)| {
+        let field_ident = &field.ident;
+        let separator = if i == 0 { " " } else { ", " };
+        let label = format!("{}{}: ", separator, field_ident.to_string().trim_end_matches('_'));
+        let value = match field.format {
+            Some(Format::Hex) => quote!(crate::FormatHex::to_hex(&self.#field_ident)),
+            Some(Format::Datetime) => quote!(crate::format_datetime(self.#field_ident)),
+            Some(Format::Utf8) => quote!(&self.#field_ident),
+            None => {
+                let format = format!("{}{{:?}}", label);
+                return quote!(write!(f, #format, self.#field_ident)?;);
+            }
+        };
+        let format = format!("{}{{}}", label);
+        quote!(write!(f, #format, #value)?;)
+    });
+
+    quote! {
+        impl std::fmt::Display for #ident {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str(#name)?;
+                f.write_str(" {")?;
+                #(#field_formats)*
+                f.write_str(" }")
+            }
+        }
+    }
+}
+
+/// Generates a constructor and accessor methods for each of the fields of a struct if enabled.
+///
+/// The constructor only takes the required fields, and leaves optional and repeated parameters
+/// empty, so that its signature does not change when a newer definition file adds an optional
+/// parameter.
+fn define_accessors(ident: &Ident, fields: &[Field], options: &Options) -> TokenStream {
+    if !options.accessors {
+        return quote!();
+    }
+
+    let (optional, required): (Vec<_>, Vec<_>) = fields.iter().partition(|field| {
+        matches!(field.ty, Container::Option(_) | Container::OptionBox(_) | Container::Vec(_))
+    });
+    let required_names: Vec<_> = required.iter().map(|field| &field.ident).collect();
+    let required_types: Vec<_> = required.iter().map(|field| &field.ty).collect();
+    let optional_values = optional.iter().map(|field| {
+        let ident = &field.ident;
+        match field.ty {
+            Container::Vec(_) => quote!(#ident: Vec::new()),
+            _ => quote!(#ident: None),
+        }
+    });
+
+    let methods = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let ty = &field.ty;
+        let name = ident.to_string();
+        let name = name.trim_end_matches('_');
+        let setter = Ident::new(&format!("set_{}", name), Span::call_site());
+        let with = Ident::new(&format!("with_{}", name), Span::call_site());
+
+        quote! {
+            pub fn #ident(&self) -> &#ty {
+                &self.#ident
+            }
+
+            pub fn #setter(&mut self, value: #ty) {
+                self.#ident = value;
+            }
+
+            pub fn #with(mut self, value: #ty) -> Self {
+                self.#ident = value;
+                self
+            }
+        }
+    });
+
+    quote! {
+        impl #ident {
+            /// Creates a value from its required fields, with no optional or repeated parameters
+            #[allow(clippy::new_without_default, clippy::too_many_arguments)]
+            pub fn new(#(#required_names: #required_types),*) -> Self {
+                #ident { #(#required_names,)* #(#optional_values),* }
+            }
+
+            #(#methods)*
+        }
+    }
+}
+
+/// Returns whether a field is a sub-parameter (rather than a value that is part of the fixed
+/// fields of its parent)
+fn is_param(field: &Field) -> bool {
+    use crate::repr::Encoding;
+
+    matches!(field.encoding, Encoding::TlvParameter | Encoding::TvParameter { .. })
+}
+
+/// Generates a table of checks for the type nums accepted by each sub-parameter field, which is
+/// used in lenient mode to skip parameters that none of the remaining fields accept
+fn expected_params(fields: &[Field]) -> TokenStream {
+    use crate::repr::Encoding;
+
+    if !fields.iter().any(is_param) {
+        return quote!();
+    }
+
+    let checks = fields.iter().filter(|field| is_param(field)).map(|field| match &field.encoding {
+        Encoding::TvParameter { tv_id } => {
+            let tv_id = *tv_id as u16;
+            quote!(|type_num| type_num == #tv_id)
+        }
+        _ => {
+            let ty = field.ty.inner();
+            quote!(<#ty as crate::LLRPValue>::can_decode_type)
+        }
+    });
+    quote!(const EXPECTED: &[fn(u16) -> bool] = &[#(#checks),*];)
+}
+
+/// Generates a call that skips unexpected parameters in lenient mode before the `index`th field is
+/// decoded, if it is a sub-parameter
+fn skip_unexpected(fields: &[Field], index: usize, decoder: &Ident) -> TokenStream {
+    if !is_param(&fields[index]) {
+        return quote!();
+    }
+    let offset = fields[..index].iter().filter(|field| is_param(field)).count();
+    quote!(#decoder.skip_unexpected(&EXPECTED[#offset..]);)
+}
+
+/// Generates a check that a reserved field is zero, which reports a warning in lenient mode
+fn check_reserved(field: &Field, decoder: &Ident) -> TokenStream {
+    let ident = &field.ident;
+    match ident == "reserved" {
+        true => quote!(#decoder.check_reserved(&#ident);),
+        false => quote!(),
+    }
+}
+
+/// Generates a check that a required sub-parameter is present before it is decoded
+fn require_field(parent: &Ident, field: &Field, decoder: &Ident) -> TokenStream {
+    use crate::repr::Encoding;
+
+    let ty = match &field.ty {
+        Container::Raw(ty) | Container::Box(ty) | Container::Vec1(ty) => ty,
+        _ => return quote!(),
+    };
+    let parent = parent.to_string();
+    let missing = ty.to_string();
+
+    match &field.encoding {
+        Encoding::TlvParameter => quote!(#decoder.require::<#ty>(#parent, #missing)?;),
+        Encoding::TvParameter { tv_id } => {
+            quote!(#decoder.require_tv(#tv_id, #parent, #missing)?;)
+        }
+        _ => quote!(),
+    }
 }
 
 fn decode_field(field: &Field, decoder: &Ident) -> TokenStream {
@@ -476,3 +1085,84 @@ fn encode_field(field: &Field, encoder: &Ident) -> TokenStream {
         Encoding::Manual { wrapper } => quote!(#encoder.write(&#wrapper::wrap(#ident))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+
+    use super::Options;
+
+    /// Generates the code for a single parameter definition
+    fn generate_parameter(definition: &str, options: &Options) -> String {
+        let xml = format!(
+            r#"<llrpdef xmlns="http://www.llrp.org/ltk/schema/core/encoding/binary/1.0">{}</llrpdef>"#,
+            definition
+        );
+        let definitions =
+            crate::repr::parse_definitions(crate::llrp_def::parse(xml.as_bytes()).unwrap());
+        super::generate(definitions, options).parameters[0].to_string()
+    }
+
+    /// Returns the signature of the generated constructor
+    fn constructor(code: &str) -> &str {
+        let start = code.find("pub fn new").unwrap();
+        code[start..][..code[start..].find('{').unwrap()].trim()
+    }
+
+    #[test]
+    fn constructor_takes_required_fields() {
+        let options = Options { non_exhaustive: true, accessors: true, ..Default::default() };
+        let v1 = generate_parameter(
+            r#"<parameterDefinition name="Config" typeNum="1000" required="false">
+                <field type="u16" name="Value" />
+                <parameter repeat="1" type="Inner" />
+            </parameterDefinition>"#,
+            &options,
+        );
+        // A newer version of the definition, with an optional and a repeated parameter added
+        let v2 = generate_parameter(
+            r#"<parameterDefinition name="Config" typeNum="1000" required="false">
+                <field type="u16" name="Value" />
+                <parameter repeat="1" type="Inner" />
+                <parameter repeat="0-1" type="Added" />
+                <parameter repeat="0-N" type="Repeated" />
+            </parameterDefinition>"#,
+            &options,
+        );
+
+        assert!(v2.contains(&quote!(#[non_exhaustive]).to_string()));
+        assert_eq!(
+            constructor(&v1),
+            quote!(pub fn new(value: u16, inner: Inner) -> Self).to_string()
+        );
+        assert_eq!(constructor(&v1), constructor(&v2));
+        assert!(v2.contains(&quote!(added: None, repeated: Vec::new()).to_string()));
+    }
+
+    #[test]
+    fn namespace_and_vendor_constants() {
+        let xml = br#"<llrpdef xmlns="http://www.llrp.org/ltk/schema/core/encoding/binary/1.0">
+            <namespaceDefinition prefix="Impinj" URI="http://developer.impinj.com/ltk/schema/encoding/xml/1.40" />
+            <vendorDefinition name="Impinj" vendorID="25882" />
+        </llrpdef>"#;
+        let definitions = crate::repr::parse_definitions(crate::llrp_def::parse(xml).unwrap());
+        let code = super::generate(definitions, &Default::default());
+
+        assert_eq!(code.namespaces.len(), 1);
+        assert_eq!(
+            code.namespaces[0].to_string(),
+            quote!(
+                pub const IMPINJ: &str = "http://developer.impinj.com/ltk/schema/encoding/xml/1.40";
+            )
+            .to_string()
+        );
+        assert_eq!(code.vendors.len(), 1);
+        assert_eq!(
+            code.vendors[0].to_string(),
+            quote!(
+                pub const IMPINJ: u32 = 25882u32;
+            )
+            .to_string()
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Code for generating structural comparisons between values of the same message or parameter type
+
+use std::collections::HashMap;
+
+use proc_macro2::{Ident, Literal, TokenStream};
+use quote::quote;
+
+use crate::repr::{type_kinds, Container, Definition, Encoding, Field, Kind};
+
+pub fn generate(definitions: &[Definition]) -> TokenStream {
+    let kinds = type_kinds(definitions);
+
+    let mut diff_impls = vec![];
+    for d in definitions {
+        match d {
+            Definition::Message { ident, fields, .. }
+            | Definition::Parameter { ident, fields, .. } => {
+                diff_impls.push(define_diff_struct(ident, fields, &kinds));
+            }
+            Definition::TvParameter { ident, fields, .. } => {
+                if let Kind::Struct = kinds[&ident.to_string()] {
+                    diff_impls.push(define_diff_struct(ident, fields, &kinds));
+                }
+            }
+            Definition::Choice { ident, choices } => {
+                diff_impls.push(define_diff_choice(ident, choices, &kinds));
+            }
+            Definition::Enum { .. } | Definition::Namespace { .. } | Definition::Vendor { .. } => {}
+        }
+    }
+
+    quote! {
+        /// A single difference found when comparing two values
+        #[derive(Debug, Clone, Eq, PartialEq)]
+        pub struct Difference {
+            /// The path to the field that differs, e.g. `antenna_configuration[0].rf_transmitter`
+            pub path: String,
+
+            /// The formatted value on the left-hand side, or `None` if the value was not present
+            pub left: Option<String>,
+
+            /// The formatted value on the right-hand side, or `None` if the value was not present
+            pub right: Option<String>,
+        }
+
+        /// Types that can be compared field by field
+        pub trait Diff {
+            /// Appends all differences between `self` and `other` to `output`, with the paths of
+            /// each difference prefixed by `path`.
+            fn diff_into(&self, other: &Self, path: &str, output: &mut Vec<Difference>);
+
+            /// Returns all differences between `self` and `other`
+            fn diff(&self, other: &Self) -> Vec<Difference> {
+                let mut output = vec![];
+                self.diff_into(other, "", &mut output);
+                output
+            }
+        }
+
+        impl<T: Diff> Diff for Box<T> {
+            fn diff_into(&self, other: &Self, path: &str, output: &mut Vec<Difference>) {
+                self.as_ref().diff_into(other.as_ref(), path, output)
+            }
+        }
+
+        pub(crate) fn join_path(path: &str, field: &str) -> String {
+            match path {
+                "" => field.into(),
+                _ => format!("{}.{}", path, field),
+            }
+        }
+
+        pub(crate) fn diff_value<T>(left: &T, right: &T, path: String, output: &mut Vec<Difference>)
+        where
+            T: PartialEq + std::fmt::Debug,
+        {
+            if left != right {
+                output.push(Difference {
+                    path,
+                    left: Some(format!("{:?}", left)),
+                    right: Some(format!("{:?}", right)),
+                });
+            }
+        }
+
+        pub(crate) fn diff_option<T>(
+            left: Option<&T>,
+            right: Option<&T>,
+            path: String,
+            output: &mut Vec<Difference>,
+        )
+        where
+            T: Diff + std::fmt::Debug,
+        {
+            match (left, right) {
+                (Some(left), Some(right)) => left.diff_into(right, &path, output),
+                (None, None) => {}
+                (left, right) => output.push(Difference {
+                    path,
+                    left: left.map(|x| format!("{:?}", x)),
+                    right: right.map(|x| format!("{:?}", x)),
+                }),
+            }
+        }
+
+        pub(crate) fn diff_slice<T>(left: &[T], right: &[T], path: &str, output: &mut Vec<Difference>)
+        where
+            T: Diff + std::fmt::Debug,
+        {
+            for i in 0..left.len().max(right.len()) {
+                let path = format!("{}[{}]", path, i);
+                diff_option(left.get(i), right.get(i), path, output);
+            }
+        }
+
+        #(#diff_impls)*
+    }
+}
+
+fn define_diff_struct(
+    ident: &Ident,
+    fields: &[Field],
+    kinds: &HashMap<String, Kind>,
+) -> TokenStream {
+    let diff_fields = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let name = Literal::string(&ident.to_string());
+        let path = quote!(join_path(path, #name));
+
+        let is_parameter = match field.encoding {
+            Encoding::TlvParameter | Encoding::TvParameter { .. } => {
+                match kinds.get(&field.ty.inner().to_string()) {
+                    Some(Kind::Struct) | Some(Kind::Choice) => true,
+                    _ => false,
+                }
+            }
+            _ => false,
+        };
+
+        if !is_parameter {
+            return quote!(diff_value(&self.#ident, &other.#ident, #path, output););
+        }
+
+        match &field.ty {
+            Container::Raw(_) | Container::Box(_) => {
+                quote!(self.#ident.diff_into(&other.#ident, &#path, output);)
+            }
+            Container::Option(_) | Container::OptionBox(_) => {
+                quote!(diff_option(self.#ident.as_ref(), other.#ident.as_ref(), #path, output);)
+            }
+            Container::Vec(_) | Container::Vec1(_) => {
+                quote!(diff_slice(&self.#ident, &other.#ident, &#path, output);)
+            }
+        }
+    });
+
+    quote! {
+        impl Diff for #ident {
+            fn diff_into(&self, other: &Self, path: &str, output: &mut Vec<Difference>) {
+                if self != other {
+                    #(#diff_fields)*
+                }
+            }
+        }
+    }
+}
+
+fn define_diff_choice(
+    ident: &Ident,
+    choices: &[Field],
+    kinds: &HashMap<String, Kind>,
+) -> TokenStream {
+    let diff_variants = choices.iter().map(|choice| {
+        let ty = choice.ty.inner();
+        let diff = match kinds.get(&ty.to_string()) {
+            Some(Kind::Struct) => quote!(left.diff_into(right, path, output)),
+            _ => quote!(diff_value(left, right, path.into(), output)),
+        };
+        quote!((Self::#ty(left), Self::#ty(right)) => #diff)
+    });
+
+    // Values containing different variants are reported as a single difference (for choices with
+    // only a single variant this case cannot occur).
+    let different_variants = match choices.len() {
+        1 => quote!(),
+        _ => quote!(_ => diff_value(self, other, path.into(), output),),
+    };
+
+    quote! {
+        impl Diff for #ident {
+            fn diff_into(&self, other: &Self, path: &str, output: &mut Vec<Difference>) {
+                match (self, other) {
+                    #(#diff_variants,)*
+                    #different_variants
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Code for generating the encoded sizes of fixed size fields, used to skip over parameters
+//! without decoding them, and tables describing the fields of each message and parameter, used to
+//! dissect messages byte by byte
+
+use std::collections::HashMap;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::repr::{Definition, Encoding, EnumVariant, Field, Format};
+
+pub fn generate(definitions: &[Definition]) -> TokenStream {
+    let mut message_ids = vec![];
+    let mut message_lens = vec![];
+    let mut param_ids = vec![];
+    let mut param_lens = vec![];
+    let mut tv_ids = vec![];
+    let mut tv_lens = vec![];
+
+    let enums: HashMap<String, &[EnumVariant]> = definitions
+        .iter()
+        .filter_map(|d| match d {
+            Definition::Enum { ident, variants } => Some((ident.to_string(), &variants[..])),
+            _ => None,
+        })
+        .collect();
+    let mut message_tables = vec![];
+    let mut param_tables = vec![];
+    let mut tv_tables = vec![];
+
+    for d in definitions {
+        match d {
+            Definition::Message { id, ident, fields } => {
+                message_tables.push((*id, field_table(&ident.to_string(), fields, &enums)))
+            }
+            Definition::Parameter { id, ident, fields } => {
+                param_tables.push((*id, field_table(&ident.to_string(), fields, &enums)))
+            }
+            Definition::TvParameter { id, ident, fields } => {
+                tv_tables.push((*id, field_table(&ident.to_string(), fields, &enums)))
+            }
+            _ => {}
+        }
+    }
+    let (message_table_ids, message_tables): (Vec<_>, Vec<_>) = message_tables.into_iter().unzip();
+    let (param_table_ids, param_tables): (Vec<_>, Vec<_>) = param_tables.into_iter().unzip();
+    let (tv_table_ids, tv_tables): (Vec<_>, Vec<_>) = tv_tables.into_iter().unzip();
+
+    for d in definitions {
+        match d {
+            Definition::Message { id, fields, .. } => {
+                if let Some(len) = fields_len(fields) {
+                    message_ids.push(*id);
+                    message_lens.push(len);
+                }
+            }
+            Definition::Parameter { id, fields, .. } => {
+                if let Some(len) = fields_len(fields) {
+                    param_ids.push(*id);
+                    param_lens.push(len);
+                }
+            }
+            Definition::TvParameter { id, ident, fields } => {
+                let bits: Option<u32> = fields.iter().map(fixed_bits).sum();
+                match bits {
+                    Some(bits) if bits % 8 == 0 => {
+                        tv_ids.push(*id);
+                        tv_lens.push(1 + bits as usize / 8);
+                    }
+                    _ => panic!("TV parameter {} does not have a fixed size", ident),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    quote! {
+        /// Returns the length of the fields that come before the parameters of a message, if they
+        /// have a fixed size
+        pub fn message_fields_len(message_type: u16) -> Option<usize> {
+            match message_type {
+                #(#message_ids => Some(#message_lens),)*
+                _ => None,
+            }
+        }
+
+        /// Returns the length of the fields that come after the header and before the
+        /// sub-parameters of a TLV parameter, if they have a fixed size
+        pub fn parameter_fields_len(type_num: u16) -> Option<usize> {
+            match type_num {
+                #(#param_ids => Some(#param_lens),)*
+                _ => None,
+            }
+        }
+
+        /// Returns the encoded length of a TV parameter, including the type byte
+        pub fn tv_param_len(tv_id: u8) -> Option<usize> {
+            match tv_id {
+                #(#tv_ids => Some(#tv_lens),)*
+                _ => None,
+            }
+        }
+
+        /// How a field is encoded
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum FieldKind {
+            /// An unsigned value of a fixed number of bits, not necessarily byte aligned
+            Bits(u8),
+
+            /// A signed value of a fixed number of bits
+            Signed(u8),
+
+            /// A 96-bit value
+            U96,
+
+            /// A bit count followed by the bits (`u1v`)
+            BitArray,
+
+            /// A byte count followed by a UTF-8 string (`utf8v`)
+            Utf8,
+
+            /// An item count followed by items of a number of bits each (`u8v`, `u16v`, ...)
+            Array(u8),
+
+            /// The remaining bytes of the parameter or message
+            BytesToEnd,
+        }
+
+        /// A field that comes before the sub-parameters of a message or parameter
+        #[derive(Debug, Clone, Copy)]
+        pub struct FieldLayout {
+            pub name: &'static str,
+            pub kind: FieldKind,
+
+            /// Whether the value is better presented in hex
+            pub hex: bool,
+
+            /// The variants of the enumeration the field holds, if any
+            pub variants: &'static [(u16, &'static str)],
+        }
+
+        /// Returns the fields that come before the parameters of a message
+        pub fn message_fields(message_type: u16) -> Option<&'static [FieldLayout]> {
+            match message_type {
+                #(#message_table_ids => Some(&#message_tables),)*
+                _ => None,
+            }
+        }
+
+        /// Returns the fields that come before the sub-parameters of a TLV parameter
+        pub fn parameter_fields(type_num: u16) -> Option<&'static [FieldLayout]> {
+            match type_num {
+                #(#param_table_ids => Some(&#param_tables),)*
+                _ => None,
+            }
+        }
+
+        /// Returns the fields of a TV parameter
+        pub fn tv_fields(tv_id: u8) -> Option<&'static [FieldLayout]> {
+            match tv_id {
+                #(#tv_table_ids => Some(&#tv_tables),)*
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Returns an array of the `FieldLayout`s of the fields before the first parameter
+fn field_table(
+    name: &str,
+    fields: &[Field],
+    enums: &HashMap<String, &[EnumVariant]>,
+) -> TokenStream {
+    let is_param = |field: &Field| {
+        matches!(field.encoding, Encoding::TlvParameter | Encoding::TvParameter { .. })
+    };
+
+    let num_fields = fields.iter().take_while(|field| !is_param(field)).count();
+    if fields[num_fields..].iter().any(|field| !is_param(field)) {
+        panic!("{} has fields after its parameters", name);
+    }
+
+    let layouts = fields[..num_fields].iter().map(|field| {
+        let name = field.ident.to_string();
+        let name = name.trim_start_matches('_');
+        let kind = field_kind(field);
+        let hex = matches!(field.format, Some(Format::Hex));
+
+        let variants = match &field.encoding {
+            Encoding::Enum { .. } => &enums[&field.ty.inner().to_string()][..],
+            _ => &[],
+        };
+        let values = variants.iter().map(|variant| variant.value);
+        let variant_names = variants.iter().map(|variant| variant.ident.to_string());
+
+        quote! {
+            FieldLayout {
+                name: #name,
+                kind: #kind,
+                hex: #hex,
+                variants: &[#((#values, #variant_names)),*],
+            }
+        }
+    });
+
+    quote!([#(#layouts),*])
+}
+
+fn field_kind(field: &Field) -> TokenStream {
+    match &field.encoding {
+        Encoding::Enum { inner } => field_kind(inner),
+        Encoding::ArrayOfT { inner } => {
+            let bits = fixed_bits(inner).expect("array items should have a fixed size") as u8;
+            quote!(FieldKind::Array(#bits))
+        }
+        Encoding::Manual { .. } => quote!(FieldKind::BytesToEnd),
+        _ => match field.ty.inner().to_string().as_str() {
+            "BitArray" => quote!(FieldKind::BitArray),
+            "LLRPString" => quote!(FieldKind::Utf8),
+            "[u8 ; 12]" => quote!(FieldKind::U96),
+            "i8" | "i16" | "i32" | "i64" => {
+                let bits = fixed_bits(field).unwrap() as u8;
+                quote!(FieldKind::Signed(#bits))
+            }
+            _ => match fixed_bits(field) {
+                Some(bits) => {
+                    let bits = bits as u8;
+                    quote!(FieldKind::Bits(#bits))
+                }
+                None => panic!("unsupported field type: {}", field.ty.inner()),
+            },
+        },
+    }
+}
+
+/// Returns the length in bytes of the fields before the first parameter, if all of the fields
+/// have a fixed size and no fields come after the parameters
+fn fields_len(fields: &[Field]) -> Option<usize> {
+    let is_param = |field: &Field| {
+        matches!(field.encoding, Encoding::TlvParameter | Encoding::TvParameter { .. })
+    };
+
+    let num_fields = fields.iter().take_while(|field| !is_param(field)).count();
+    if fields[num_fields..].iter().any(|field| !is_param(field)) {
+        return None;
+    }
+
+    let bits: u32 = fields[..num_fields].iter().map(fixed_bits).sum::<Option<u32>>()?;
+    if bits % 8 != 0 {
+        return None;
+    }
+    Some(bits as usize / 8)
+}
+
+/// Returns the number of bits used to encode a field, if it has a fixed size
+fn fixed_bits(field: &Field) -> Option<u32> {
+    match &field.encoding {
+        Encoding::RawBits { num_bits } => Some(*num_bits as u32),
+        Encoding::Enum { inner } => fixed_bits(inner),
+        Encoding::Primitive => match field.ty.inner().to_string().as_str() {
+            "u8" | "i8" => Some(8),
+            "u16" | "i16" => Some(16),
+            "u32" | "i32" => Some(32),
+            "u64" | "i64" => Some(64),
+            "[u8 ; 12]" => Some(96),
+            _ => None,
+        },
+        _ => None,
+    }
+}
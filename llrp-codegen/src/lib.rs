@@ -1,16 +1,71 @@
+mod check;
 mod codegen;
+mod diff;
+mod layout;
 mod llrp_def;
+mod names;
 mod repr;
+mod roundtrip;
+mod visit;
 
-pub use crate::{codegen::GeneratedCode, repr::Definition};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+pub use crate::{
+    check::check_definitions,
+    codegen::{GeneratedCode, Options},
+    repr::Definition,
+};
 
 const LLRP_DEF: &[u8] = include_bytes!("../llrp-1x1-def.xml");
 
+/// The sources of the code generator, which determine the generated code together with the
+/// definitions
+const SOURCES: &[&str] = &[
+    include_str!("../base/common.rs"),
+    include_str!("check.rs"),
+    include_str!("codegen.rs"),
+    include_str!("diff.rs"),
+    include_str!("layout.rs"),
+    include_str!("lib.rs"),
+    include_str!("llrp_def.rs"),
+    include_str!("names.rs"),
+    include_str!("repr.rs"),
+    include_str!("roundtrip.rs"),
+    include_str!("visit.rs"),
+];
+
 pub fn load_definitions() -> Vec<Definition> {
     let def = llrp_def::parse(LLRP_DEF).unwrap();
-    repr::parse_definitions(def)
+    let definitions = repr::parse_definitions(def);
+
+    let errors = check::check_definitions(&definitions);
+    if !errors.is_empty() {
+        panic!("Invalid LLRP definitions:\n{}", errors.join("\n"));
+    }
+
+    definitions
+}
+
+/// Returns a hash of the definition file and the version and sources of the code generator. Code
+/// generated with the same options is the same as long as the hash is.
+pub fn input_hash() -> u64 {
+    let mut hasher = DefaultHasher::new();
+    LLRP_DEF.hash(&mut hasher);
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    SOURCES.hash(&mut hasher);
+    hasher.finish()
 }
 
 pub fn generate_code(definitions: Vec<Definition>) -> GeneratedCode {
-    codegen::generate(definitions)
+    codegen::generate(definitions, &Options::default())
+}
+
+pub fn generate_code_with_options(
+    definitions: Vec<Definition>,
+    options: &Options,
+) -> GeneratedCode {
+    codegen::generate(definitions, options)
 }
@@ -22,7 +22,26 @@ pub enum Definition {
     Choice(ChoiceDefinition),
 
     #[serde(rename = "namespaceDefinition")]
-    Namespace(serde::de::IgnoredAny),
+    Namespace(NamespaceDefinition),
+
+    #[serde(rename = "vendorDefinition")]
+    Vendor(VendorDefinition),
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct NamespaceDefinition {
+    pub prefix: String,
+
+    #[serde(rename = "URI")]
+    pub uri: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct VendorDefinition {
+    pub name: String,
+
+    #[serde(rename = "vendorID")]
+    pub vendor_id: u32,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -0,0 +1,71 @@
+//! Code for generating lookup tables between the type numbers and names of messages and parameters
+
+use heck::ShoutySnakeCase;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::repr::Definition;
+
+pub fn generate(definitions: &[Definition]) -> TokenStream {
+    let mut messages = vec![];
+    let mut parameters = vec![];
+    for d in definitions {
+        match d {
+            Definition::Message { id, ident, .. } => {
+                messages.push((*id, ident.to_string().to_shouty_snake_case()));
+            }
+            Definition::Parameter { id, ident, .. } => parameters.push((*id, ident.to_string())),
+            Definition::TvParameter { id, ident, .. } => {
+                parameters.push((*id as u16, ident.to_string()))
+            }
+            _ => {}
+        }
+    }
+    messages.sort();
+    parameters.sort();
+
+    let (message_ids, message_names): (Vec<_>, Vec<_>) = messages.into_iter().unzip();
+    let (param_ids, param_names): (Vec<_>, Vec<_>) = parameters.into_iter().unzip();
+
+    quote! {
+        /// The type number and name of every message in the definitions, sorted by type number
+        pub static MESSAGES: &[(u16, &str)] = &[#((#message_ids, #message_names),)*];
+
+        /// The type number and name of every parameter (both TV and TLV) in the definitions,
+        /// sorted by type number
+        pub static PARAMETERS: &[(u16, &str)] = &[#((#param_ids, #param_names),)*];
+
+        /// Returns the name of the message with type number `type_num`, e.g. `ADD_ROSPEC`
+        pub fn message_name(type_num: u16) -> Option<&'static str> {
+            match type_num {
+                #(#message_ids => Some(#message_names),)*
+                _ => None,
+            }
+        }
+
+        /// Returns the type number of the message named `name`
+        pub fn message_type(name: &str) -> Option<u16> {
+            match name {
+                #(#message_names => Some(#message_ids),)*
+                _ => None,
+            }
+        }
+
+        /// Returns the name of the parameter with type number `type_num`, e.g. `ROSpec`. TV
+        /// parameters are identified by their TV type number (1 to 127).
+        pub fn parameter_name(type_num: u16) -> Option<&'static str> {
+            match type_num {
+                #(#param_ids => Some(#param_names),)*
+                _ => None,
+            }
+        }
+
+        /// Returns the type number of the parameter named `name`
+        pub fn parameter_type(name: &str) -> Option<u16> {
+            match name {
+                #(#param_names => Some(#param_ids),)*
+                _ => None,
+            }
+        }
+    }
+}
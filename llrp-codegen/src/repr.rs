@@ -17,6 +17,8 @@ pub enum Definition {
     TvParameter { id: u8, ident: Ident, fields: Vec<Field> },
     Enum { ident: Ident, variants: Vec<EnumVariant> },
     Choice { ident: Ident, choices: Vec<Field> },
+    Namespace { prefix: String, uri: String },
+    Vendor { name: String, id: u32 },
 }
 
 #[derive(Debug, Clone)]
@@ -53,6 +55,20 @@ pub enum Container {
     Vec1(TokenStream),
 }
 
+impl Container {
+    /// Returns the type of the value stored inside of the container
+    pub fn inner(&self) -> &TokenStream {
+        match self {
+            Container::Raw(ty)
+            | Container::Box(ty)
+            | Container::Option(ty)
+            | Container::OptionBox(ty)
+            | Container::Vec(ty)
+            | Container::Vec1(ty) => ty,
+        }
+    }
+}
+
 impl quote::ToTokens for Container {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         use quote::TokenStreamExt;
@@ -78,6 +94,28 @@ pub struct Field {
 
     /// Represents how the field is encoded
     pub encoding: Encoding,
+
+    /// How the value of the field should be presented to users
+    pub format: Option<Format>,
+}
+
+/// The `format` of a field in the definition file
+#[derive(Debug, Copy, Clone)]
+pub enum Format {
+    Hex,
+    Datetime,
+    Utf8,
+}
+
+impl Format {
+    fn parse(format: &str) -> Option<Format> {
+        match format {
+            "Hex" => Some(Format::Hex),
+            "Datetime" => Some(Format::Datetime),
+            "UTF8" => Some(Format::Utf8),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -96,6 +134,40 @@ pub struct EnumVariant {
     pub value: u16,
 }
 
+/// Describes how a named type is represented in the generated code
+#[derive(Debug, Copy, Clone)]
+pub enum Kind {
+    /// A parameter that is represented as a struct
+    Struct,
+
+    /// A TV parameter that is represented as a typedef of a primitive type
+    Alias,
+
+    /// A choice between multiple parameters
+    Choice,
+}
+
+/// Returns the kind of each parameter and choice type, keyed by the name of the type
+pub fn type_kinds(definitions: &[Definition]) -> HashMap<String, Kind> {
+    let mut kinds = HashMap::new();
+    for d in definitions {
+        match d {
+            Definition::Parameter { ident, .. } => {
+                kinds.insert(ident.to_string(), Kind::Struct);
+            }
+            Definition::TvParameter { ident, fields, .. } => {
+                let kind = if fields.len() == 1 { Kind::Alias } else { Kind::Struct };
+                kinds.insert(ident.to_string(), kind);
+            }
+            Definition::Choice { ident, .. } => {
+                kinds.insert(ident.to_string(), Kind::Choice);
+            }
+            _ => {}
+        }
+    }
+    kinds
+}
+
 pub fn parse_definitions(def: llrp_def::LLRPDef) -> Vec<Definition> {
     let mut definitions = vec![];
 
@@ -168,7 +240,13 @@ pub fn parse_definitions(def: llrp_def::LLRPDef) -> Vec<Definition> {
                 choices: parse_fields(&def.fields, &tv_params),
             },
 
-            llrp_def::Definition::Namespace(_) => continue,
+            llrp_def::Definition::Namespace(def) => {
+                Definition::Namespace { prefix: def.prefix.clone(), uri: def.uri.clone() }
+            }
+
+            llrp_def::Definition::Vendor(def) => {
+                Definition::Vendor { name: def.name.clone(), id: def.vendor_id }
+            }
         })
     }
 
@@ -187,7 +265,8 @@ fn parse_fields(fields: &[llrp_def::Field], tv_params: &HashMap<String, TvField>
                 map_field(type_, type_, *repeat, tv_params)
             }
 
-            llrp_def::Field::Field { type_, name, format: _, enumeration } => {
+            llrp_def::Field::Field { type_, name, format, enumeration } => {
+                let format = format.as_ref().and_then(|x| Format::parse(x));
                 match enumeration.as_ref() {
                     Some(enumeration) => {
                         let enum_ident = Ident::new(enumeration, Span::call_site());
@@ -198,9 +277,10 @@ fn parse_fields(fields: &[llrp_def::Field], tv_params: &HashMap<String, TvField>
                             _ => Container::Raw(quote!(#enum_ident)),
                         };
 
-                        Field { ident: field_ident(name), ty, encoding: Encoding::Enum { inner } }
+                        let encoding = Encoding::Enum { inner };
+                        Field { ident: field_ident(name), ty, encoding, format }
                     }
-                    None => map_field(name, type_, Repeat::One, &tv_params),
+                    None => Field { format, ..map_field(name, type_, Repeat::One, &tv_params) },
                 }
             }
 
@@ -246,7 +326,7 @@ fn type_of(type_name: &str) -> (TokenStream, Encoding) {
         "s32"   => ("i32",      Primitive),
         "s64"   => ("i64",      Primitive),
         "u1v"   => ("BitArray", Primitive),
-        "utf8v" => ("String",   Primitive),
+        "utf8v" => ("LLRPString", Primitive),
         "bytesToEnd" => ("Vec<u8>", Manual { wrapper: Ident::new("BytesToEnd", Span::call_site()) }),
 
         // Arrays of values
@@ -280,7 +360,7 @@ fn field_ident(name: &str) -> Ident {
 fn inner_field(type_name: &str) -> Box<Field> {
     let (ty, encoding) = type_of(type_name);
     let ident = Ident::new(&format!("__{}_item", type_name), Span::call_site());
-    Box::new(Field { ident, ty: Container::Raw(ty), encoding })
+    Box::new(Field { ident, ty: Container::Raw(ty), encoding, format: None })
 }
 
 fn map_field(
@@ -309,5 +389,5 @@ fn map_field(
         (Repeat::OneToN, _) => Container::Vec1(base_type),
     };
 
-    Field { ident, ty, encoding }
+    Field { ident, ty, encoding, format: None }
 }
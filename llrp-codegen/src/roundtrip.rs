@@ -0,0 +1,165 @@
+//! Code for generating round-trip tests that encode and decode a sample value of every message
+
+use heck::SnakeCase;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+use crate::repr::{Container, Definition, Field};
+
+pub fn generate(definitions: &[Definition]) -> TokenStream {
+    let mut sample_impls = vec![];
+    let mut tests = vec![];
+
+    for d in definitions {
+        match d {
+            Definition::Message { ident, fields, .. } => {
+                sample_impls.push(define_sample_struct(ident, fields));
+
+                let test_name = format!("roundtrip_{}", ident.to_string().to_snake_case());
+                let test_name = Ident::new(&test_name, Span::call_site());
+                tests.push(quote! {
+                    #[test]
+                    fn #test_name() {
+                        check_roundtrip(#ident::sample().into());
+                    }
+                });
+            }
+            Definition::Parameter { ident, fields, .. } => {
+                sample_impls.push(define_sample_struct(ident, fields));
+            }
+            Definition::TvParameter { id, ident, fields } => {
+                if fields.len() > 1 {
+                    sample_impls.push(define_sample_struct(ident, fields));
+                }
+
+                let test_name = format!("roundtrip_tv_{}", ident.to_string().to_snake_case());
+                let test_name = Ident::new(&test_name, Span::call_site());
+                tests.push(quote! {
+                    #[test]
+                    fn #test_name() {
+                        check_tv_roundtrip::<#ident>(#id);
+                    }
+                });
+            }
+            Definition::Enum { ident, variants } => {
+                let first = &variants[0].ident;
+                sample_impls.push(quote! {
+                    impl Sample for #ident {
+                        fn sample() -> Self {
+                            #ident::#first
+                        }
+                    }
+                });
+            }
+            Definition::Choice { ident, choices } => {
+                let first = choices[0].ty.inner();
+                sample_impls.push(quote! {
+                    impl Sample for #ident {
+                        fn sample() -> Self {
+                            #ident::#first(Sample::sample())
+                        }
+                    }
+                });
+            }
+            _ => {}
+        }
+    }
+
+    quote! {
+        /// Constructs a minimal value of a type, containing a single entry for each required
+        /// repeated parameter, and no optional parameters
+        trait Sample {
+            fn sample() -> Self;
+        }
+
+        macro_rules! impl_sample_default {
+            ($($ty:ty),*) => {
+                $(
+                    impl Sample for $ty {
+                        fn sample() -> Self {
+                            Default::default()
+                        }
+                    }
+                )*
+            };
+        }
+        impl_sample_default!(bool, u8, u16, u32, u64, i8, i16, i32, i64, [u8; 12]);
+
+        impl Sample for LLRPString {
+            fn sample() -> Self {
+                "".into()
+            }
+        }
+
+        impl Sample for BitArray {
+            fn sample() -> Self {
+                BitArray::from_bytes(vec![])
+            }
+        }
+
+        impl<T> Sample for Vec<T> {
+            fn sample() -> Self {
+                vec![]
+            }
+        }
+
+        impl<T> Sample for Option<T> {
+            fn sample() -> Self {
+                None
+            }
+        }
+
+        impl<T: Sample> Sample for Box<T> {
+            fn sample() -> Self {
+                Box::new(T::sample())
+            }
+        }
+
+        #(#sample_impls)*
+
+        fn check_roundtrip(message: Message) {
+            let binary = crate::BinaryMessage::from_dynamic_message(1, &message).unwrap();
+
+            let mut frame = vec![];
+            crate::write_message(&mut frame, binary).unwrap();
+
+            let decoded = crate::read_message(&frame[..]).unwrap().to_dynamic_message().unwrap();
+            assert_eq!(decoded, message);
+        }
+
+        fn check_tv_roundtrip<T>(tv_id: u8)
+        where
+            T: crate::LLRPValue + Sample + PartialEq + std::fmt::Debug,
+        {
+            let value = T::sample();
+
+            let mut buffer = vec![];
+            crate::Encoder::new(&mut buffer).write_tv(&value, tv_id);
+            assert_eq!(buffer[0], 0x80 | tv_id);
+
+            let mut decoder = crate::Decoder::new(&buffer);
+            assert_eq!(decoder.read_tv::<T>(tv_id).unwrap(), value);
+            decoder.validate_consumed().unwrap();
+        }
+
+        #(#tests)*
+    }
+}
+
+fn define_sample_struct(ident: &Ident, fields: &[Field]) -> TokenStream {
+    let field_samples = fields.iter().map(|field| {
+        let field_ident = &field.ident;
+        match field.ty {
+            Container::Vec1(_) => quote!(#field_ident: vec![Sample::sample()]),
+            _ => quote!(#field_ident: Sample::sample()),
+        }
+    });
+
+    quote! {
+        impl Sample for #ident {
+            fn sample() -> Self {
+                #ident { #(#field_samples,)* }
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Code for generating the `Visitor` traits used to traverse the parameter tree of a message
+
+use std::collections::HashMap;
+
+use heck::SnakeCase;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+use crate::repr::{type_kinds, Container, Definition, Encoding, Field, Kind};
+
+pub fn generate(definitions: &[Definition]) -> TokenStream {
+    let kinds = type_kinds(definitions);
+
+    let mut visit_fns = vec![];
+    let mut visit_mut_fns = vec![];
+    let mut find_fns = vec![];
+    let mut walk_impls = vec![];
+    let mut message_names = vec![];
+
+    for d in definitions {
+        match d {
+            Definition::Message { ident, fields, .. } => {
+                message_names.push(ident);
+                walk_impls.push(define_walk_struct(ident, fields, &kinds));
+            }
+            Definition::Parameter { ident, fields, .. }
+            | Definition::TvParameter { ident, fields, .. } => {
+                let (visit, visit_mut) = visit_fn_names(ident);
+                match kinds[&ident.to_string()] {
+                    Kind::Alias => {
+                        visit_fns.push(quote!(fn #visit(&mut self, value: &'ast #ident) {}));
+                        visit_mut_fns.push(quote!(fn #visit_mut(&mut self, value: &mut #ident) {}));
+                    }
+                    _ => {
+                        visit_fns.push(quote! {
+                            fn #visit(&mut self, value: &'ast #ident) {
+                                value.walk(self)
+                            }
+                        });
+                        find_fns.push(quote! {
+                            fn #visit(&mut self, value: &'ast #ident) {
+                                if let Some(value) = (value as &dyn Any).downcast_ref::<T>() {
+                                    self.found.push(value);
+                                }
+                                value.walk(self)
+                            }
+                        });
+                        visit_mut_fns.push(quote! {
+                            fn #visit_mut(&mut self, value: &mut #ident) {
+                                value.walk_mut(self)
+                            }
+                        });
+                        walk_impls.push(define_walk_struct(ident, fields, &kinds));
+                    }
+                }
+            }
+            Definition::Choice { ident, choices } => {
+                walk_impls.push(define_walk_choice(ident, choices, &kinds));
+            }
+            Definition::Enum { .. } | Definition::Namespace { .. } | Definition::Vendor { .. } => {}
+        }
+    }
+
+    quote! {
+        /// A visitor over the parameters contained within a message.
+        ///
+        /// Every method has a default implementation that continues the traversal into the
+        /// sub-parameters of the visited value, so implementations only need to override the
+        /// methods for the parameters they are interested in.
+        pub trait Visitor<'ast> {
+            #(#visit_fns)*
+        }
+
+        /// A visitor over the parameters contained within a message that is able to modify them.
+        pub trait VisitorMut {
+            #(#visit_mut_fns)*
+        }
+
+        /// Types that contain parameters that can be traversed by a visitor
+        pub trait Walk {
+            /// Calls the matching `visit_*` method of `visitor` for each sub-parameter of `self`
+            fn walk<'ast, V: Visitor<'ast> + ?Sized>(&'ast self, visitor: &mut V);
+
+            /// Calls the matching `visit_*_mut` method of `visitor` for each sub-parameter of `self`
+            fn walk_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V);
+        }
+
+        #(#walk_impls)*
+
+        struct FindAll<'ast, T> {
+            found: Vec<&'ast T>,
+        }
+
+        impl<'ast, T: 'static> Visitor<'ast> for FindAll<'ast, T> {
+            #(#find_fns)*
+        }
+
+        /// Returns every parameter of type `T` contained within `value`, regardless of how deeply
+        /// it is nested
+        pub fn find_all<T>(value: &impl Walk) -> Vec<&T>
+        where
+            T: crate::TlvParameter + 'static,
+        {
+            let mut finder = FindAll { found: vec![] };
+            value.walk(&mut finder);
+            finder.found
+        }
+
+        impl Walk for Message {
+            fn walk<'ast, V: Visitor<'ast> + ?Sized>(&'ast self, visitor: &mut V) {
+                match self {
+                    #(Self::#message_names(msg) => msg.walk(visitor),)*
+                }
+            }
+
+            fn walk_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+                match self {
+                    #(Self::#message_names(msg) => msg.walk_mut(visitor),)*
+                }
+            }
+        }
+    }
+}
+
+fn visit_fn_names(ident: &Ident) -> (Ident, Ident) {
+    let name = ident.to_string().to_snake_case();
+    (
+        Ident::new(&format!("visit_{}", name), Span::call_site()),
+        Ident::new(&format!("visit_{}_mut", name), Span::call_site()),
+    )
+}
+
+fn define_walk_struct(
+    ident: &Ident,
+    fields: &[Field],
+    kinds: &HashMap<String, Kind>,
+) -> TokenStream {
+    let walk_fields = fields.iter().filter_map(|field| walk_field(field, kinds, false));
+    let walk_mut_fields = fields.iter().filter_map(|field| walk_field(field, kinds, true));
+
+    quote! {
+        impl Walk for #ident {
+            fn walk<'ast, V: Visitor<'ast> + ?Sized>(&'ast self, visitor: &mut V) {
+                #(#walk_fields)*
+            }
+
+            fn walk_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+                #(#walk_mut_fields)*
+            }
+        }
+    }
+}
+
+fn define_walk_choice(
+    ident: &Ident,
+    choices: &[Field],
+    kinds: &HashMap<String, Kind>,
+) -> TokenStream {
+    let mut walk_variants = vec![];
+    let mut walk_mut_variants = vec![];
+
+    for choice in choices {
+        let ty = choice.ty.inner();
+        let value = Ident::new("value", Span::call_site());
+        walk_variants.push(visit_value(ty, &value, kinds, false));
+        walk_mut_variants.push(visit_value(ty, &value, kinds, true));
+    }
+
+    let variants = choices.iter().map(|choice| choice.ty.inner());
+    let variants_mut = variants.clone();
+
+    quote! {
+        impl Walk for #ident {
+            fn walk<'ast, V: Visitor<'ast> + ?Sized>(&'ast self, visitor: &mut V) {
+                match self {
+                    #(Self::#variants(value) => { #walk_variants })*
+                }
+            }
+
+            fn walk_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+                match self {
+                    #(Self::#variants_mut(value) => { #walk_mut_variants })*
+                }
+            }
+        }
+    }
+}
+
+/// Generates the code for visiting a single field of a struct, returns `None` if the field does
+/// not contain any parameters.
+fn walk_field(field: &Field, kinds: &HashMap<String, Kind>, mutable: bool) -> Option<TokenStream> {
+    match field.encoding {
+        Encoding::TlvParameter | Encoding::TvParameter { .. } => {}
+        _ => return None,
+    }
+
+    let ty = field.ty.inner();
+    if !kinds.contains_key(&ty.to_string()) {
+        return None;
+    }
+
+    let ident = &field.ident;
+    let reference = match mutable {
+        true => quote!(&mut self.#ident),
+        false => quote!(&self.#ident),
+    };
+
+    let value = Ident::new("value", Span::call_site());
+    let visit = visit_value(ty, &value, kinds, mutable);
+
+    Some(match &field.ty {
+        Container::Raw(_) | Container::Box(_) => quote! {
+            let #value = #reference;
+            #visit
+        },
+        // Note: the trailing semicolon prevents consecutive `if let` statements in the unformatted
+        // output from being mistaken for a missing `else`
+        Container::Option(_) | Container::OptionBox(_) => quote! {
+            if let Some(#value) = #reference {
+                #visit
+            };
+        },
+        Container::Vec(_) | Container::Vec1(_) => quote! {
+            for #value in #reference {
+                #visit
+            }
+        },
+    })
+}
+
+fn visit_value(
+    ty: &TokenStream,
+    value: &Ident,
+    kinds: &HashMap<String, Kind>,
+    mutable: bool,
+) -> TokenStream {
+    let ident = Ident::new(&ty.to_string(), Span::call_site());
+    let (visit, visit_mut) = visit_fn_names(&ident);
+
+    match (kinds[&ident.to_string()], mutable) {
+        (Kind::Choice, false) => quote!(#value.walk(visitor);),
+        (Kind::Choice, true) => quote!(#value.walk_mut(visitor);),
+        (_, false) => quote!(visitor.#visit(#value);),
+        (_, true) => quote!(visitor.#visit_mut(#value);),
+    }
+}
@@ -0,0 +1,93 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use llrp::{
+    choices, enumerations, messages, messages::RoAccessReport, parameters, report, write_message,
+    BinaryMessage, LLRPMessage,
+};
+
+mod fixtures;
+
+fn frame_parsing(c: &mut Criterion) {
+    let mut stream = vec![];
+    for id in 0..100 {
+        stream.extend(fixtures::frame(id, fixtures::ro_access_report(10)));
+    }
+
+    let mut group = c.benchmark_group("frame_parsing");
+    group.throughput(Throughput::Bytes(stream.len() as u64));
+    group.bench_function("read_message", |b| {
+        b.iter(|| {
+            let mut reader = &stream[..];
+            while !reader.is_empty() {
+                llrp::read_message(&mut reader).unwrap();
+            }
+        })
+    });
+    group.bench_function("read_message_into", |b| {
+        let mut buffer = vec![];
+        b.iter(|| {
+            let mut reader = &stream[..];
+            while !reader.is_empty() {
+                llrp::read_message_into(&mut reader, &mut buffer).unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+fn ro_access_report_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ro_access_report_decode");
+    for &num_tags in &[1, 100, 1000] {
+        let raw = BinaryMessage::from_message(1, fixtures::ro_access_report(num_tags)).unwrap();
+        group.throughput(Throughput::Elements(num_tags as u64));
+
+        group.bench_with_input(BenchmarkId::new("to_message", num_tags), &raw, |b, raw| {
+            b.iter(|| raw.to_message::<RoAccessReport>().unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("to_dynamic_message", num_tags), &raw, |b, raw| {
+            b.iter(|| raw.to_dynamic_message().unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("select_parameters", num_tags), &raw, |b, raw| {
+            // EPC_96 and AntennaID
+            b.iter(|| raw.select_parameters(&[13, 1]).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn rospec_encode(c: &mut Criterion) {
+    let message = fixtures::add_rospec(1, 4);
+
+    let mut group = c.benchmark_group("rospec_encode");
+    group.bench_function("encode", |b| {
+        let mut buffer = Vec::with_capacity(1024);
+        b.iter(|| {
+            buffer.clear();
+            message.encode(&mut buffer);
+        })
+    });
+    group.bench_function("from_message", |b| {
+        b.iter(|| BinaryMessage::from_message(1, message.clone()).unwrap())
+    });
+    group.finish();
+}
+
+fn epc_hex(c: &mut Criterion) {
+    let epcs = fixtures::epcs(1000);
+    let hex: Vec<_> = epcs.iter().map(|epc| epc.to_hex()).collect();
+
+    let mut group = c.benchmark_group("epc_hex");
+    group.throughput(Throughput::Elements(epcs.len() as u64));
+    group.bench_function("to_hex", |b| {
+        b.iter(|| epcs.iter().map(|epc| epc.to_hex()).collect::<Vec<_>>())
+    });
+    group.bench_function("from_hex", |b| {
+        b.iter(|| {
+            hex.iter().map(|hex| llrp::report::Epc::from_hex(hex).unwrap()).collect::<Vec<_>>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, frame_parsing, ro_access_report_decode, rospec_encode, epc_hex);
+criterion_main!(benches);
@@ -0,0 +1,150 @@
+//! Generators of representative messages, used by the benchmarks. The unit tests also include
+//! this module with `#[path]`, so everything is imported through `super`.
+
+use super::{
+    choices::*, enumerations::*, messages::*, parameters::*, report::Epc, write_message,
+    BinaryMessage, LLRPMessage,
+};
+
+/// Returns the report of a single tag, with the fields enabled by `ro_report_spec`
+pub fn tag_report_data(index: u32) -> TagReportData {
+    let mut epc = [0; 12];
+    epc[..4].copy_from_slice(&[0xe2, 0x80, 0x11, 0x60]);
+    epc[8..].copy_from_slice(&index.to_be_bytes());
+
+    TagReportData {
+        epc_parameter: EPCParameter::EPC_96(epc),
+        ro_spec_id: Some(1),
+        spec_index: None,
+        inventory_parameter_spec_id: None,
+        antenna_id: Some((index % 4 + 1) as u16),
+        peak_rssi: Some(-40 - (index % 30) as i8),
+        channel_index: None,
+        first_seen_timestamp_utc: Some(1_557_458_645_000_000 + index as u64 * 1000),
+        first_seen_timestamp_uptime: None,
+        last_seen_timestamp_utc: Some(1_557_458_645_500_000 + index as u64 * 1000),
+        last_seen_timestamp_uptime: None,
+        tag_seen_count: Some((index % 10 + 1) as u16),
+        air_protocol_tag_data: vec![],
+        access_spec_id: None,
+        access_command_op_spec_result: vec![],
+        custom: vec![],
+    }
+}
+
+/// Returns a report containing `num_tags` tags
+pub fn ro_access_report(num_tags: usize) -> RoAccessReport {
+    RoAccessReport {
+        tag_report_data: (0..num_tags as u32).map(tag_report_data).collect(),
+        rf_survey_report_data: vec![],
+        custom: vec![],
+    }
+}
+
+/// Returns the EPCs of the tags in `ro_access_report(num_tags)`
+pub fn epcs(num_tags: usize) -> Vec<Epc> {
+    (0..num_tags as u32).map(|index| Epc::from(tag_report_data(index).epc_parameter)).collect()
+}
+
+/// Returns a report specification that enables the fields set by `tag_report_data`
+pub fn ro_report_spec() -> ROReportSpec {
+    ROReportSpec {
+        ro_report_trigger: ROReportTriggerType::Upon_N_Tags_Or_End_Of_ROSpec,
+        n: 100,
+        tag_report_content_selector: TagReportContentSelector {
+            enable_ro_spec_id: true,
+            enable_spec_index: false,
+            enable_inventory_parameter_spec_id: false,
+            enable_antenna_id: true,
+            enable_channel_index: false,
+            enable_peak_rssi: true,
+            enable_first_seen_timestamp: true,
+            enable_last_seen_timestamp: true,
+            enable_tag_seen_count: true,
+            enable_access_spec_id: false,
+            reserved: 0,
+            air_protocol_epc_memory_selector: vec![C1G2EPCMemorySelector {
+                enable_crc: false,
+                enable_pc_bits: true,
+                reserved: 0,
+            }
+            .into()],
+        },
+        custom: vec![],
+    }
+}
+
+/// Returns a request to add an inventory ROSpec using all of the reader's antennas
+pub fn add_rospec(ro_spec_id: u32, num_antennas: u16) -> AddRospec {
+    let antenna_configuration = (1..=num_antennas)
+        .map(|antenna_id| AntennaConfiguration {
+            antenna_id,
+            rf_receiver: Some(RFReceiver { receiver_sensitivity: 1 }),
+            rf_transmitter: Some(RFTransmitter {
+                hop_table_id: 1,
+                channel_index: 0,
+                transmit_power: 81,
+            }),
+            air_protocol_inventory_command_settings: vec![C1G2InventoryCommand {
+                tag_inventory_state_aware: false,
+                reserved: 0,
+                c1g2_filter: vec![],
+                c1g2_rf_control: Some(C1G2RFControl { mode_index: 1002, tari: 0 }),
+                c1g2_singulation_control: Some(C1G2SingulationControl {
+                    session: 1,
+                    reserved: 0,
+                    tag_population: 32,
+                    tag_transit_time: 0,
+                    c1g2_tag_inventory_state_aware_singulation_action: None,
+                }),
+                custom: vec![],
+            }
+            .into()],
+        })
+        .collect();
+
+    AddRospec {
+        ro_spec: ROSpec {
+            ro_spec_id,
+            priority: 0,
+            current_state: ROSpecState::Disabled,
+            ro_boundary_spec: ROBoundarySpec {
+                ro_spec_start_trigger: ROSpecStartTrigger {
+                    ro_spec_start_trigger_type: ROSpecStartTriggerType::Immediate,
+                    periodic_trigger_value: None,
+                    gpi_trigger_value: None,
+                },
+                ro_spec_stop_trigger: ROSpecStopTrigger {
+                    ro_spec_stop_trigger_type: ROSpecStopTriggerType::Null,
+                    duration_trigger_value: 0,
+                    gpi_trigger_value: None,
+                },
+            },
+            spec_parameter: vec![AISpec {
+                antenna_ids: vec![0],
+                ai_spec_stop_trigger: AISpecStopTrigger {
+                    ai_spec_stop_trigger_type: AISpecStopTriggerType::Null,
+                    duration_trigger: 0,
+                    gpi_trigger_value: None,
+                    tag_observation_trigger: None,
+                },
+                inventory_parameter_spec: vec![InventoryParameterSpec {
+                    inventory_parameter_spec_id: 1,
+                    protocol_id: AirProtocols::EPCGlobalClass1Gen2,
+                    antenna_configuration,
+                    custom: vec![],
+                }],
+                custom: vec![],
+            }
+            .into()],
+            ro_report_spec: Some(ro_report_spec()),
+        },
+    }
+}
+
+/// Encodes `message` as a complete frame, including the header
+pub fn frame<T: LLRPMessage>(id: u32, message: T) -> Vec<u8> {
+    let mut frame = vec![];
+    write_message(&mut frame, BinaryMessage::from_message(id, message).unwrap()).unwrap();
+    frame
+}
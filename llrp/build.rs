@@ -1,11 +1,30 @@
-use std::io::Write;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    io::Write,
+    path::Path,
+};
 
 fn main() {
-    let definitions = llrp_codegen::load_definitions();
-    let code = llrp_codegen::generate_code(definitions);
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let options = llrp_codegen::Options {
+        accessors: cfg!(feature = "accessors"),
+        ..Default::default()
+    };
 
     let out_dir = std::env::var_os("OUT_DIR").unwrap();
-    let out_path = std::path::Path::new(&out_dir).join("llrp_generated.rs");
+    let out_path = Path::new(&out_dir).join("llrp_generated.rs");
+    let hash_path = Path::new(&out_dir).join("llrp_generated.hash");
+
+    // Skip generating (and formatting) the code if it was generated from the same inputs
+    let hash = input_hash(&options);
+    if out_path.exists() && std::fs::read_to_string(&hash_path).ok().as_ref() == Some(&hash) {
+        return;
+    }
+
+    let definitions = llrp_codegen::load_definitions();
+    let code = llrp_codegen::generate_code_with_options(definitions, &options);
 
     let mut output = std::fs::File::create(&out_path).unwrap();
     write!(output, "{}", code).unwrap();
@@ -19,4 +38,16 @@ fn main() {
             .format(rustfmt_nightly::Input::File(out_path))
             .unwrap();
     }
+
+    std::fs::write(&hash_path, hash).unwrap();
+}
+
+/// Hashes the inputs of the generated code: the definitions and code generator, and the options
+/// and features that change its output
+fn input_hash(options: &llrp_codegen::Options) -> String {
+    let mut hasher = DefaultHasher::new();
+    llrp_codegen::input_hash().hash(&mut hasher);
+    options.accessors.hash(&mut hasher);
+    cfg!(feature = "format").hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
 }
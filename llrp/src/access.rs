@@ -0,0 +1,339 @@
+//! Helpers for building AccessSpecs for common tag operations
+
+use std::convert::TryFrom;
+
+use crate::{
+    choices::{AccessCommandOpSpec, AccessCommandOpSpecResult},
+    enumerations::{
+        AccessReportTriggerType, AccessSpecState, AccessSpecStopTriggerType, AirProtocols,
+        C1G2KillResultType, C1G2LockDataField, C1G2LockPrivilege, C1G2ReadResultType,
+        C1G2WriteResultType,
+    },
+    parameters::{
+        AccessCommand, AccessReportSpec, AccessSpec, AccessSpecStopTrigger, C1G2BlockPermalock,
+        C1G2GetBlockPermalockStatus, C1G2Kill, C1G2Lock, C1G2LockPayload, C1G2Read, C1G2TagSpec,
+        C1G2TargetTag, C1G2Write, TagReportData,
+    },
+    BitArray,
+};
+
+/// The memory bank containing the CRC, PC word and EPC of a tag
+const EPC_MEMORY_BANK: u8 = 1;
+
+/// The OpSpecID used for writing the EPC in the AccessSpec returned by `write_epc_access_spec`
+pub const WRITE_EPC_OP_SPEC_ID: u16 = 1;
+
+/// The OpSpecID used for reading back the EPC in the AccessSpec returned by
+/// `write_epc_access_spec`
+pub const VERIFY_EPC_OP_SPEC_ID: u16 = 2;
+
+/// The OpSpecID used for the kill command in the AccessSpec returned by `kill_access_spec`
+pub const KILL_OP_SPEC_ID: u16 = 1;
+
+/// Converts `bytes` to a list of big-endian words, padding the last word with zeros if required
+pub(crate) fn to_words(bytes: &[u8]) -> Vec<u16> {
+    bytes.chunks(2).map(|x| u16::from_be_bytes([x[0], *x.get(1).unwrap_or(&0)])).collect()
+}
+
+/// The length in bytes of the longest EPC that the length field of the PC word can describe
+pub const MAX_EPC_LEN: usize = 62;
+
+/// Returns the PC word for an EPC of `epc_len` bytes, with all other PC bits cleared. Returns
+/// `None` if the EPC is empty, is not a whole number of words, or is longer than `MAX_EPC_LEN`.
+pub fn epc_pc_word(epc_len: usize) -> Option<u16> {
+    if epc_len == 0 || epc_len % 2 == 1 || epc_len > MAX_EPC_LEN {
+        return None;
+    }
+    Some(((epc_len / 2) as u16) << 11)
+}
+
+/// Builds a (disabled) AccessSpec that changes the EPC of the tag with `current_epc` to
+/// `new_epc`, updating the PC word to match the length of the new EPC. Returns `None` if
+/// `new_epc` cannot be described by the PC word (see `epc_pc_word`).
+///
+/// If `verify` is set, the AccessSpec also reads back the EPC after it is written, allowing the
+/// result to be checked with `epc_write_succeeded`. The AccessSpec applies to all ROSpecs and
+/// antennas, and is removed by the reader after it has been executed once.
+pub fn write_epc_access_spec(
+    access_spec_id: u32,
+    current_epc: &[u8],
+    new_epc: &[u8],
+    verify: bool,
+) -> Option<AccessSpec> {
+    let mut write_data = vec![epc_pc_word(new_epc.len())?];
+    write_data.extend(to_words(new_epc));
+
+    let mut access_command_op_spec = vec![C1G2Write {
+        op_spec_id: WRITE_EPC_OP_SPEC_ID,
+        access_password: 0,
+        mb: EPC_MEMORY_BANK,
+        reserved: 0,
+        word_pointer: 1,
+        write_data,
+    }
+    .into()];
+
+    if verify {
+        access_command_op_spec.push(
+            C1G2Read {
+                op_spec_id: VERIFY_EPC_OP_SPEC_ID,
+                access_password: 0,
+                mb: EPC_MEMORY_BANK,
+                reserved: 0,
+                word_pointer: 2,
+                word_count: to_words(new_epc).len() as u16,
+            }
+            .into(),
+        );
+    }
+
+    Some(single_tag_access_spec(access_spec_id, current_epc, access_command_op_spec))
+}
+
+/// Checks the OpSpec results of an AccessSpec created by `write_epc_access_spec`.
+///
+/// Returns `true` if the write was successful, and if the EPC was read back, that it matches
+/// `new_epc`.
+pub fn epc_write_succeeded(data: &TagReportData, new_epc: &[u8]) -> bool {
+    let mut written = false;
+    for result in &data.access_command_op_spec_result {
+        match result {
+            AccessCommandOpSpecResult::C1G2WriteOpSpecResult(result)
+                if result.op_spec_id == WRITE_EPC_OP_SPEC_ID =>
+            {
+                written = result.result == C1G2WriteResultType::Success;
+            }
+            AccessCommandOpSpecResult::C1G2ReadOpSpecResult(result)
+                if result.op_spec_id == VERIFY_EPC_OP_SPEC_ID =>
+            {
+                let verified = result.result == C1G2ReadResultType::Success
+                    && result.read_data == to_words(new_epc);
+                if !verified {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+    written
+}
+
+/// Builds a (disabled) AccessSpec that runs `access_command_op_spec` on the tag with `epc`, on all
+/// ROSpecs and antennas, and is removed by the reader after it has been executed once
+fn single_tag_access_spec(
+    access_spec_id: u32,
+    epc: &[u8],
+    access_command_op_spec: Vec<AccessCommandOpSpec>,
+) -> AccessSpec {
+    AccessSpec {
+        access_spec_id,
+        antenna_id: 0,
+        protocol_id: AirProtocols::EPCGlobalClass1Gen2,
+        current_state: AccessSpecState::Disabled,
+        reserved: 0,
+        ro_spec_id: 0,
+        access_spec_stop_trigger: AccessSpecStopTrigger {
+            access_spec_stop_trigger: AccessSpecStopTriggerType::Operation_Count,
+            operation_count_value: 1,
+        },
+        access_command: AccessCommand {
+            air_protocol_tag_spec: C1G2TagSpec {
+                c1g2_target_tag: vec![C1G2TargetTag {
+                    mb: EPC_MEMORY_BANK,
+                    match_: true,
+                    reserved: 0,
+                    pointer: 0x20,
+                    tag_mask: BitArray::from_bytes(vec![0xff; epc.len()]),
+                    tag_data: BitArray::from_bytes(epc),
+                }],
+            }
+            .into(),
+            access_command_op_spec,
+            custom: vec![],
+        },
+        access_report_spec: Some(AccessReportSpec {
+            access_report_trigger: AccessReportTriggerType::End_Of_AccessSpec,
+        }),
+        custom: vec![],
+    }
+}
+
+/// An explicit confirmation that a tag should be permanently killed, required by
+/// `kill_access_spec` so that a kill cannot be requested by accident (e.g. by passing the wrong
+/// arguments to a similar helper)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ConfirmKill;
+
+/// Builds a (disabled) AccessSpec that permanently kills the tag with `epc`.
+///
+/// Tags cannot be killed with a zero kill password, so `None` is returned if `kill_password` is
+/// zero. The AccessSpec applies to all ROSpecs and antennas, and is removed by the reader after it
+/// has been executed once. The outcome can be checked with `kill_result`.
+pub fn kill_access_spec(
+    access_spec_id: u32,
+    epc: &[u8],
+    kill_password: u32,
+    _confirm: ConfirmKill,
+) -> Option<AccessSpec> {
+    if kill_password == 0 {
+        return None;
+    }
+    let kill = C1G2Kill { op_spec_id: KILL_OP_SPEC_ID, kill_password };
+    Some(single_tag_access_spec(access_spec_id, epc, vec![kill.into()]))
+}
+
+/// Returns the outcome of the kill command of an AccessSpec created by `kill_access_spec`: `Ok` if
+/// the tag was killed, or the error reported by the reader otherwise. Returns `None` if the tag
+/// report does not contain the result.
+pub fn kill_result(data: &TagReportData) -> Option<Result<(), C1G2KillResultType>> {
+    data.access_command_op_spec_result.iter().find_map(|result| match result {
+        AccessCommandOpSpecResult::C1G2KillOpSpecResult(result)
+            if result.op_spec_id == KILL_OP_SPEC_ID =>
+        {
+            match result.result {
+                C1G2KillResultType::Success => Some(Ok(())),
+                ref error => Some(Err(error.clone())),
+            }
+        }
+        _ => None,
+    })
+}
+
+/// Commonly used combinations of lock actions
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LockPolicy {
+    /// Lock the EPC memory and the access password, leaving the user memory unlocked
+    LockEpcAndAccessPassword,
+
+    /// Lock the EPC memory, user memory and both passwords
+    LockAll,
+
+    /// Permanently lock the kill password, preventing the tag from ever being killed or having its
+    /// kill password changed
+    PermalockKillPassword,
+
+    /// Unlock all memory banks and passwords
+    UnlockAll,
+}
+
+impl LockPolicy {
+    /// Returns the lock payloads required to apply the policy
+    pub fn payloads(self) -> Vec<C1G2LockPayload> {
+        use self::{C1G2LockDataField as Field, C1G2LockPrivilege as Privilege};
+
+        let actions = match self {
+            LockPolicy::LockEpcAndAccessPassword => vec![
+                (Privilege::Read_Write, Field::EPC_Memory),
+                (Privilege::Read_Write, Field::Access_Password),
+                (Privilege::Unlock, Field::User_Memory),
+            ],
+            LockPolicy::LockAll => vec![
+                (Privilege::Read_Write, Field::Kill_Password),
+                (Privilege::Read_Write, Field::Access_Password),
+                (Privilege::Read_Write, Field::EPC_Memory),
+                (Privilege::Read_Write, Field::User_Memory),
+            ],
+            LockPolicy::PermalockKillPassword => {
+                vec![(Privilege::Perma_Lock, Field::Kill_Password)]
+            }
+            LockPolicy::UnlockAll => vec![
+                (Privilege::Unlock, Field::Kill_Password),
+                (Privilege::Unlock, Field::Access_Password),
+                (Privilege::Unlock, Field::EPC_Memory),
+                (Privilege::Unlock, Field::User_Memory),
+            ],
+        };
+
+        actions
+            .into_iter()
+            .map(|(privilege, data_field)| C1G2LockPayload { privilege, data_field })
+            .collect()
+    }
+
+    /// Builds a `C1G2Lock` OpSpec that applies the policy.
+    ///
+    /// Note: locking requires the tag to be in the secured state, so `access_password` must be the
+    /// (non-zero) access password of the tag.
+    pub fn op_spec(self, op_spec_id: u16, access_password: u32) -> C1G2Lock {
+        C1G2Lock { op_spec_id, access_password, c1g2_lock_payload: self.payloads() }
+    }
+}
+
+/// The number of blocks covered by each word of a block permalock mask
+const BLOCKS_PER_MASK_WORD: u16 = 16;
+
+/// Builds a `C1G2BlockPermalock` OpSpec that permanently locks each of the blocks in `blocks` in
+/// memory bank `mb`, or returns `None` if `blocks` is empty
+pub fn block_permalock_op_spec(
+    op_spec_id: u16,
+    access_password: u32,
+    mb: u8,
+    blocks: &[u16],
+) -> Option<C1G2BlockPermalock> {
+    let first = blocks.iter().min()? / BLOCKS_PER_MASK_WORD;
+    let last = blocks.iter().max()? / BLOCKS_PER_MASK_WORD;
+
+    let mut block_mask = vec![0; (last - first + 1) as usize];
+    for block in blocks {
+        // The first block of each word is stored in the most significant bit
+        let word = (block / BLOCKS_PER_MASK_WORD - first) as usize;
+        block_mask[word] |= 0x8000 >> (block % BLOCKS_PER_MASK_WORD);
+    }
+
+    Some(C1G2BlockPermalock {
+        op_spec_id,
+        access_password,
+        mb,
+        reserved: 0,
+        block_pointer: first,
+        block_mask_word_count: block_mask.len() as u16,
+        block_mask,
+    })
+}
+
+/// Builds a `C1G2GetBlockPermalockStatus` OpSpec that reads the permalock status of at least
+/// `num_blocks` blocks starting at `first_block` in memory bank `mb`.
+///
+/// The reader reports the status of whole mask words, so the status should be decoded with
+/// `permalocked_blocks` using the `block_pointer` of the returned OpSpec.
+pub fn block_permalock_status_op_spec(
+    op_spec_id: u16,
+    access_password: u32,
+    mb: u8,
+    first_block: u16,
+    num_blocks: u16,
+) -> C1G2GetBlockPermalockStatus {
+    let block_pointer = first_block / BLOCKS_PER_MASK_WORD;
+    // The range is clamped to the last block that can be addressed
+    let last_block = (first_block as u32 + num_blocks.max(1) as u32 - 1).min(u16::MAX as u32);
+    let last = last_block as u16 / BLOCKS_PER_MASK_WORD;
+
+    C1G2GetBlockPermalockStatus {
+        op_spec_id,
+        access_password,
+        mb,
+        reserved: 0,
+        block_pointer,
+        block_range: last - block_pointer + 1,
+    }
+}
+
+/// Returns the block numbers that are permalocked in `permalock_status`, where `block_pointer` is
+/// the mask word offset that the status was read from. Blocks past the last block that can be
+/// addressed are ignored.
+pub fn permalocked_blocks(block_pointer: u16, permalock_status: &[u16]) -> Vec<u16> {
+    let mut blocks = vec![];
+    for (i, word) in permalock_status.iter().enumerate() {
+        let offset = (block_pointer as usize + i) * BLOCKS_PER_MASK_WORD as usize;
+        for bit in 0..BLOCKS_PER_MASK_WORD as usize {
+            if word & (0x8000 >> bit) == 0 {
+                continue;
+            }
+            match u16::try_from(offset + bit) {
+                Ok(block) => blocks.push(block),
+                Err(_) => return blocks,
+            }
+        }
+    }
+    blocks
+}
@@ -2,7 +2,69 @@ use std::io;
 
 use byteorder::{BigEndian, ReadBytesExt};
 
-use crate::{messages::Message, LLRPMessage};
+use crate::{messages::Message, DecodeWarning, LLRPMessage};
+
+/// How received messages with a protocol version other than the negotiated version are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionPolicy {
+    /// Reject the message
+    Strict,
+
+    /// Accept the message, but log a warning
+    Warn,
+
+    /// Accept the message
+    Accept,
+}
+
+/// The protocol version negotiated with a reader, along with how received messages with a
+/// different version are handled.
+///
+/// The `read_*` methods read a message like the functions of the same name, and then check its
+/// version with `MessageHeader::check_version`. A message with a rejected version is still
+/// consumed, so the next read starts at the following frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionCheck {
+    pub negotiated: u8,
+    pub policy: VersionPolicy,
+}
+
+impl VersionCheck {
+    pub fn new(negotiated: u8, policy: VersionPolicy) -> Self {
+        Self { negotiated, policy }
+    }
+
+    fn check(&self, header: &MessageHeader) -> crate::Result<()> {
+        header.check_version(self.negotiated, self.policy)
+    }
+
+    pub fn read_message<R: io::Read>(&self, reader: R) -> crate::Result<BinaryMessage> {
+        let message = read_message(reader)?;
+        message.check_version(self.negotiated, self.policy)?;
+        Ok(message)
+    }
+
+    pub fn read_message_into<R: io::Read>(
+        &self,
+        reader: R,
+        buffer: &mut Vec<u8>,
+    ) -> crate::Result<MessageHeader> {
+        let header = read_message_into(reader, buffer)?;
+        self.check(&header)?;
+        Ok(header)
+    }
+
+    pub fn read_raw_message<R: io::Read>(&self, reader: R) -> crate::Result<RawMessage> {
+        read_raw_message_checked(reader, |header| self.check(header))
+    }
+
+    pub fn read_dynamic_message<R: io::BufRead>(
+        &self,
+        reader: R,
+    ) -> crate::Result<(MessageHeader, Message)> {
+        read_dynamic_message_checked(reader, |header| self.check(header))
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct BinaryMessage {
@@ -14,36 +76,185 @@ pub struct BinaryMessage {
 
 impl BinaryMessage {
     pub fn from_message<T: LLRPMessage>(id: u32, message: T) -> crate::Result<BinaryMessage> {
+        message.validate()?;
         let mut buffer = vec![];
-        message.encode(&mut buffer);
+        message.encode_checked(&mut buffer)?;
         Ok(BinaryMessage { ver: 1, message_type: T::ID, id, value: buffer })
     }
 
     pub fn to_message<T: LLRPMessage>(&self) -> crate::Result<T> {
+        #[cfg(feature = "tracing")]
+        let _span = self.decode_span().entered();
+
         let (msg, _) = T::decode(&self.value)?;
         Ok(msg)
     }
 
     pub fn from_dynamic_message(id: u32, message: &Message) -> crate::Result<BinaryMessage> {
+        message.validate()?;
         let mut buffer = vec![];
-        message.encode(&mut buffer);
+        message.encode_checked(&mut buffer)?;
         Ok(BinaryMessage { ver: 1, message_type: message.message_type(), id, value: buffer })
     }
 
     pub fn to_dynamic_message(&self) -> crate::Result<Message> {
-        Message::decode(self.message_type as u32, &self.value)
+        #[cfg(feature = "tracing")]
+        let span = self.decode_span();
+        #[cfg(feature = "tracing")]
+        let _guard = span.enter();
+
+        let msg = Message::decode(self.message_type as u32, &self.value)?;
+
+        #[cfg(feature = "tracing")]
+        if let Some(status) = msg.status() {
+            span.record("status", tracing::field::debug(&status.status_code));
+        }
+
+        Ok(msg)
+    }
+
+    /// Checks the protocol version of the message against the version negotiated with the reader.
+    /// See `MessageHeader::check_version`.
+    pub fn check_version(&self, negotiated: u8, policy: VersionPolicy) -> crate::Result<()> {
+        let header = MessageHeader { ver: self.ver, message_type: self.message_type, id: self.id };
+        header.check_version(negotiated, policy)
+    }
+
+    /// Decodes the message like `to_dynamic_message`, but skips data that could not be decoded
+    /// instead of failing, returning warnings that describe what was skipped. See
+    /// `Decoder::lenient` for the problems that can be recovered from.
+    ///
+    /// Trailing bytes after the end of the message are also reported.
+    pub fn to_dynamic_message_lenient(&self) -> crate::Result<(Message, Vec<DecodeWarning>)> {
+        let mut decoder = crate::Decoder::lenient(&self.value);
+        let msg = Message::decode_from(self.message_type as u32, &mut decoder)?;
+        decoder.validate_consumed_in(self.message_type)?;
+        Ok((msg, decoder.take_warnings()))
+    }
+
+    #[cfg(feature = "tracing")]
+    fn decode_span(&self) -> tracing::Span {
+        tracing::debug_span!(
+            "llrp_decode",
+            message_type = self.message_type,
+            id = self.id,
+            len = self.value.len(),
+            status = tracing::field::Empty,
+        )
     }
 }
 
-const LLRP_HEADER_LENGTH: usize = 10;
+/// A decoded message along with the frame (header and body) it was decoded from.
+///
+/// This allows decoded fields to be inspected while the original bytes are forwarded unchanged,
+/// e.g. with `writer.write_all(raw.frame())`.
+#[derive(Debug, Clone)]
+pub struct RawMessage {
+    header: MessageHeader,
+    message: Message,
+    frame: Vec<u8>,
+}
+
+impl RawMessage {
+    /// Decodes a complete frame. The length in the header must match the length of `frame`.
+    pub fn from_frame(frame: Vec<u8>) -> crate::Result<RawMessage> {
+        let (header, length) = decode_header(&frame)?;
+        if length != frame.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid length: {} (frame is {} bytes)", length, frame.len()),
+            )
+            .into());
+        }
+
+        let message = Message::decode(header.message_type as u32, &frame[LLRP_HEADER_LENGTH..])?;
+        Ok(RawMessage { header, message, frame })
+    }
 
-pub fn read_message<R: io::Read>(mut reader: R) -> io::Result<BinaryMessage> {
+    pub fn header(&self) -> &MessageHeader {
+        &self.header
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    /// Returns the original bytes of the frame, including the header
+    pub fn frame(&self) -> &[u8] {
+        &self.frame
+    }
+
+    /// Returns the original bytes of the message body
+    pub fn payload(&self) -> &[u8] {
+        &self.frame[LLRP_HEADER_LENGTH..]
+    }
+
+    pub fn into_message(self) -> Message {
+        self.message
+    }
+
+    pub fn into_frame(self) -> Vec<u8> {
+        self.frame
+    }
+}
+
+pub(crate) const LLRP_HEADER_LENGTH: usize = 10;
+
+/// The header of an LLRP message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageHeader {
+    pub ver: u8,
+    pub message_type: u16,
+    pub id: u32,
+}
+
+impl MessageHeader {
+    /// Checks the protocol version of the message against the version negotiated with the reader.
+    ///
+    /// With `VersionPolicy::Strict` a mismatch is reported as `Error::VersionMismatch`. With
+    /// `VersionPolicy::Warn` it is logged (with the `tracing` feature) and the message is accepted.
+    pub fn check_version(&self, negotiated: u8, policy: VersionPolicy) -> crate::Result<()> {
+        if self.ver == negotiated {
+            return Ok(());
+        }
+
+        match policy {
+            VersionPolicy::Strict => {
+                Err(crate::Error::VersionMismatch { expected: negotiated, found: self.ver })
+            }
+            VersionPolicy::Warn => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    expected = negotiated,
+                    found = self.ver,
+                    message_type = self.message_type,
+                    id = self.id,
+                    "LLRP protocol version mismatch"
+                );
+                Ok(())
+            }
+            VersionPolicy::Accept => Ok(()),
+        }
+    }
+}
+
+/// Decodes a message header, returning it along with the length of the message (including the
+/// header)
+fn decode_header(mut header: &[u8]) -> io::Result<(MessageHeader, usize)> {
     // First 16 bits are packed with [3-bit reserved, 3-bit version, 10-bit message type]
-    let prefix = reader.read_u16::<BigEndian>()?;
+    let prefix = header.read_u16::<BigEndian>()?;
     let ver = ((prefix >> 10) & 0b111) as u8;
     let message_type = prefix & 0b11_1111_1111;
 
-    let length = reader.read_u32::<BigEndian>()? as usize;
+    let length = header.read_u32::<BigEndian>()? as usize;
+    let id = header.read_u32::<BigEndian>()?;
+
+    Ok((MessageHeader { ver, message_type, id }, length))
+}
+
+/// Reads the body of a message of `length` bytes (including the header) from `reader` into
+/// `buffer`
+fn read_body<R: io::Read>(mut reader: R, length: usize, buffer: &mut Vec<u8>) -> io::Result<()> {
     if length < LLRP_HEADER_LENGTH {
         return Err(io::Error::new(
             io::ErrorKind::InvalidData,
@@ -51,22 +262,178 @@ pub fn read_message<R: io::Read>(mut reader: R) -> io::Result<BinaryMessage> {
         ));
     }
 
-    let id = reader.read_u32::<BigEndian>()?;
-
-    let mut value = vec![0; length - LLRP_HEADER_LENGTH];
-    reader.read_exact(&mut value)?;
+    buffer.clear();
+    buffer.resize(length - LLRP_HEADER_LENGTH, 0);
+    reader.read_exact(buffer)
+}
 
+pub fn read_message<R: io::Read>(reader: R) -> io::Result<BinaryMessage> {
+    let mut value = vec![];
+    let MessageHeader { ver, message_type, id } = read_message_into(reader, &mut value)?;
     Ok(BinaryMessage { ver, message_type, id, value })
 }
 
+/// Reads a message from `reader`, storing the body of the message in `buffer` and returning its
+/// header.
+///
+/// `buffer` is reused, so reading messages in a loop with the same buffer only allocates when a
+/// message is larger than all previous ones. The message can be decoded with
+/// `Message::decode(header.message_type as u32, &buffer)`.
+pub fn read_message_into<R: io::Read>(
+    mut reader: R,
+    buffer: &mut Vec<u8>,
+) -> io::Result<MessageHeader> {
+    let mut header = [0; LLRP_HEADER_LENGTH];
+    reader.read_exact(&mut header)?;
+
+    let (header, length) = decode_header(&header)?;
+    read_body(reader, length, buffer)?;
+    Ok(header)
+}
+
+/// Reads and decodes the next message from `reader`, keeping the bytes of the frame.
+///
+/// The frame is consumed even if the message fails to decode.
+pub fn read_raw_message<R: io::Read>(reader: R) -> crate::Result<RawMessage> {
+    read_raw_message_checked(reader, |_| Ok(()))
+}
+
+/// Reads the next frame like `read_raw_message`, decoding it only if `check` accepts its header
+fn read_raw_message_checked<R, F>(mut reader: R, check: F) -> crate::Result<RawMessage>
+where
+    R: io::Read,
+    F: FnOnce(&MessageHeader) -> crate::Result<()>,
+{
+    let mut frame = vec![0; LLRP_HEADER_LENGTH];
+    reader.read_exact(&mut frame)?;
+
+    let (header, length) = decode_header(&frame)?;
+    let mut body = vec![];
+    read_body(reader, length, &mut body)?;
+    frame.extend_from_slice(&body);
+
+    check(&header)?;
+    RawMessage::from_frame(frame)
+}
+
+/// Reads and decodes the next message from `reader`.
+///
+/// If the whole frame is already in the reader's buffer, the message is decoded directly from the
+/// buffer, avoiding the copy made by `read_message`. The frame is consumed even if the message
+/// fails to decode, so the next call starts at the following frame.
+pub fn read_dynamic_message<R: io::BufRead>(reader: R) -> crate::Result<(MessageHeader, Message)> {
+    read_dynamic_message_checked(reader, |_| Ok(()))
+}
+
+/// Reads the next frame like `read_dynamic_message`, decoding it only if `check` accepts its
+/// header
+fn read_dynamic_message_checked<R, F>(
+    mut reader: R,
+    check: F,
+) -> crate::Result<(MessageHeader, Message)>
+where
+    R: io::BufRead,
+    F: FnOnce(&MessageHeader) -> crate::Result<()>,
+{
+    let available = reader.fill_buf()?;
+    if available.len() >= LLRP_HEADER_LENGTH {
+        let (header, length) = decode_header(&available[..LLRP_HEADER_LENGTH])?;
+        if length >= LLRP_HEADER_LENGTH && available.len() >= length {
+            let payload = &available[LLRP_HEADER_LENGTH..length];
+            let result =
+                check(&header).and_then(|_| Message::decode(header.message_type as u32, payload));
+            reader.consume(length);
+            return Ok((header, result?));
+        }
+    }
+
+    // The frame is split across multiple reads, so it needs to be copied
+    let mut buffer = vec![];
+    let header = read_message_into(reader, &mut buffer)?;
+    check(&header)?;
+    Ok((header, Message::decode(header.message_type as u32, &buffer)?))
+}
+
+/// Reads the next message from `reader`, skipping any bytes before it that do not start a
+/// plausible LLRP header. Returns the message and the number of bytes that were skipped.
+///
+/// This allows a stream to be recovered after a framing error (e.g. from `read_message`). A header
+/// is plausible if its reserved bits are clear, its version is 1 or 2, its message type is known
+/// and its length is between the header length and `max_len`. Corrupted data can still look like
+/// a plausible header, so if the returned message fails to decode, this should be called again.
+///
+/// Note: the header is searched for one byte at a time, so `reader` should be buffered.
+pub fn read_message_resync<R: io::Read>(
+    mut reader: R,
+    max_len: usize,
+) -> io::Result<(BinaryMessage, usize)> {
+    let mut header = [0; LLRP_HEADER_LENGTH];
+    reader.read_exact(&mut header)?;
+
+    let mut skipped = 0;
+    loop {
+        let (candidate, length) = decode_header(&header)?;
+        let plausible = header[0] & 0b1110_0000 == 0
+            && (candidate.ver == 1 || candidate.ver == 2)
+            && Message::is_known_type(candidate.message_type)
+            && (LLRP_HEADER_LENGTH..=max_len).contains(&length);
+        if plausible {
+            let MessageHeader { ver, message_type, id } = candidate;
+            let mut value = vec![];
+            read_body(reader, length, &mut value)?;
+            return Ok((BinaryMessage { ver, message_type, id, value }, skipped));
+        }
+
+        header.copy_within(1.., 0);
+        reader.read_exact(&mut header[LLRP_HEADER_LENGTH - 1..])?;
+        skipped += 1;
+    }
+}
+
+pub(crate) fn encode_header(
+    ver: u8,
+    message_type: u16,
+    length: usize,
+    id: u32,
+) -> [u8; LLRP_HEADER_LENGTH] {
+    let mut header = [0; LLRP_HEADER_LENGTH];
+    header[0] = ((ver & 0b111) << 2) | (message_type >> 8) as u8;
+    header[1] = message_type as u8;
+    header[2..6].copy_from_slice(&(length as u32).to_be_bytes());
+    header[6..10].copy_from_slice(&id.to_be_bytes());
+    header
+}
+
 pub fn write_message<W: io::Write>(mut writer: W, message: BinaryMessage) -> io::Result<()> {
-    let prefix = [
-        ((message.ver & 0b111) << 2) | (message.message_type >> 8) as u8,
-        message.message_type as u8,
-    ];
-
-    writer.write_all(&prefix)?;
-    writer.write_all(&((message.value.len() + LLRP_HEADER_LENGTH) as u32).to_be_bytes())?;
-    writer.write_all(&message.id.to_be_bytes())?;
+    let length = message.value.len() + LLRP_HEADER_LENGTH;
+    writer.write_all(&encode_header(message.ver, message.message_type, length, message.id))?;
     writer.write_all(&message.value)
 }
+
+/// Encodes a complete LLRP frame (header and message) into `buffer` without allocating, returning
+/// the length of the frame
+pub fn encode_message<T: LLRPMessage>(
+    id: u32,
+    message: &T,
+    buffer: &mut [u8],
+) -> crate::Result<usize> {
+    message.validate()?;
+
+    let available = buffer.len();
+    let (header, body) = buffer.split_at_mut(available.min(LLRP_HEADER_LENGTH));
+    let length = match message.encode_to_slice(body) {
+        Ok(len) => len + LLRP_HEADER_LENGTH,
+        Err(crate::Error::BufferTooSmall { needed, .. }) => {
+            let needed = needed + LLRP_HEADER_LENGTH;
+            return Err(crate::Error::BufferTooSmall { needed, available });
+        }
+        Err(e) => return Err(e),
+    };
+
+    if header.len() < LLRP_HEADER_LENGTH {
+        return Err(crate::Error::BufferTooSmall { needed: length, available });
+    }
+    header.copy_from_slice(&encode_header(1, T::ID, length, id));
+
+    Ok(length)
+}
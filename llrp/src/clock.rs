@@ -0,0 +1,114 @@
+//! Estimation of the offset between a reader's clock and the local clock
+
+use std::{
+    collections::VecDeque,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{choices::Timestamp, units};
+
+/// Estimates the offset and drift of a reader's clock relative to the local clock, from the
+/// timestamps of messages (e.g. reader event notifications) and the local time they were received.
+///
+/// The estimate is a least squares fit over the most recent samples, so it includes the average
+/// network and processing delay. Samples must either all be UTC timestamps or all be uptime
+/// timestamps: the estimator is reset if the kind of timestamp changes or if the reader's clock
+/// moves backwards (e.g. after a reboot).
+#[derive(Debug, Clone)]
+pub struct ClockEstimator {
+    max_samples: usize,
+    utc: bool,
+    /// The reader time and `local - reader` offset of each sample, in microseconds
+    samples: VecDeque<(u64, i64)>,
+}
+
+impl ClockEstimator {
+    /// Creates an estimator that uses up to `max_samples` of the most recent samples
+    pub fn new(max_samples: usize) -> Self {
+        Self { max_samples: max_samples.max(1), utc: false, samples: VecDeque::new() }
+    }
+
+    /// Adds a sample of the reader's clock, received at local time `local`
+    pub fn add_sample(&mut self, reader: &Timestamp, local: SystemTime) {
+        let local_us = match local.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration.as_micros() as i64,
+            Err(_) => return,
+        };
+        let reader_us = reader.microseconds();
+        let utc = reader.utc().is_some();
+
+        let backwards = self.samples.back().map(|&(last, _)| reader_us < last) == Some(true);
+        if utc != self.utc || backwards {
+            self.samples.clear();
+            self.utc = utc;
+        }
+
+        if self.samples.len() == self.max_samples {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((reader_us, local_us - reader_us as i64));
+    }
+
+    /// Removes all samples
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Returns the number of samples the estimate is based on
+    pub fn num_samples(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns the estimated offset (local time minus reader time) at the most recent sample, in
+    /// microseconds
+    pub fn offset_us(&self) -> Option<i64> {
+        let &(last, _) = self.samples.back()?;
+        Some(self.offset_at(last)?.round() as i64)
+    }
+
+    /// Returns the estimated drift of the reader's clock relative to the local clock, in parts per
+    /// million. A positive value means that the reader's clock is running slow.
+    pub fn drift_ppm(&self) -> Option<f64> {
+        if self.samples.len() < 2 {
+            return None;
+        }
+        Some(self.fit()?.1 * 1e6)
+    }
+
+    /// Converts a timestamp from the reader into local time. Returns `None` if there are no
+    /// samples or the timestamp is not the same kind as the samples.
+    ///
+    /// Tag report timestamps can be corrected with `tag.first_seen_timestamp()` and
+    /// `tag.last_seen_timestamp()`.
+    pub fn to_local(&self, reader: &Timestamp) -> Option<SystemTime> {
+        if reader.utc().is_some() != self.utc {
+            return None;
+        }
+        let reader_us = reader.microseconds();
+        let local_us = reader_us as f64 + self.offset_at(reader_us)?;
+        if local_us < 0.0 {
+            return None;
+        }
+        Some(units::micros_to_time(local_us.round() as u64))
+    }
+
+    fn offset_at(&self, reader_us: u64) -> Option<f64> {
+        let (intercept, slope, start) = self.fit()?;
+        Some(intercept + slope * (reader_us as i128 - start as i128) as f64)
+    }
+
+    /// Fits `offset = intercept + slope * (reader - start)` to the samples
+    fn fit(&self) -> Option<(f64, f64, u64)> {
+        let &(start, _) = self.samples.front()?;
+        let n = self.samples.len() as f64;
+        let points = || self.samples.iter().map(|&(x, y)| ((x - start) as f64, y as f64));
+
+        let mean_x = points().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points().map(|(_, y)| y).sum::<f64>() / n;
+        let sxx: f64 = points().map(|(x, _)| (x - mean_x) * (x - mean_x)).sum();
+        let sxy: f64 = points().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+
+        let slope = if sxx > 0.0 { sxy / sxx } else { 0.0 };
+        Some((mean_y - slope * mean_x, slope, start))
+    }
+}
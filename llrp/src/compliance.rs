@@ -0,0 +1,260 @@
+//! Checking observed message sequences against the rules of the LLRP specification
+//!
+//! `ComplianceChecker` is passed every message sent to and received from a reader, and reports
+//! each rule that is broken as a `Violation`. It can be used to check the behaviour of a reader as
+//! well as of a client application.
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    enumerations::{ROSpecState, StatusCode},
+    messages::{ErrorMessage, Message},
+    names, BinaryMessage, Error, LLRPMessage,
+};
+
+/// A broken protocol rule
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// A message could not be decoded
+    Malformed { message_type: u16, id: u32, error: Error },
+
+    /// A request was sent with the same message id as a request that has not been answered yet
+    DuplicateMessageId { message_type: u16, id: u32 },
+
+    /// `START_ROSPEC` was sent for a ROSpec that is disabled
+    StartDisabledRoSpec { ro_spec_id: u32, id: u32 },
+
+    /// A report or event was received before `ENABLE_EVENTS_AND_REPORTS` was sent to a reader that
+    /// holds reports until then
+    ReportBeforeEnable { message_type: u16, id: u32 },
+
+    /// A response was received with a message id that does not match any outstanding request
+    UnsolicitedResponse { message_type: u16, id: u32 },
+
+    /// The response to a request is not of the type that answers the request
+    UnexpectedResponse { request_type: u16, response_type: u16, id: u32 },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::Malformed { message_type, id, error } => {
+                write!(f, "{} (id {}) could not be decoded: {}", name(*message_type), id, error)
+            }
+            Violation::DuplicateMessageId { message_type, id } => write!(
+                f,
+                "{} reuses message id {} of an unanswered request",
+                name(*message_type),
+                id
+            ),
+            Violation::StartDisabledRoSpec { ro_spec_id, id } => {
+                write!(f, "START_ROSPEC (id {}) for disabled ROSpec {}", id, ro_spec_id)
+            }
+            Violation::ReportBeforeEnable { message_type, id } => write!(
+                f,
+                "{} (id {}) received before ENABLE_EVENTS_AND_REPORTS",
+                name(*message_type),
+                id
+            ),
+            Violation::UnsolicitedResponse { message_type, id } => {
+                write!(f, "{} (id {}) does not answer any request", name(*message_type), id)
+            }
+            Violation::UnexpectedResponse { request_type, response_type, id } => write!(
+                f,
+                "{} (id {}) answered with {}",
+                name(*request_type),
+                id,
+                name(*response_type)
+            ),
+        }
+    }
+}
+
+/// Tracks the messages exchanged with a reader and reports protocol violations.
+///
+/// The state of each ROSpec is tracked from the `ADD_ROSPEC`, `ENABLE_ROSPEC`, `DISABLE_ROSPEC`
+/// and `DELETE_ROSPEC` requests that the reader reports as successful. ROSpecs that were added
+/// before the checker was created are not known, and are not checked.
+#[derive(Debug, Clone, Default)]
+pub struct ComplianceChecker {
+    hold_events_and_reports: bool,
+    events_enabled: bool,
+    /// The requests that have not been answered yet, keyed by message id
+    pending: HashMap<u32, Message>,
+    /// Whether each known ROSpec is enabled
+    ro_specs: HashMap<u32, bool>,
+}
+
+impl ComplianceChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks that no reports or events are received before `ENABLE_EVENTS_AND_REPORTS` is sent
+    /// (default false). This should be set if the reader is configured to hold events and reports upon
+    /// reconnect, since other readers send them as soon as the connection is established.
+    pub fn hold_events_and_reports(mut self, hold: bool) -> Self {
+        self.hold_events_and_reports = hold;
+        self
+    }
+
+    /// Checks a message sent to the reader
+    pub fn observe_sent(&mut self, message: &BinaryMessage) -> Vec<Violation> {
+        let mut violations = vec![];
+        let decoded = match decode(message, &mut violations) {
+            Some(decoded) => decoded,
+            None => return violations,
+        };
+
+        match &decoded {
+            Message::EnableEventsAndReports(_) => self.events_enabled = true,
+            Message::StartRospec(start) if self.ro_specs.get(&start.ro_spec_id) == Some(&false) => {
+                violations.push(Violation::StartDisabledRoSpec {
+                    ro_spec_id: start.ro_spec_id,
+                    id: message.id,
+                });
+            }
+            _ => {}
+        }
+
+        if response_type(message.message_type).is_some()
+            && self.pending.insert(message.id, decoded).is_some()
+        {
+            violations.push(Violation::DuplicateMessageId {
+                message_type: message.message_type,
+                id: message.id,
+            });
+        }
+        violations
+    }
+
+    /// Checks a message received from the reader
+    pub fn observe_received(&mut self, message: &BinaryMessage) -> Vec<Violation> {
+        let mut violations = vec![];
+        let decoded = decode(message, &mut violations);
+
+        let held = match &decoded {
+            Some(Message::RoAccessReport(_)) => true,
+            Some(Message::ReaderEventNotification(notification)) => {
+                // The connection attempt event is always sent when a client connects
+                let data = &notification.reader_event_notification_data;
+                data.connection_attempt_event.is_none()
+            }
+            _ => false,
+        };
+        if held && self.hold_events_and_reports && !self.events_enabled {
+            violations.push(Violation::ReportBeforeEnable {
+                message_type: message.message_type,
+                id: message.id,
+            });
+        }
+
+        if !is_response(message.message_type) {
+            return violations;
+        }
+
+        let request = match self.pending.remove(&message.id) {
+            Some(request) => request,
+            None => {
+                violations.push(Violation::UnsolicitedResponse {
+                    message_type: message.message_type,
+                    id: message.id,
+                });
+                return violations;
+            }
+        };
+
+        let request_type = request.message_type();
+        if response_type(request_type) != Some(message.message_type)
+            && message.message_type != ErrorMessage::ID
+        {
+            violations.push(Violation::UnexpectedResponse {
+                request_type,
+                response_type: message.message_type,
+                id: message.id,
+            });
+            return violations;
+        }
+
+        let success = decoded
+            .as_ref()
+            .and_then(|decoded| decoded.status())
+            .map(|status| status.status_code == StatusCode::M_Success);
+        if success == Some(true) {
+            self.apply(&request);
+        }
+        violations
+    }
+
+    /// Returns whether a ROSpec is enabled, or `None` if the ROSpec is not known
+    pub fn ro_spec_enabled(&self, ro_spec_id: u32) -> Option<bool> {
+        self.ro_specs.get(&ro_spec_id).copied()
+    }
+
+    /// Returns the number of requests that have not been answered yet
+    pub fn num_pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Updates the state of the ROSpecs after the reader accepted a request
+    fn apply(&mut self, request: &Message) {
+        let (ro_spec_id, enabled) = match request {
+            Message::AddRospec(add) => {
+                let enabled = add.ro_spec.current_state != ROSpecState::Disabled;
+                self.ro_specs.insert(add.ro_spec.ro_spec_id, enabled);
+                return;
+            }
+            Message::DeleteRospec(delete) => {
+                match delete.ro_spec_id {
+                    0 => self.ro_specs.clear(),
+                    id => {
+                        self.ro_specs.remove(&id);
+                    }
+                }
+                return;
+            }
+            Message::EnableRospec(enable) => (enable.ro_spec_id, true),
+            Message::DisableRospec(disable) => (disable.ro_spec_id, false),
+            _ => return,
+        };
+
+        // An id of 0 refers to all ROSpecs
+        match ro_spec_id {
+            0 => self.ro_specs.values_mut().for_each(|state| *state = enabled),
+            id => {
+                self.ro_specs.insert(id, enabled);
+            }
+        }
+    }
+}
+
+fn decode(message: &BinaryMessage, violations: &mut Vec<Violation>) -> Option<Message> {
+    match message.to_dynamic_message() {
+        Ok(decoded) => Some(decoded),
+        Err(error) => {
+            violations.push(Violation::Malformed {
+                message_type: message.message_type,
+                id: message.id,
+                error,
+            });
+            None
+        }
+    }
+}
+
+/// Returns the type of the response to a request, e.g. `ADD_ROSPEC_RESPONSE` for `ADD_ROSPEC`
+fn response_type(request_type: u16) -> Option<u16> {
+    let name = names::message_name(request_type)?;
+    names::message_type(&format!("{}_RESPONSE", name))
+}
+
+fn is_response(message_type: u16) -> bool {
+    message_type == ErrorMessage::ID
+        || names::message_name(message_type).map(|name| name.ends_with("_RESPONSE")) == Some(true)
+}
+
+fn name(message_type: u16) -> String {
+    names::message_name(message_type)
+        .map(String::from)
+        .unwrap_or_else(|| format!("message type {}", message_type))
+}
@@ -0,0 +1,186 @@
+//! Utilities for working with reader configurations
+
+use crate::{
+    diff::{diff_option, Diff, Difference},
+    enumerations::GetReaderConfigRequestedData,
+    messages::{GetReaderConfig, GetReaderConfigResponse, SetReaderConfig},
+};
+
+/// Returns a request for the complete configuration of a reader, including all antennas and GPIO
+/// ports. The response can be stored as a snapshot of the configuration and later restored with
+/// [`restore_config`].
+pub fn snapshot_request() -> GetReaderConfig {
+    GetReaderConfig {
+        antenna_id: 0,
+        requested_data: GetReaderConfigRequestedData::All,
+        gpi_port_num: 0,
+        gpo_port_num: 0,
+        custom: vec![],
+    }
+}
+
+/// Builds a message that restores the configuration captured in `snapshot`.
+///
+/// Read-only values in the snapshot (`Identification` and `LLRPConfigurationStateValue`) are not
+/// included, and the reader is not reset to its factory defaults first.
+pub fn restore_config(snapshot: &GetReaderConfigResponse) -> SetReaderConfig {
+    SetReaderConfig {
+        reset_to_factory_default: false,
+        reserved: 0,
+        reader_event_notification_spec: snapshot.reader_event_notification_spec.clone(),
+        antenna_properties: snapshot.antenna_properties.clone(),
+        antenna_configuration: snapshot.antenna_configuration.clone(),
+        ro_report_spec: snapshot.ro_report_spec.clone(),
+        access_report_spec: snapshot.access_report_spec.clone(),
+        keepalive_spec: snapshot.keepalive_spec.clone(),
+        gpo_write_data: snapshot.gpo_write_data.clone(),
+        gpi_port_current_state: snapshot.gpi_port_current_state.clone(),
+        events_and_reports: snapshot.events_and_reports.clone(),
+        custom: snapshot.custom.clone(),
+    }
+}
+
+/// Compares the configuration that would be applied by `desired` against the `current`
+/// configuration of a reader.
+///
+/// Only the parameters included in `desired` are compared. Antenna and GPIO parameters are matched
+/// by their antenna/port number rather than by position.
+pub fn diff_config(
+    current: &GetReaderConfigResponse,
+    desired: &SetReaderConfig,
+) -> Vec<Difference> {
+    let mut output = vec![];
+
+    macro_rules! diff_optional {
+        ($field:ident) => {
+            if desired.$field.is_some() {
+                let path = stringify!($field).into();
+                diff_option(current.$field.as_ref(), desired.$field.as_ref(), path, &mut output);
+            }
+        };
+    }
+
+    diff_optional!(reader_event_notification_spec);
+    diff_optional!(ro_report_spec);
+    diff_optional!(access_report_spec);
+    diff_optional!(keepalive_spec);
+    diff_optional!(events_and_reports);
+
+    diff_keyed(
+        "antenna_properties",
+        &current.antenna_properties,
+        &desired.antenna_properties,
+        |x| x.antenna_id,
+        &mut output,
+    );
+    diff_keyed(
+        "antenna_configuration",
+        &current.antenna_configuration,
+        &desired.antenna_configuration,
+        |x| x.antenna_id,
+        &mut output,
+    );
+    diff_keyed(
+        "gpi_port_current_state",
+        &current.gpi_port_current_state,
+        &desired.gpi_port_current_state,
+        |x| x.gpi_port_num,
+        &mut output,
+    );
+    diff_keyed(
+        "gpo_write_data",
+        &current.gpo_write_data,
+        &desired.gpo_write_data,
+        |x| x.gpo_port_number,
+        &mut output,
+    );
+
+    output
+}
+
+/// Builds a message that applies only the parts of `desired` that differ from the `current`
+/// configuration of a reader, or returns `None` if the reader is already configured as desired.
+///
+/// Parameters are compared in the same way as [`diff_config`]. `Custom` parameters cannot be
+/// compared field by field, so they are included unless an identical parameter is already present.
+/// If `desired` resets the reader to its factory defaults then it is returned unchanged.
+pub fn config_update(
+    current: &GetReaderConfigResponse,
+    desired: &SetReaderConfig,
+) -> Option<SetReaderConfig> {
+    if desired.reset_to_factory_default {
+        return Some(desired.clone());
+    }
+
+    let custom: Vec<_> =
+        desired.custom.iter().filter(|x| !current.custom.contains(x)).cloned().collect();
+    if custom.is_empty() && diff_config(current, desired).is_empty() {
+        return None;
+    }
+
+    macro_rules! changed_optional {
+        ($field:ident) => {
+            match desired.$field != current.$field {
+                true => desired.$field.clone(),
+                false => None,
+            }
+        };
+    }
+
+    Some(SetReaderConfig {
+        reset_to_factory_default: false,
+        reserved: 0,
+        reader_event_notification_spec: changed_optional!(reader_event_notification_spec),
+        antenna_properties: changed_keyed(
+            &current.antenna_properties,
+            &desired.antenna_properties,
+            |x| x.antenna_id,
+        ),
+        antenna_configuration: changed_keyed(
+            &current.antenna_configuration,
+            &desired.antenna_configuration,
+            |x| x.antenna_id,
+        ),
+        ro_report_spec: changed_optional!(ro_report_spec),
+        access_report_spec: changed_optional!(access_report_spec),
+        keepalive_spec: changed_optional!(keepalive_spec),
+        gpo_write_data: changed_keyed(&current.gpo_write_data, &desired.gpo_write_data, |x| {
+            x.gpo_port_number
+        }),
+        gpi_port_current_state: changed_keyed(
+            &current.gpi_port_current_state,
+            &desired.gpi_port_current_state,
+            |x| x.gpi_port_num,
+        ),
+        events_and_reports: changed_optional!(events_and_reports),
+        custom,
+    })
+}
+
+/// Returns the `desired` values that differ from the `current` value with a matching key
+fn changed_keyed<T: PartialEq + Clone>(
+    current: &[T],
+    desired: &[T],
+    key: impl Fn(&T) -> u16,
+) -> Vec<T> {
+    desired
+        .iter()
+        .filter(|value| current.iter().find(|x| key(x) == key(value)) != Some(value))
+        .cloned()
+        .collect()
+}
+
+/// Compares each of the `desired` values against the `current` values with a matching key
+fn diff_keyed<T: Diff + std::fmt::Debug>(
+    name: &str,
+    current: &[T],
+    desired: &[T],
+    key: impl Fn(&T) -> u16,
+    output: &mut Vec<Difference>,
+) {
+    for value in desired {
+        let id = key(value);
+        let path = format!("{}[{}]", name, id);
+        diff_option(current.iter().find(|x| key(x) == id), Some(value), path, output);
+    }
+}
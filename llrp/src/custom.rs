@@ -0,0 +1,191 @@
+//! Support for vendor defined parameters and messages carried inside `Custom` parameters and
+//! `CUSTOM_MESSAGE`s
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    choices::{AccessCommandOpSpec, AccessCommandOpSpecResult},
+    messages::CustomMessage,
+    parameters::Custom,
+    BinaryMessage, LLRPMessage,
+};
+
+/// A vendor defined parameter that is encoded as the data of a `Custom` parameter.
+///
+/// Implementing this trait allows proprietary OpSpecs and OpSpecResults to be used anywhere the
+/// LLRP definitions allow a `Custom` parameter, e.g. `AccessCommand::access_command_op_spec` and
+/// `TagReportData::access_command_op_spec_result`.
+pub trait CustomParameter: Sized {
+    /// The IANA Private Enterprise Number of the vendor that defined the parameter
+    const VENDOR_ID: u32;
+
+    /// The vendor specific subtype of the parameter
+    const SUBTYPE: u32;
+
+    /// Decodes the parameter from the data of a `Custom` parameter
+    fn decode_data(data: &[u8]) -> crate::Result<Self>;
+
+    /// Encodes the parameter as the data of a `Custom` parameter
+    fn encode_data(&self, buffer: &mut Vec<u8>);
+}
+
+impl Custom {
+    /// Wraps a vendor defined parameter in a `Custom` parameter
+    pub fn from_parameter<T: CustomParameter>(value: &T) -> Custom {
+        let mut data = vec![];
+        value.encode_data(&mut data);
+        Custom { vendor_identifier: T::VENDOR_ID, parameter_subtype: T::SUBTYPE, data }
+    }
+
+    /// Returns whether this parameter contains a value of type `T`
+    pub fn is<T: CustomParameter>(&self) -> bool {
+        self.vendor_identifier == T::VENDOR_ID && self.parameter_subtype == T::SUBTYPE
+    }
+
+    /// Decodes the parameter as a `T`, or returns `None` if the vendor identifier or subtype do not
+    /// match
+    pub fn to_parameter<T: CustomParameter>(&self) -> Option<crate::Result<T>> {
+        match self.is::<T>() {
+            true => Some(T::decode_data(&self.data)),
+            false => None,
+        }
+    }
+}
+
+impl AccessCommandOpSpec {
+    /// Creates an OpSpec from a vendor defined OpSpec
+    pub fn custom<T: CustomParameter>(value: &T) -> Self {
+        AccessCommandOpSpec::Custom(Custom::from_parameter(value))
+    }
+}
+
+impl AccessCommandOpSpecResult {
+    /// Decodes the result as a vendor defined OpSpecResult of type `T`, or returns `None` if this
+    /// is not a result of that type
+    pub fn to_custom<T: CustomParameter>(&self) -> Option<crate::Result<T>> {
+        match self {
+            AccessCommandOpSpecResult::Custom(custom) => custom.to_parameter(),
+            _ => None,
+        }
+    }
+}
+
+/// A vendor defined message that is encoded as the data of a `CUSTOM_MESSAGE`, e.g. a proprietary
+/// management command and its response.
+pub trait CustomMessageType: Sized {
+    /// The IANA Private Enterprise Number of the vendor that defined the message
+    const VENDOR_ID: u32;
+
+    /// The vendor specific subtype of the message
+    const SUBTYPE: u8;
+
+    /// Decodes the message from the data of a `CUSTOM_MESSAGE`
+    fn decode_data(data: &[u8]) -> crate::Result<Self>;
+
+    /// Encodes the message as the data of a `CUSTOM_MESSAGE`
+    fn encode_data(&self, buffer: &mut Vec<u8>);
+}
+
+impl CustomMessage {
+    /// Wraps a vendor defined message in a `CUSTOM_MESSAGE`
+    pub fn from_custom<T: CustomMessageType>(value: &T) -> CustomMessage {
+        let mut data = vec![];
+        value.encode_data(&mut data);
+        CustomMessage { vendor_identifier: T::VENDOR_ID, message_subtype: T::SUBTYPE, data }
+    }
+
+    /// Returns whether this message contains a value of type `T`
+    pub fn is<T: CustomMessageType>(&self) -> bool {
+        self.vendor_identifier == T::VENDOR_ID && self.message_subtype == T::SUBTYPE
+    }
+
+    /// Decodes the message as a `T`, or returns `None` if the vendor identifier or subtype do not
+    /// match
+    pub fn to_custom<T: CustomMessageType>(&self) -> Option<crate::Result<T>> {
+        match self.is::<T>() {
+            true => Some(T::decode_data(&self.data)),
+            false => None,
+        }
+    }
+}
+
+type Decoder<T> = Box<dyn Fn(&[u8]) -> crate::Result<T> + Send + Sync>;
+
+/// Decodes `CUSTOM_MESSAGE`s from several vendors into a single type, e.g. an enum with a variant
+/// for each vendor defined message an application handles.
+///
+/// ```
+/// # use llrp::custom::CustomMessageRegistry;
+/// #[derive(Debug)]
+/// enum VendorMessage {
+///     Ping(Vec<u8>),
+/// }
+///
+/// let registry = CustomMessageRegistry::new()
+///     .register_fn(25882, 1, |data| Ok(VendorMessage::Ping(data.to_vec())));
+/// assert!(registry.contains(25882, 1));
+/// ```
+pub struct CustomMessageRegistry<T> {
+    decoders: HashMap<(u32, u8), Decoder<T>>,
+}
+
+impl<T: 'static> CustomMessageRegistry<T> {
+    pub fn new() -> Self {
+        Self { decoders: HashMap::new() }
+    }
+
+    /// Decodes messages of type `M`, converting them with `wrap` (e.g. an enum variant)
+    pub fn register<M: CustomMessageType + 'static>(self, wrap: fn(M) -> T) -> Self {
+        self.register_fn(M::VENDOR_ID, M::SUBTYPE, move |data| M::decode_data(data).map(wrap))
+    }
+
+    /// Decodes the data of messages with a vendor identifier and subtype using `decode`. Replaces
+    /// any decoder previously registered for the same vendor identifier and subtype.
+    pub fn register_fn(
+        mut self,
+        vendor_id: u32,
+        subtype: u8,
+        decode: impl Fn(&[u8]) -> crate::Result<T> + Send + Sync + 'static,
+    ) -> Self {
+        self.decoders.insert((vendor_id, subtype), Box::new(decode));
+        self
+    }
+
+    /// Returns whether a decoder is registered for a vendor identifier and subtype
+    pub fn contains(&self, vendor_id: u32, subtype: u8) -> bool {
+        self.decoders.contains_key(&(vendor_id, subtype))
+    }
+
+    /// Decodes a `CUSTOM_MESSAGE`, or returns `None` if no decoder is registered for its vendor
+    /// identifier and subtype
+    pub fn decode(&self, message: &CustomMessage) -> Option<crate::Result<T>> {
+        let decode = self.decoders.get(&(message.vendor_identifier, message.message_subtype))?;
+        Some(decode(&message.data))
+    }
+
+    /// Decodes a binary message, or returns `None` if it is not a `CUSTOM_MESSAGE` with a
+    /// registered vendor identifier and subtype
+    pub fn decode_message(&self, message: &BinaryMessage) -> Option<crate::Result<T>> {
+        if message.message_type != CustomMessage::ID {
+            return None;
+        }
+        match message.to_message::<CustomMessage>() {
+            Ok(custom) => self.decode(&custom),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+impl<T: 'static> Default for CustomMessageRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for CustomMessageRegistry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut keys: Vec<_> = self.decoders.keys().collect();
+        keys.sort();
+        f.debug_struct("CustomMessageRegistry").field("decoders", &keys).finish()
+    }
+}
@@ -0,0 +1,397 @@
+//! Structured dumps of messages, listing the offset, wire length, raw bytes and decoded value of
+//! every field, similar to the PDML output of packet dissectors such as Wireshark.
+//!
+//! Unlike decoding, dissection keeps the position of each field in the frame, so a dissection can
+//! be compared byte by byte against the output of other tools.
+
+use std::fmt::{self, Write};
+
+use crate::{
+    binary::{BinaryMessage, LLRP_HEADER_LENGTH},
+    layout::{self, FieldKind, FieldLayout},
+    names, Error, FormatHex, Result,
+};
+
+/// A field of a dissected message. Parameters and the message itself are represented as fields
+/// with children.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DissectedField {
+    pub name: String,
+
+    /// The byte offset of the field from the start of the frame
+    pub offset: usize,
+
+    /// The number of bytes the field occupies. For fields that are not a whole number of bytes,
+    /// this is the number of bytes containing the field.
+    pub len: usize,
+
+    /// For fields that are not a whole number of bytes, the first bit of the field within its
+    /// first byte (counting from the most significant bit) and the number of bits
+    pub bits: Option<(u8, u8)>,
+
+    /// The decoded value of the field, empty for messages and parameters
+    pub value: String,
+
+    pub children: Vec<DissectedField>,
+}
+
+/// A message dissected into its fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dissection {
+    frame: Vec<u8>,
+    root: DissectedField,
+}
+
+impl Dissection {
+    /// Dissects a complete frame (header and message)
+    pub fn new(frame: Vec<u8>) -> Result<Dissection> {
+        let root = dissect_message(&frame)?;
+        Ok(Dissection { frame, root })
+    }
+
+    /// Returns the field representing the whole message
+    pub fn root(&self) -> &DissectedField {
+        &self.root
+    }
+
+    pub fn frame(&self) -> &[u8] {
+        &self.frame
+    }
+
+    /// Returns the bytes of the frame that contain a field
+    pub fn raw(&self, field: &DissectedField) -> &[u8] {
+        &self.frame[field.offset..field.offset + field.len]
+    }
+
+    /// Renders the dissection as a PDML document, with the message as a `proto` element and each
+    /// field as a (nested) `field` element with `pos`, `size`, `show` and `value` attributes
+    pub fn to_pdml(&self) -> String {
+        let root = &self.root;
+        let mut out = String::from("<pdml>\n<packet>\n");
+        writeln!(
+            out,
+            r#"  <proto name="llrp" showname="{}" pos="{}" size="{}">"#,
+            escape(&root.name),
+            root.offset,
+            root.len
+        )
+        .unwrap();
+        for child in &root.children {
+            self.write_pdml_field(&mut out, child, 2);
+        }
+        out.push_str("  </proto>\n</packet>\n</pdml>\n");
+        out
+    }
+
+    fn write_pdml_field(&self, out: &mut String, field: &DissectedField, depth: usize) {
+        write!(
+            out,
+            r#"{:indent$}<field name="{}" pos="{}" size="{}" show="{}" value="{}""#,
+            "",
+            escape(&field.name),
+            field.offset,
+            field.len,
+            escape(&field.value),
+            self.raw(field).to_hex(),
+            indent = depth * 2
+        )
+        .unwrap();
+
+        if field.children.is_empty() {
+            out.push_str("/>\n");
+            return;
+        }
+        out.push_str(">\n");
+        for child in &field.children {
+            self.write_pdml_field(out, child, depth + 1);
+        }
+        writeln!(out, "{:indent$}</field>", "", indent = depth * 2).unwrap();
+    }
+}
+
+/// Lists each field on a line with its offset, length, name and value, indented by depth
+impl fmt::Display for Dissection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn write_field(
+            f: &mut fmt::Formatter,
+            dissection: &Dissection,
+            field: &DissectedField,
+            depth: usize,
+        ) -> fmt::Result {
+            write!(
+                f,
+                "{:5} {:5}  {:indent$}{}",
+                field.offset,
+                field.len,
+                "",
+                field.name,
+                indent = depth * 2
+            )?;
+            if let Some((first, count)) = field.bits {
+                write!(f, " (bits {}..{})", first, first as usize + count as usize)?;
+            }
+            if field.children.is_empty() {
+                write!(f, " = {} [{}]", field.value, dissection.raw(field).to_hex())?;
+            }
+            writeln!(f)?;
+
+            for child in &field.children {
+                write_field(f, dissection, child, depth + 1)?;
+            }
+            Ok(())
+        }
+
+        write_field(f, self, &self.root, 0)
+    }
+}
+
+impl BinaryMessage {
+    /// Dissects the message into its fields, see `Dissection`
+    pub fn dissect(&self) -> Result<Dissection> {
+        Dissection::new(self.frame())
+    }
+}
+
+fn dissect_message(frame: &[u8]) -> Result<DissectedField> {
+    let mut cursor = Cursor { data: frame, bit: 0, end: frame.len() };
+    let mut children = vec![];
+
+    cursor.skip(3)?;
+    children.push(cursor.number("version", 3)?);
+    let (message_type_field, message_type) = cursor.field("message_type", 10)?;
+    children.push(message_type_field);
+    children.push(cursor.number("length", 32)?);
+    children.push(cursor.number("id", 32)?);
+    let message_type = message_type as u16;
+
+    match layout::message_fields(message_type) {
+        Some(fields) => {
+            for field in fields {
+                children.push(cursor.layout_field(field)?);
+            }
+            children.extend(cursor.parameters()?);
+        }
+        None if frame.len() > LLRP_HEADER_LENGTH => children.push(cursor.bytes_to_end("data")?),
+        None => {}
+    }
+
+    let name = names::message_name(message_type)
+        .map(String::from)
+        .unwrap_or_else(|| format!("message type {}", message_type));
+    Ok(DissectedField {
+        name,
+        offset: 0,
+        len: frame.len(),
+        bits: None,
+        value: String::new(),
+        children,
+    })
+}
+
+/// A bit position within the bytes of a frame, up to the end of the current message or parameter
+struct Cursor<'a> {
+    data: &'a [u8],
+    bit: usize,
+    end: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn remaining_bits(&self) -> usize {
+        (self.end * 8).saturating_sub(self.bit)
+    }
+
+    fn check(&self, num_bits: usize) -> Result<()> {
+        if num_bits > self.remaining_bits() {
+            return Err(Error::InsufficientData {
+                needed: byte_len(num_bits),
+                remaining: self.remaining_bits() / 8,
+            });
+        }
+        Ok(())
+    }
+
+    fn skip(&mut self, num_bits: usize) -> Result<()> {
+        self.check(num_bits)?;
+        self.bit += num_bits;
+        Ok(())
+    }
+
+    fn read(&mut self, num_bits: usize) -> Result<u64> {
+        self.check(num_bits)?;
+        let mut value = 0;
+        for _ in 0..num_bits {
+            let bit = self.data[self.bit / 8] >> (7 - self.bit % 8) & 1;
+            value = value << 1 | bit as u64;
+            self.bit += 1;
+        }
+        Ok(value)
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        self.check(len * 8)?;
+        let start = self.bit / 8;
+        self.bit += len * 8;
+        Ok(&self.data[start..start + len])
+    }
+
+    /// Returns a field covering the bits from `start` to the current position
+    fn finish(&self, name: &str, start: usize, value: String) -> DissectedField {
+        let num_bits = self.bit - start;
+        let offset = start / 8;
+        let len = byte_len(self.bit) - offset;
+        let first_bit = (start % 8) as u8;
+        let bits = if first_bit != 0 || num_bits != len * 8 {
+            Some((first_bit, num_bits as u8))
+        } else {
+            None
+        };
+        DissectedField { name: name.into(), offset, len, bits, value, children: vec![] }
+    }
+
+    fn field(&mut self, name: &str, num_bits: usize) -> Result<(DissectedField, u64)> {
+        let start = self.bit;
+        let value = self.read(num_bits)?;
+        Ok((self.finish(name, start, value.to_string()), value))
+    }
+
+    fn number(&mut self, name: &str, num_bits: usize) -> Result<DissectedField> {
+        Ok(self.field(name, num_bits)?.0)
+    }
+
+    fn bytes_to_end(&mut self, name: &str) -> Result<DissectedField> {
+        let start = self.bit;
+        let bytes = self.read_bytes(self.remaining_bits() / 8)?;
+        Ok(self.finish(name, start, bytes.to_hex()))
+    }
+
+    fn layout_field(&mut self, field: &FieldLayout) -> Result<DissectedField> {
+        let start = self.bit;
+        let value = match field.kind {
+            FieldKind::Bits(num_bits) => {
+                let value = self.read(num_bits as usize)?;
+                format_value(field, value, num_bits)
+            }
+            FieldKind::Signed(num_bits) => {
+                let shift = 64 - num_bits as u32;
+                let value = (self.read(num_bits as usize)? << shift) as i64 >> shift;
+                value.to_string()
+            }
+            FieldKind::U96 => self.read_bytes(12)?.to_hex(),
+            FieldKind::BitArray => {
+                let num_bits = self.read(16)? as usize;
+                let bytes = self.read_bytes(byte_len(num_bits))?;
+                format!("{} bits: {}", num_bits, bytes.to_hex())
+            }
+            FieldKind::Utf8 => {
+                let len = self.read(16)? as usize;
+                format!("{:?}", String::from_utf8_lossy(self.read_bytes(len)?))
+            }
+            FieldKind::Array(item_bits) => {
+                let count = self.read(16)? as usize;
+                self.check(count * item_bits as usize)?;
+                if item_bits == 8 && field.variants.is_empty() {
+                    self.read_bytes(count)?.to_hex()
+                } else {
+                    let mut items = vec![];
+                    for _ in 0..count {
+                        items.push(format_value(field, self.read(item_bits as usize)?, item_bits));
+                    }
+                    format!("[{}]", items.join(", "))
+                }
+            }
+            FieldKind::BytesToEnd => self.read_bytes(self.remaining_bits() / 8)?.to_hex(),
+        };
+        Ok(self.finish(field.name, start, value))
+    }
+
+    /// Dissects the parameters from the current position to the end
+    fn parameters(&mut self) -> Result<Vec<DissectedField>> {
+        let mut params = vec![];
+        while self.remaining_bits() > 0 {
+            params.push(self.parameter()?);
+        }
+        Ok(params)
+    }
+
+    fn parameter(&mut self) -> Result<DissectedField> {
+        let start = self.bit;
+        let mut children = vec![];
+
+        let is_tv = self.read(1)? == 1;
+        let type_num = if is_tv {
+            let (type_field, tv_id) = self.field("type", 7)?;
+            children.push(type_field);
+            let fields = layout::tv_fields(tv_id as u8).ok_or(Error::InvalidType(tv_id as u16))?;
+            for field in fields {
+                children.push(self.layout_field(field)?);
+            }
+            tv_id as u16
+        } else {
+            self.skip(5)?;
+            let (type_field, type_num) = self.field("type", 10)?;
+            children.push(type_field);
+            let (length_field, length) = self.field("length", 16)?;
+            children.push(length_field);
+
+            let param_end = start / 8 + length as usize;
+            if length < 4 || param_end > self.end {
+                return Err(Error::TlvParameterLengthInvalid(length as u16));
+            }
+
+            let outer_end = std::mem::replace(&mut self.end, param_end);
+            match layout::parameter_fields(type_num as u16) {
+                Some(fields) => {
+                    for field in fields {
+                        children.push(self.layout_field(field)?);
+                    }
+                    children.extend(self.parameters()?);
+                }
+                None if self.remaining_bits() > 0 => children.push(self.bytes_to_end("data")?),
+                None => {}
+            }
+            self.end = outer_end;
+            type_num as u16
+        };
+
+        let name = names::parameter_name(type_num)
+            .map(String::from)
+            .unwrap_or_else(|| format!("parameter type {}", type_num));
+        Ok(DissectedField { children, ..self.finish(&name, start, String::new()) })
+    }
+}
+
+/// Returns the number of bytes needed to hold a number of bits
+fn byte_len(num_bits: usize) -> usize {
+    let whole_bytes = num_bits / 8;
+    if whole_bytes * 8 < num_bits {
+        whole_bytes + 1
+    } else {
+        whole_bytes
+    }
+}
+
+fn format_value(field: &FieldLayout, value: u64, num_bits: u8) -> String {
+    if let Some((_, name)) = field.variants.iter().find(|(v, _)| *v as u64 == value) {
+        return format!("{} ({})", name, value);
+    }
+    match (num_bits, field.hex) {
+        (1, _) => (value == 1).to_string(),
+        (_, true) => format!("0x{:x}", value),
+        _ => value.to_string(),
+    }
+}
+
+/// Escapes a string for use in an XML attribute
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,156 @@
+//! Composable filters over a stream of tag reads
+//!
+//! Filters can be chained with `and`, e.g. to only accept reads of tags with a particular EPC
+//! prefix, seen on antennas 1 or 2 and reported at most once a second:
+//!
+//! ```
+//! use std::time::Duration;
+//! use llrp::filter::{Antennas, EpcMask, RateLimit, TagFilter};
+//!
+//! let mut filter = EpcMask::prefix(&[0xe2, 0x80])
+//!     .and(Antennas::new(&[1, 2]))
+//!     .and(RateLimit::new(Duration::from_secs(1)));
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, SystemTime},
+};
+
+use crate::{parameters::TagReportData, report::Epc};
+
+/// A predicate over tag reads. Filters may be stateful (e.g. `RateLimit`), so each read should
+/// only be passed to a filter once.
+pub trait TagFilter {
+    /// Returns whether a read of a tag at time `now` should be kept
+    fn accept(&mut self, data: &TagReportData, now: SystemTime) -> bool;
+
+    /// Returns a filter that accepts reads accepted by both `self` and `other`. `other` is only
+    /// checked if `self` accepts the read.
+    fn and<F: TagFilter>(self, other: F) -> And<Self, F>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    /// Returns the reads in `tags` that are accepted by the filter
+    fn filter<'a>(&mut self, tags: &'a [TagReportData], now: SystemTime) -> Vec<&'a TagReportData> {
+        tags.iter().filter(|data| self.accept(data, now)).collect()
+    }
+}
+
+impl<F> TagFilter for F
+where
+    F: FnMut(&TagReportData, SystemTime) -> bool,
+{
+    fn accept(&mut self, data: &TagReportData, now: SystemTime) -> bool {
+        self(data, now)
+    }
+}
+
+/// A filter that accepts reads accepted by both of its filters, see `TagFilter::and`
+#[derive(Debug, Clone)]
+pub struct And<A, B>(A, B);
+
+impl<A: TagFilter, B: TagFilter> TagFilter for And<A, B> {
+    fn accept(&mut self, data: &TagReportData, now: SystemTime) -> bool {
+        self.0.accept(data, now) && self.1.accept(data, now)
+    }
+}
+
+/// Accepts tags with an EPC that matches a pattern in the bits selected by a mask
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EpcMask {
+    pattern: Vec<u8>,
+    mask: Vec<u8>,
+}
+
+impl EpcMask {
+    /// Creates a filter matching the bits of `pattern` that are set in `mask`. EPCs shorter than
+    /// the pattern never match.
+    pub fn new(pattern: &[u8], mask: &[u8]) -> Self {
+        assert_eq!(pattern.len(), mask.len(), "pattern and mask must be the same length");
+        Self { pattern: pattern.to_vec(), mask: mask.to_vec() }
+    }
+
+    /// Creates a filter matching EPCs that start with `prefix`
+    pub fn prefix(prefix: &[u8]) -> Self {
+        Self::new(prefix, &vec![0xff; prefix.len()])
+    }
+
+    pub fn matches(&self, epc: &[u8]) -> bool {
+        epc.len() >= self.pattern.len()
+            && epc
+                .iter()
+                .zip(self.pattern.iter().zip(&self.mask))
+                .all(|(epc, (pattern, mask))| epc & mask == pattern & mask)
+    }
+}
+
+impl TagFilter for EpcMask {
+    fn accept(&mut self, data: &TagReportData, _: SystemTime) -> bool {
+        self.matches(data.epc_parameter.bytes())
+    }
+}
+
+/// Accepts reads from a set of antennas
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Antennas(HashSet<u16>);
+
+impl Antennas {
+    pub fn new(antenna_ids: &[u16]) -> Self {
+        Self(antenna_ids.iter().copied().collect())
+    }
+}
+
+impl TagFilter for Antennas {
+    fn accept(&mut self, data: &TagReportData, _: SystemTime) -> bool {
+        data.antenna_id.map(|id| self.0.contains(&id)) == Some(true)
+    }
+}
+
+/// Accepts reads with a peak RSSI of at least the given value in dBm. Reads without an RSSI are
+/// rejected.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MinRssi(pub i8);
+
+impl TagFilter for MinRssi {
+    fn accept(&mut self, data: &TagReportData, _: SystemTime) -> bool {
+        data.peak_rssi.map(|rssi| rssi >= self.0) == Some(true)
+    }
+}
+
+/// Accepts at most one read of each EPC per interval
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    interval: Duration,
+    last_accepted: HashMap<Epc, SystemTime>,
+}
+
+impl RateLimit {
+    pub fn new(interval: Duration) -> Self {
+        Self { interval, last_accepted: HashMap::new() }
+    }
+
+    /// Forgets EPCs that were last accepted more than one interval before `now`, which bounds the
+    /// memory used when many different tags are read
+    pub fn remove_expired(&mut self, now: SystemTime) {
+        let interval = self.interval;
+        self.last_accepted
+            .retain(|_, last| now.duration_since(*last).unwrap_or_default() < interval);
+    }
+}
+
+impl TagFilter for RateLimit {
+    fn accept(&mut self, data: &TagReportData, now: SystemTime) -> bool {
+        let epc = data.epc();
+        if let Some(last) = self.last_accepted.get(&epc) {
+            if now.duration_since(*last).unwrap_or_default() < self.interval {
+                return false;
+            }
+        }
+        self.last_accepted.insert(epc, now);
+        true
+    }
+}
@@ -0,0 +1,128 @@
+//! Hex dumps of binary messages for debugging
+
+use std::fmt::Write;
+
+use crate::binary::{encode_header, BinaryMessage, LLRP_HEADER_LENGTH};
+
+const BYTES_PER_LINE: usize = 16;
+
+impl BinaryMessage {
+    /// Returns a dump of the full frame (header and message), with the offset, hex and ASCII
+    /// representation of each 16 byte line
+    pub fn hexdump(&self) -> String {
+        let mut out = String::new();
+        dump_lines(&mut out, 0, &self.frame());
+        out
+    }
+
+    /// Returns a dump of the full frame like `hexdump`, with labels for the header fields and
+    /// the boundaries of the top-level TLV parameters.
+    ///
+    /// The parameters start after the fields of the message, if they have a fixed size. Otherwise
+    /// they are located by searching for the first offset from which the rest of the message is a
+    /// chain of well-formed TLV headers. If no such offset exists, or the rest of the message is
+    /// not a chain of parameters, the body is dumped as a single block.
+    pub fn annotated_hexdump(&self) -> String {
+        let frame = self.frame();
+        let length = frame.len();
+
+        let mut out = String::new();
+        writeln!(
+            out,
+            "; header: version {}, message type {}{}, length {}, id {}",
+            self.ver,
+            self.message_type,
+            name_suffix(crate::names::message_name(self.message_type)),
+            length,
+            self.id
+        )
+        .unwrap();
+        dump_lines(&mut out, 0, &frame[..LLRP_HEADER_LENGTH]);
+
+        let body = &self.value;
+        if body.is_empty() {
+            return out;
+        }
+        let start = match crate::layout::message_fields_len(self.message_type) {
+            Some(len) => Some(len).filter(|&len| len <= body.len()),
+            None => (0..body.len()).find(|&start| tlv_chain(&body[start..]).is_some()),
+        };
+        let start = match start.filter(|&start| tlv_chain(&body[start..]).is_some()) {
+            Some(start) => start,
+            None => {
+                out.push_str("; body\n");
+                dump_lines(&mut out, LLRP_HEADER_LENGTH, body);
+                return out;
+            }
+        };
+
+        if start > 0 {
+            out.push_str("; fields\n");
+            dump_lines(&mut out, LLRP_HEADER_LENGTH, &body[..start]);
+        }
+
+        let mut offset = start;
+        for (type_num, len) in tlv_chain(&body[start..]).unwrap() {
+            let name = name_suffix(crate::names::parameter_name(type_num));
+            writeln!(out, "; parameter type {}{}, length {}", type_num, name, len).unwrap();
+            dump_lines(&mut out, LLRP_HEADER_LENGTH + offset, &body[offset..offset + len]);
+            offset += len;
+        }
+
+        out
+    }
+
+    pub(crate) fn frame(&self) -> Vec<u8> {
+        let length = self.value.len() + LLRP_HEADER_LENGTH;
+        let mut frame = encode_header(self.ver, self.message_type, length, self.id).to_vec();
+        frame.extend_from_slice(&self.value);
+        frame
+    }
+}
+
+fn name_suffix(name: Option<&str>) -> String {
+    name.map(|name| format!(" ({})", name)).unwrap_or_default()
+}
+
+/// Returns the type and length of each parameter if `bytes` consists entirely of TLV parameters
+fn tlv_chain(mut bytes: &[u8]) -> Option<Vec<(u16, usize)>> {
+    let mut params = vec![];
+    while !bytes.is_empty() {
+        if bytes.len() < 4 || bytes[0] & 0b1111_1100 != 0 {
+            return None;
+        }
+        let type_num = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let len = u16::from_be_bytes([bytes[2], bytes[3]]) as usize;
+        if len < 4 || len > bytes.len() {
+            return None;
+        }
+        params.push((type_num, len));
+        bytes = &bytes[len..];
+    }
+    Some(params)
+}
+
+fn dump_lines(out: &mut String, offset: usize, bytes: &[u8]) {
+    for (i, line) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+        write!(out, "{:08x} ", offset + i * BYTES_PER_LINE).unwrap();
+        for j in 0..BYTES_PER_LINE {
+            if j % 8 == 0 {
+                out.push(' ');
+            }
+            match line.get(j) {
+                Some(byte) => write!(out, "{:02x} ", byte).unwrap(),
+                None => out.push_str("   "),
+            }
+        }
+
+        out.push_str(" |");
+        out.extend(line.iter().map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        }));
+        out.push_str("|\n");
+    }
+}
@@ -0,0 +1,82 @@
+//! A writer for streaming decoded messages as newline delimited JSON
+
+use std::io;
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{parameters::TagReportData, BinaryMessage, FormatHex};
+
+/// Writes messages and tag reports to an underlying writer as one JSON object per line.
+pub struct JsonLinesWriter<W: io::Write> {
+    writer: W,
+    include_raw: bool,
+    reader: Option<String>,
+}
+
+impl<W: io::Write> JsonLinesWriter<W> {
+    pub fn new(writer: W) -> JsonLinesWriter<W> {
+        JsonLinesWriter { writer, include_raw: false, reader: None }
+    }
+
+    /// Include the raw payload of each message as a hex string in the `raw` field
+    pub fn include_raw(mut self, include_raw: bool) -> Self {
+        self.include_raw = include_raw;
+        self
+    }
+
+    /// Tag every line with the identity of the reader that the data came from
+    pub fn reader(mut self, reader: impl Into<String>) -> Self {
+        self.reader = Some(reader.into());
+        self
+    }
+
+    /// Decodes `message` and writes it as a single line.
+    ///
+    /// Messages that fail to decode are still written, with the error in the `error` field instead
+    /// of the decoded message.
+    pub fn write_message(&mut self, message: &BinaryMessage) -> io::Result<()> {
+        let mut line = json!({
+            "message_type": message.message_type,
+            "id": message.id,
+        });
+
+        match message.to_dynamic_message() {
+            Ok(decoded) => line["message"] = to_value(&decoded)?,
+            Err(e) => line["error"] = format!("{:?}", e).into(),
+        }
+
+        if self.include_raw {
+            line["raw"] = message.value.to_hex().into();
+        }
+
+        self.write_line(line)
+    }
+
+    /// Writes a single tag report as a line
+    pub fn write_tag_report(&mut self, data: &TagReportData) -> io::Result<()> {
+        self.write_line(json!({ "tag_report_data": to_value(data)? }))
+    }
+
+    /// Flushes the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_line(&mut self, mut line: serde_json::Value) -> io::Result<()> {
+        if let Some(reader) = &self.reader {
+            line["reader"] = reader.as_str().into();
+        }
+        serde_json::to_writer(&mut self.writer, &line)?;
+        self.writer.write_all(b"\n")
+    }
+}
+
+fn to_value(value: &impl Serialize) -> io::Result<serde_json::Value> {
+    serde_json::to_value(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
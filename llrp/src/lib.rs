@@ -1,8 +1,44 @@
+pub mod access;
 mod binary;
+pub mod clock;
+pub mod compliance;
+pub mod config;
+pub mod custom;
+pub mod dissect;
+pub mod filter;
+mod hexdump;
+#[cfg(feature = "json")]
+pub mod jsonl;
+pub mod portal;
+pub mod prelude;
+pub mod presence;
+#[cfg(feature = "decode_profile")]
+pub mod profile;
+pub mod report;
+pub mod rssi;
+pub mod schedule;
+pub mod select;
+pub mod spec_id;
+pub mod spill;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod stats;
+mod summary;
+mod timestamp;
+mod trigger;
+pub mod units;
+pub mod version;
 
+#[cfg(test)]
+#[path = "../benches/fixtures/mod.rs"]
+mod fixtures;
 #[cfg(test)]
 mod tests;
 
-pub use crate::binary::{read_message, write_message, BinaryMessage};
+pub use crate::binary::{
+    encode_message, read_dynamic_message, read_message, read_message_into, read_message_resync,
+    read_raw_message, write_message, BinaryMessage, MessageHeader, RawMessage, VersionCheck,
+    VersionPolicy,
+};
 
 include!(concat!(env!("OUT_DIR"), "/llrp_generated.rs"));
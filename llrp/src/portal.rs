@@ -0,0 +1,148 @@
+//! Inferring the direction tags travel through a portal with antennas on each side
+
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use crate::{parameters::TagReportData, report::Epc};
+
+/// The side of a portal an antenna faces
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Side {
+    Inside,
+    Outside,
+}
+
+/// The direction a tag travelled through a portal
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Direction {
+    /// From the outside to the inside
+    In,
+
+    /// From the inside to the outside
+    Out,
+}
+
+/// A tag that passed through the portal
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PortalEvent {
+    pub epc: Epc,
+    pub direction: Direction,
+
+    /// The time of the first read of the tag during the pass
+    pub first_seen: SystemTime,
+
+    /// The time of the last read of the tag during the pass
+    pub last_seen: SystemTime,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Peak {
+    time: SystemTime,
+    rssi: i8,
+}
+
+#[derive(Debug, Clone)]
+struct Pass {
+    first_seen: SystemTime,
+    last_seen: SystemTime,
+    inside: Option<Peak>,
+    outside: Option<Peak>,
+}
+
+/// Infers the direction tags travel through a portal from the antennas they are read on.
+///
+/// Each antenna is mapped to the side of the portal it faces. A tag is closest to the antennas on
+/// a side when it is read with the highest RSSI on that side, so the order of the strongest read
+/// on each side gives the direction of travel. A pass ends once a tag has not been read for
+/// `timeout`, and is only reported if the tag was read on both sides.
+#[derive(Debug, Clone)]
+pub struct DirectionDetector {
+    timeout: Duration,
+    sides: HashMap<u16, Side>,
+    passes: HashMap<Epc, Pass>,
+}
+
+impl DirectionDetector {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout, sides: HashMap::new(), passes: HashMap::new() }
+    }
+
+    /// Sets the side of the portal that an antenna faces. Reads from antennas without a side are
+    /// ignored.
+    pub fn antenna(mut self, antenna_id: u16, side: Side) -> Self {
+        self.sides.insert(antenna_id, side);
+        self
+    }
+
+    /// Records a read of a tag at time `now`. Reads without an antenna or RSSI are ignored.
+    pub fn observe(&mut self, data: &TagReportData, now: SystemTime) {
+        let (side, rssi) =
+            match (data.antenna_id.and_then(|id| self.sides.get(&id)), data.peak_rssi) {
+                (Some(&side), Some(rssi)) => (side, rssi),
+                _ => return,
+            };
+
+        let pass = self.passes.entry(data.epc()).or_insert(Pass {
+            first_seen: now,
+            last_seen: now,
+            inside: None,
+            outside: None,
+        });
+        pass.first_seen = pass.first_seen.min(now);
+        pass.last_seen = pass.last_seen.max(now);
+
+        let peak = match side {
+            Side::Inside => &mut pass.inside,
+            Side::Outside => &mut pass.outside,
+        };
+        match *peak {
+            Some(existing) if existing.rssi >= rssi => {}
+            _ => *peak = Some(Peak { time: now, rssi }),
+        }
+    }
+
+    /// Ends the passes of tags that have not been read for longer than the timeout at time
+    /// `now`, returning an event for each tag with a known direction, ordered by the time the tag
+    /// was last seen
+    pub fn expire(&mut self, now: SystemTime) -> Vec<PortalEvent> {
+        let timeout = self.timeout;
+        let mut events = vec![];
+        self.passes.retain(|epc, pass| {
+            if now.duration_since(pass.last_seen).unwrap_or_default() <= timeout {
+                return true;
+            }
+            if let Some(direction) = pass.direction() {
+                events.push(PortalEvent {
+                    epc: epc.clone(),
+                    direction,
+                    first_seen: pass.first_seen,
+                    last_seen: pass.last_seen,
+                });
+            }
+            false
+        });
+
+        events.sort_by(|a, b| (a.last_seen, a.epc.bytes()).cmp(&(b.last_seen, b.epc.bytes())));
+        events
+    }
+
+    /// Returns the number of tags that are currently passing through the portal
+    pub fn num_passing(&self) -> usize {
+        self.passes.len()
+    }
+}
+
+impl Pass {
+    fn direction(&self) -> Option<Direction> {
+        let (inside, outside) = (self.inside?, self.outside?);
+        if outside.time < inside.time {
+            Some(Direction::In)
+        } else if inside.time < outside.time {
+            Some(Direction::Out)
+        } else {
+            None
+        }
+    }
+}
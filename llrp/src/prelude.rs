@@ -0,0 +1,16 @@
+//! Re-exports of the types and traits needed to decode and encode messages
+//!
+//! ```
+//! use llrp::prelude::*;
+//!
+//! let keepalive = [0x04, 0x3e, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x07];
+//! let raw = read_message(&keepalive[..]).unwrap();
+//! assert_eq!(raw.message_type, Keepalive::ID);
+//! assert!(matches!(raw.to_dynamic_message().unwrap(), Message::Keepalive(_)));
+//! ```
+
+pub use crate::{
+    choices::*, enumerations::*, messages::*, parameters::*, read_message, write_message,
+    BinaryMessage, BitArray, Error, FormatHex, LLRPEnumeration, LLRPMessage, LLRPRequest,
+    LLRPResponse, LLRPValue, Result, TlvParameter,
+};
@@ -0,0 +1,164 @@
+//! Tracking which tags are present from a stream of tag reports
+
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use crate::{messages::RoAccessReport, parameters::TagReportData, report::Epc};
+
+/// A change in the set of tags that are present
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PresenceEvent {
+    /// A tag has been read enough times to be considered present
+    TagArrived {
+        epc: Epc,
+        /// The antenna the tag arrived at (`None` unless tracking per antenna)
+        antenna_id: Option<u16>,
+        /// The time of the first read of the tag
+        first_seen: SystemTime,
+    },
+
+    /// A tag has not been read for longer than the timeout
+    TagDeparted {
+        epc: Epc,
+        /// The antenna the tag departed from (`None` unless tracking per antenna)
+        antenna_id: Option<u16>,
+        /// The time of the last read of the tag
+        last_seen: SystemTime,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct TagState {
+    first_seen: SystemTime,
+    last_seen: SystemTime,
+    reads: u32,
+    present: bool,
+}
+
+/// Tracks which tags are present, emitting an event when a tag arrives or departs.
+///
+/// A tag arrives once it has been read `min_reads` times without a gap longer than the timeout,
+/// which filters out stray reads, and departs once it has not been read for longer than the
+/// timeout. Times are provided by the caller, so reader timestamps can be used (e.g. converted
+/// with `ClockEstimator::to_local`) as well as the time reports were received.
+#[derive(Debug, Clone)]
+pub struct PresenceTracker {
+    timeout: Duration,
+    min_reads: u32,
+    per_antenna: bool,
+    tags: HashMap<(Epc, Option<u16>), TagState>,
+}
+
+impl PresenceTracker {
+    /// Creates a tracker where tags depart after not being read for `timeout`
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout, min_reads: 1, per_antenna: false, tags: HashMap::new() }
+    }
+
+    /// Sets the number of reads required before a tag is considered present (default 1)
+    pub fn min_reads(mut self, min_reads: u32) -> Self {
+        self.min_reads = min_reads.max(1);
+        self
+    }
+
+    /// Tracks the presence of tags at each antenna separately (default false)
+    pub fn per_antenna(mut self, per_antenna: bool) -> Self {
+        self.per_antenna = per_antenna;
+        self
+    }
+
+    /// Records a read of a tag at time `now`, returning an event if the tag has just arrived.
+    ///
+    /// Tags that have timed out are not removed until `expire` is called, so a tag that is read
+    /// again after the timeout but before `expire` is still considered present.
+    pub fn observe(&mut self, data: &TagReportData, now: SystemTime) -> Option<PresenceEvent> {
+        let antenna_id = if self.per_antenna { data.antenna_id } else { None };
+        let reads = data.tag_seen_count.map_or(1, |count| count.max(1) as u32);
+        let key = (data.epc(), antenna_id);
+
+        let timeout = self.timeout;
+        let state = self.tags.entry(key.clone()).or_insert(TagState {
+            first_seen: now,
+            last_seen: now,
+            reads: 0,
+            present: false,
+        });
+
+        // Reads of a tag that is not yet present only count if they are close together
+        if !state.present && elapsed(state.last_seen, now) > timeout {
+            *state = TagState { first_seen: now, last_seen: now, reads: 0, present: false };
+        }
+
+        state.last_seen = state.last_seen.max(now);
+        state.reads = state.reads.saturating_add(reads);
+        if state.present || state.reads < self.min_reads {
+            return None;
+        }
+
+        state.present = true;
+        let (epc, antenna_id) = key;
+        Some(PresenceEvent::TagArrived { epc, antenna_id, first_seen: state.first_seen })
+    }
+
+    /// Records all of the tags in a report, returning the tags that arrived
+    pub fn observe_report(
+        &mut self,
+        report: &RoAccessReport,
+        now: SystemTime,
+    ) -> Vec<PresenceEvent> {
+        report.tag_report_data.iter().filter_map(|data| self.observe(data, now)).collect()
+    }
+
+    /// Removes tags that have not been read for longer than the timeout at time `now`, returning
+    /// an event for each tag that departed, ordered by the time it was last seen
+    pub fn expire(&mut self, now: SystemTime) -> Vec<PresenceEvent> {
+        let timeout = self.timeout;
+        let mut departed = vec![];
+        self.tags.retain(|(epc, antenna_id), state| {
+            if elapsed(state.last_seen, now) <= timeout {
+                return true;
+            }
+            if state.present {
+                departed.push((state.last_seen, epc.clone(), *antenna_id));
+            }
+            false
+        });
+
+        departed.sort_by(|a, b| (a.0, a.1.bytes(), a.2).cmp(&(b.0, b.1.bytes(), b.2)));
+        departed
+            .into_iter()
+            .map(|(last_seen, epc, antenna_id)| PresenceEvent::TagDeparted {
+                epc,
+                antenna_id,
+                last_seen,
+            })
+            .collect()
+    }
+
+    /// Returns whether a tag is currently present (at `antenna_id` if tracking per antenna)
+    pub fn is_present(&self, epc: &Epc, antenna_id: Option<u16>) -> bool {
+        let antenna_id = if self.per_antenna { antenna_id } else { None };
+        self.tags.get(&(epc.clone(), antenna_id)).map(|state| state.present) == Some(true)
+    }
+
+    /// Returns the tags that are currently present, with the antenna they are present at if
+    /// tracking per antenna
+    pub fn present(&self) -> impl Iterator<Item = (&Epc, Option<u16>)> {
+        self.tags
+            .iter()
+            .filter(|(_, state)| state.present)
+            .map(|((epc, antenna), _)| (epc, *antenna))
+    }
+
+    /// Removes all tags without emitting any events
+    pub fn clear(&mut self) {
+        self.tags.clear();
+    }
+}
+
+/// Returns the time from `earlier` to `later`, or zero if `later` is before `earlier`
+fn elapsed(earlier: SystemTime, later: SystemTime) -> Duration {
+    later.duration_since(earlier).unwrap_or_default()
+}
@@ -0,0 +1,66 @@
+//! Per parameter type decoding statistics, enabled with the `decode_profile` feature
+//!
+//! Statistics are collected separately for each thread, so they should be read from the thread
+//! that decodes the messages.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::ParameterType;
+
+/// The number of times parameters of a particular type were decoded and the time spent doing so
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecodeStats {
+    /// The number of parameters of this type that were decoded
+    pub count: u64,
+
+    /// The total time spent decoding parameters of this type, including the time spent decoding
+    /// their sub-parameters
+    pub total_time: Duration,
+}
+
+thread_local! {
+    static STATS: RefCell<HashMap<ParameterType, DecodeStats>> = RefCell::new(HashMap::new());
+}
+
+/// Returns the statistics collected on the current thread since the last call to
+/// `reset_decode_stats`, sorted by the total time spent decoding each parameter type (largest
+/// first)
+pub fn decode_stats() -> Vec<(ParameterType, DecodeStats)> {
+    let mut stats: Vec<_> =
+        STATS.with(|stats| stats.borrow().iter().map(|(&ty, &stats)| (ty, stats)).collect());
+    stats.sort_by(|a, b| b.1.total_time.cmp(&a.1.total_time).then(a.0.cmp(&b.0)));
+    stats
+}
+
+/// Clears the statistics collected on the current thread
+pub fn reset_decode_stats() {
+    STATS.with(|stats| stats.borrow_mut().clear());
+}
+
+/// Records the time between its creation and when it is dropped against a parameter type
+pub(crate) struct Timer {
+    ty: ParameterType,
+    start: Instant,
+}
+
+impl Timer {
+    pub(crate) fn start(ty: ParameterType) -> Self {
+        Self { ty, start: Instant::now() }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        STATS.with(|stats| {
+            let mut stats = stats.borrow_mut();
+            let entry = stats.entry(self.ty).or_default();
+            entry.count += 1;
+            entry.total_time += elapsed;
+        });
+    }
+}
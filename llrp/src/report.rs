@@ -0,0 +1,255 @@
+//! Convenience types for consuming tag reports
+
+use std::{
+    collections::HashSet,
+    hash::{Hash, Hasher},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use crate::{
+    choices::{AccessCommandOpSpecResult, EPCParameter},
+    parameters::{EPCData, TagReportData},
+    BitArray, Decoder, Encoder, FormatHex, LLRPValue,
+};
+
+impl EPCParameter {
+    /// Returns the bytes of the EPC, regardless of how it was encoded
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            EPCParameter::EPCData(value) => &value.epc.bytes,
+            EPCParameter::EPC_96(value) => value,
+        }
+    }
+
+    /// Returns the EPC formatted as a lowercase hex string
+    pub fn to_hex(&self) -> String {
+        self.bytes().to_hex()
+    }
+}
+
+/// An EPC, independent of whether it is encoded as an `EPC-96` or an `EPCData` parameter.
+///
+/// The wire form is remembered, so a decoded EPC is encoded back the same way. EPCs created with
+/// `Epc::new` use the compact `EPC-96` form when they are exactly 96 bits long. The wire form is
+/// not part of the identity of the EPC: the same tag reported in either form compares equal.
+#[derive(Debug, Clone)]
+pub struct Epc {
+    bytes: Vec<u8>,
+    num_bits: u16,
+    epc_96: bool,
+}
+
+/// The maximum length of an EPC in bytes, limited by the 16-bit bit count of `EPCData`
+pub const MAX_EPC_BYTES: usize = u16::MAX as usize / 8;
+
+impl Epc {
+    /// Creates an EPC from its bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes` is longer than `MAX_EPC_BYTES`.
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        let bytes = bytes.into();
+        assert!(bytes.len() <= MAX_EPC_BYTES, "EPC of {} bytes is too long", bytes.len());
+        let num_bits = (bytes.len() * 8) as u16;
+        Epc { bytes, num_bits, epc_96: num_bits == 96 }
+    }
+
+    /// Returns the bytes of the EPC
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the length of the EPC in bits
+    pub fn bit_len(&self) -> u16 {
+        self.num_bits
+    }
+
+    /// Returns whether the EPC is encoded as an `EPC-96` parameter
+    pub fn is_epc_96(&self) -> bool {
+        self.epc_96
+    }
+
+    /// Returns the EPC formatted as a lowercase hex string
+    pub fn to_hex(&self) -> String {
+        self.bytes.to_hex()
+    }
+
+    /// Parses an EPC from a hex string, e.g. one returned by `to_hex`. Returns `None` if the
+    /// string is not valid hex or the EPC is longer than `MAX_EPC_BYTES`.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let bytes = crate::parse_hex(hex)?;
+        match bytes.len() <= MAX_EPC_BYTES {
+            true => Some(Epc::new(bytes)),
+            false => None,
+        }
+    }
+}
+
+impl PartialEq for Epc {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes && self.num_bits == other.num_bits
+    }
+}
+
+impl Eq for Epc {}
+
+impl Hash for Epc {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bytes.hash(state);
+        self.num_bits.hash(state);
+    }
+}
+
+impl From<&EPCParameter> for Epc {
+    fn from(value: &EPCParameter) -> Self {
+        match value {
+            EPCParameter::EPCData(value) => {
+                Epc { bytes: value.epc.bytes.clone(), num_bits: value.epc.num_bits, epc_96: false }
+            }
+            EPCParameter::EPC_96(value) => {
+                Epc { bytes: value.to_vec(), num_bits: 96, epc_96: true }
+            }
+        }
+    }
+}
+
+impl From<EPCParameter> for Epc {
+    fn from(value: EPCParameter) -> Self {
+        Epc::from(&value)
+    }
+}
+
+impl From<&Epc> for EPCParameter {
+    fn from(value: &Epc) -> Self {
+        let mut epc_96 = [0; 12];
+        match value.epc_96 && value.bytes.len() == epc_96.len() {
+            true => {
+                epc_96.copy_from_slice(&value.bytes);
+                EPCParameter::EPC_96(epc_96)
+            }
+            false => EPCParameter::EPCData(EPCData {
+                epc: BitArray { num_bits: value.num_bits, bytes: value.bytes.clone() },
+            }),
+        }
+    }
+}
+
+impl From<Epc> for EPCParameter {
+    fn from(value: Epc) -> Self {
+        EPCParameter::from(&value)
+    }
+}
+
+impl LLRPValue for Epc {
+    fn can_decode_type(type_num: u16) -> bool {
+        EPCParameter::can_decode_type(type_num)
+    }
+
+    fn decode(decoder: &mut Decoder) -> crate::Result<Self> {
+        Ok(decoder.read::<EPCParameter>()?.into())
+    }
+
+    fn decode_type(decoder: &mut Decoder, type_num: u16) -> crate::Result<Self> {
+        Ok(EPCParameter::decode_type(decoder, type_num)?.into())
+    }
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.write(&EPCParameter::from(self))
+    }
+}
+
+impl TagReportData {
+    /// Returns the EPC of the tag
+    pub fn epc(&self) -> Epc {
+        Epc::from(&self.epc_parameter)
+    }
+}
+
+/// Interns EPCs so that structures holding many observations of the same tags can share a single
+/// copy of each EPC.
+#[derive(Debug, Clone, Default)]
+pub struct EpcCache {
+    epcs: HashSet<Arc<[u8]>>,
+}
+
+impl EpcCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the shared copy of `epc`, adding it to the cache if it has not been seen before
+    pub fn intern(&mut self, epc: &[u8]) -> Arc<[u8]> {
+        if let Some(existing) = self.epcs.get(epc) {
+            return existing.clone();
+        }
+        let epc: Arc<[u8]> = epc.into();
+        self.epcs.insert(epc.clone());
+        epc
+    }
+
+    /// Returns the shared copy of the EPC in `epc`
+    pub fn intern_parameter(&mut self, epc: &EPCParameter) -> Arc<[u8]> {
+        self.intern(epc.bytes())
+    }
+
+    /// Removes `epc` from the cache, returning whether it was present. Copies that are still in use
+    /// remain valid.
+    pub fn remove(&mut self, epc: &[u8]) -> bool {
+        self.epcs.remove(epc)
+    }
+
+    /// Removes EPCs that are no longer referenced outside of the cache
+    pub fn remove_unused(&mut self) {
+        self.epcs.retain(|epc| Arc::strong_count(epc) > 1);
+    }
+
+    /// Returns the number of distinct EPCs in the cache
+    pub fn len(&self) -> usize {
+        self.epcs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.epcs.is_empty()
+    }
+}
+
+/// A flattened view of a single `TagReportData` parameter
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TagObservation {
+    /// The EPC of the tag as a lowercase hex string
+    pub epc: String,
+
+    /// The antenna the tag was seen on
+    pub antenna_id: Option<u16>,
+
+    /// The peak RSSI of the tag in dBm
+    pub peak_rssi_dbm: Option<i8>,
+
+    /// The time the tag was first seen (`None` if the reader only reported an uptime timestamp)
+    pub first_seen: Option<SystemTime>,
+
+    /// The time the tag was last seen (`None` if the reader only reported an uptime timestamp)
+    pub last_seen: Option<SystemTime>,
+
+    /// The number of times the tag was seen
+    pub read_count: Option<u16>,
+
+    /// The results of any OpSpecs that were executed on the tag
+    pub op_spec_results: Vec<AccessCommandOpSpecResult>,
+}
+
+impl From<&TagReportData> for TagObservation {
+    fn from(data: &TagReportData) -> TagObservation {
+        TagObservation {
+            epc: data.epc_parameter.to_hex(),
+            antenna_id: data.antenna_id,
+            peak_rssi_dbm: data.peak_rssi,
+            first_seen: data.first_seen_timestamp().and_then(|x| x.utc()),
+            last_seen: data.last_seen_timestamp().and_then(|x| x.utc()),
+            read_count: data.tag_seen_count,
+            op_spec_results: data.access_command_op_spec_result.clone(),
+        }
+    }
+}
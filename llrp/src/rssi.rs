@@ -0,0 +1,127 @@
+//! Smoothing of the RSSI of each tag, as a building block for proximity and zone classification
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::{parameters::TagReportData, report::Epc};
+
+/// The number of consecutive outliers after which the RSSI of a tag is assumed to have changed
+/// (e.g. because the tag moved), restarting the smoothing from the latest value
+const MAX_CONSECUTIVE_OUTLIERS: u32 = 3;
+
+/// How the RSSI values of a tag are combined
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Smoothing {
+    /// An exponential moving average, where each new value has a weight of `alpha` (between 0
+    /// and 1)
+    Ema { alpha: f64 },
+
+    /// The mean of the most recent `size` values
+    Window { size: usize },
+}
+
+#[derive(Debug, Clone, Default)]
+struct TagRssi {
+    ema: f64,
+    window: VecDeque<i8>,
+    outliers: u32,
+}
+
+/// Smooths the RSSI of each tag, optionally rejecting outliers
+#[derive(Debug, Clone)]
+pub struct RssiSmoother {
+    smoothing: Smoothing,
+    max_deviation: Option<f64>,
+    tags: HashMap<Epc, TagRssi>,
+}
+
+impl RssiSmoother {
+    pub fn new(smoothing: Smoothing) -> Self {
+        let smoothing = match smoothing {
+            Smoothing::Ema { alpha } => Smoothing::Ema { alpha: alpha.clamp(0.0, 1.0) },
+            Smoothing::Window { size } => Smoothing::Window { size: size.max(1) },
+        };
+        Self { smoothing, max_deviation: None, tags: HashMap::new() }
+    }
+
+    /// Ignores values that differ from the smoothed RSSI by more than `max_deviation` dB. If
+    /// several values in a row are rejected, the smoothing restarts from the latest value.
+    pub fn reject_outliers(mut self, max_deviation: f64) -> Self {
+        self.max_deviation = Some(max_deviation);
+        self
+    }
+
+    /// Adds an RSSI value (in dBm) for a tag, returning the smoothed RSSI of the tag
+    pub fn update(&mut self, epc: &Epc, rssi: i8) -> f64 {
+        let smoothing = self.smoothing;
+        let max_deviation = self.max_deviation;
+
+        if !self.tags.contains_key(epc) {
+            self.tags.entry(epc.clone()).or_default().restart(rssi);
+            return rssi as f64;
+        }
+        let tag = self.tags.get_mut(epc).unwrap();
+
+        let current = tag.value(smoothing);
+        if let Some(max_deviation) = max_deviation {
+            if (rssi as f64 - current).abs() > max_deviation {
+                tag.outliers += 1;
+                if tag.outliers < MAX_CONSECUTIVE_OUTLIERS {
+                    return current;
+                }
+                tag.restart(rssi);
+                return rssi as f64;
+            }
+        }
+        tag.outliers = 0;
+
+        match smoothing {
+            Smoothing::Ema { alpha } => tag.ema += alpha * (rssi as f64 - tag.ema),
+            Smoothing::Window { size } => {
+                if tag.window.len() == size {
+                    tag.window.pop_front();
+                }
+                tag.window.push_back(rssi);
+            }
+        }
+        tag.value(smoothing)
+    }
+
+    /// Adds the RSSI of a tag read, returning the smoothed RSSI of the tag. Returns `None` if the
+    /// read does not include an RSSI.
+    pub fn observe(&mut self, data: &TagReportData) -> Option<f64> {
+        let rssi = data.peak_rssi?;
+        Some(self.update(&data.epc(), rssi))
+    }
+
+    /// Returns the smoothed RSSI of a tag
+    pub fn get(&self, epc: &Epc) -> Option<f64> {
+        Some(self.tags.get(epc)?.value(self.smoothing))
+    }
+
+    /// Removes a tag, e.g. after it has departed
+    pub fn remove(&mut self, epc: &Epc) -> bool {
+        self.tags.remove(epc).is_some()
+    }
+
+    pub fn clear(&mut self) {
+        self.tags.clear();
+    }
+}
+
+impl TagRssi {
+    fn restart(&mut self, rssi: i8) {
+        self.ema = rssi as f64;
+        self.window.clear();
+        self.window.push_back(rssi);
+        self.outliers = 0;
+    }
+
+    fn value(&self, smoothing: Smoothing) -> f64 {
+        match smoothing {
+            Smoothing::Ema { .. } => self.ema,
+            Smoothing::Window { .. } => {
+                self.window.iter().map(|&x| x as f64).sum::<f64>() / self.window.len() as f64
+            }
+        }
+    }
+}
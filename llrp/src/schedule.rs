@@ -0,0 +1,282 @@
+//! Time-based scheduling of ROSpecs from the client side
+//!
+//! The scheduler does not send messages itself: `RoSpecScheduler::poll` returns the messages to
+//! send to the reader at the current time, and the `ROSpecEvent`s reported by the reader are fed
+//! back with `RoSpecScheduler::handle_event` so that the scheduler tracks the state of each spec.
+//!
+//! ```
+//! use std::time::{Duration, SystemTime};
+//! use llrp::schedule::{RoSpecScheduler, Schedule, Weekday};
+//!
+//! // Inventory every 5 minutes for 30 seconds, but only on weekdays
+//! let mut scheduler = RoSpecScheduler::new().spec(
+//!     1,
+//!     Schedule::every(Duration::from_secs(300), Duration::from_secs(30)).weekdays(&[
+//!         Weekday::Monday,
+//!         Weekday::Tuesday,
+//!         Weekday::Wednesday,
+//!         Weekday::Thursday,
+//!         Weekday::Friday,
+//!     ]),
+//! );
+//!
+//! for action in scheduler.poll(SystemTime::now()) {
+//!     let _message = action.to_message();
+//!     // ... send the message to the reader
+//! }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    enumerations::ROSpecEventType,
+    messages::{EnableRospec, Message, StartRospec, StopRospec},
+    parameters::ROSpecEvent,
+};
+
+const NANOS_PER_DAY: u128 = 24 * 60 * 60 * 1_000_000_000;
+
+/// The maximum number of windows skipped when searching for the next window on an allowed day
+const MAX_SKIPPED_WINDOWS: usize = 1000;
+
+/// A day of the week, in UTC
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+/// A repeating window of time in which a spec should run.
+///
+/// Windows of `duration` start every `period`, aligned to the UNIX epoch plus an offset (e.g. a
+/// period of one hour starts windows on the hour). Times are in UTC.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Schedule {
+    period: Duration,
+    duration: Duration,
+    offset: Duration,
+    /// The allowed days, with bit 0 set for Monday
+    weekdays: u8,
+}
+
+impl Schedule {
+    /// Creates a schedule with windows of `duration` starting every `period`
+    pub fn every(period: Duration, duration: Duration) -> Self {
+        Self {
+            period: period.max(Duration::from_millis(1)),
+            duration,
+            offset: Duration::from_secs(0),
+            weekdays: 0x7f,
+        }
+    }
+
+    /// Shifts the start of each window by `offset` (default zero)
+    pub fn offset(mut self, offset: Duration) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Restricts the schedule to windows that start on the given days (default every day)
+    pub fn weekdays(mut self, days: &[Weekday]) -> Self {
+        self.weekdays = days.iter().fold(0, |mask, &day| mask | 1 << day as u8);
+        self
+    }
+
+    /// Returns the start of the window containing `now`, or `None` if the schedule is not active
+    pub fn window_start(&self, now: SystemTime) -> Option<SystemTime> {
+        let now = nanos(now);
+        let (period, offset) = (self.period.as_nanos(), self.offset.as_nanos());
+        if now < offset {
+            return None;
+        }
+
+        let start = now - (now - offset) % period;
+        if now - start < self.duration.as_nanos() && self.allowed(start) {
+            Some(time(start))
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the schedule is active at `now`
+    pub fn is_active(&self, now: SystemTime) -> bool {
+        self.window_start(now).is_some()
+    }
+
+    /// Returns the start of the first window after `now`, or `None` if there are no more windows
+    /// (e.g. if no days are allowed)
+    pub fn next_start(&self, now: SystemTime) -> Option<SystemTime> {
+        let now = nanos(now);
+        let (period, offset) = (self.period.as_nanos(), self.offset.as_nanos());
+
+        let mut k = if now < offset { 0 } else { (now - offset) / period + 1 };
+        for _ in 0..MAX_SKIPPED_WINDOWS {
+            let start = offset + k * period;
+            if self.allowed(start) {
+                return Some(time(start));
+            }
+            // Skip to the first window starting on the next day
+            let next_day = (start / NANOS_PER_DAY + 1) * NANOS_PER_DAY;
+            k = (next_day - offset) / period;
+            if offset + k * period < next_day {
+                k += 1;
+            }
+        }
+        None
+    }
+
+    /// Returns the next time after `now` that the schedule becomes active or inactive
+    pub fn next_transition(&self, now: SystemTime) -> Option<SystemTime> {
+        match self.window_start(now) {
+            Some(start) => Some(start + self.duration),
+            None => self.next_start(now),
+        }
+    }
+
+    fn allowed(&self, start: u128) -> bool {
+        // 1970-01-01 was a Thursday
+        let weekday = (start / NANOS_PER_DAY + 3) % 7;
+        self.weekdays & (1 << weekday) != 0
+    }
+}
+
+fn nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos()
+}
+
+fn time(nanos: u128) -> SystemTime {
+    let secs = (nanos / 1_000_000_000) as u64;
+    UNIX_EPOCH + Duration::new(secs, (nanos % 1_000_000_000) as u32)
+}
+
+/// A message the scheduler needs to be sent to the reader
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ScheduleAction {
+    Enable(u32),
+    Start(u32),
+    Stop(u32),
+}
+
+impl ScheduleAction {
+    pub fn ro_spec_id(&self) -> u32 {
+        match *self {
+            ScheduleAction::Enable(id) | ScheduleAction::Start(id) | ScheduleAction::Stop(id) => id,
+        }
+    }
+
+    pub fn to_message(&self) -> Message {
+        match *self {
+            ScheduleAction::Enable(ro_spec_id) => {
+                Message::EnableRospec(EnableRospec { ro_spec_id })
+            }
+            ScheduleAction::Start(ro_spec_id) => Message::StartRospec(StartRospec { ro_spec_id }),
+            ScheduleAction::Stop(ro_spec_id) => Message::StopRospec(StopRospec { ro_spec_id }),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ScheduledSpec {
+    schedule: Schedule,
+    enabled: bool,
+    /// Whether the spec is running on the reader, or `None` if unknown (e.g. after reconnecting)
+    running: Option<bool>,
+    /// The start of the window the spec was last started in
+    started: Option<SystemTime>,
+}
+
+/// Starts and stops a set of ROSpecs according to their schedules.
+///
+/// The ROSpecs must already have been added to the reader, with a `Null` start and stop trigger so
+/// that they are only started and stopped by the scheduler. A spec that ends by itself (e.g. from
+/// an AISpec stop trigger) is not restarted until its next window.
+#[derive(Debug, Clone, Default)]
+pub struct RoSpecScheduler {
+    specs: BTreeMap<u32, ScheduledSpec>,
+}
+
+impl RoSpecScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a ROSpec to the scheduler. The spec is assumed not to be running, as after it was
+    /// just added to the reader (use `reset` if the state of the reader is unknown).
+    pub fn spec(mut self, ro_spec_id: u32, schedule: Schedule) -> Self {
+        self.specs.insert(
+            ro_spec_id,
+            ScheduledSpec { schedule, enabled: false, running: Some(false), started: None },
+        );
+        self
+    }
+
+    /// Returns the messages to send to the reader at time `now`, ordered by ROSpec id
+    pub fn poll(&mut self, now: SystemTime) -> Vec<ScheduleAction> {
+        let mut actions = vec![];
+        for (&id, spec) in &mut self.specs {
+            if !spec.enabled {
+                actions.push(ScheduleAction::Enable(id));
+                spec.enabled = true;
+            }
+
+            match spec.schedule.window_start(now) {
+                Some(start) if spec.started != Some(start) => {
+                    if spec.running != Some(true) {
+                        actions.push(ScheduleAction::Start(id));
+                    }
+                    spec.started = Some(start);
+                    spec.running = Some(true);
+                }
+                None if spec.running != Some(false) => {
+                    actions.push(ScheduleAction::Stop(id));
+                    spec.running = Some(false);
+                }
+                _ => {}
+            }
+        }
+        actions
+    }
+
+    /// Returns the next time after `now` that `poll` may return any actions
+    pub fn next_poll(&self, now: SystemTime) -> Option<SystemTime> {
+        self.specs.values().filter_map(|spec| spec.schedule.next_transition(now)).min()
+    }
+
+    /// Updates the state of a spec from a `ROSpecEvent` reported by the reader
+    pub fn handle_event(&mut self, event: &ROSpecEvent) {
+        let spec = match self.specs.get_mut(&event.ro_spec_id) {
+            Some(spec) => spec,
+            None => return,
+        };
+        match event.event_type {
+            ROSpecEventType::Start_Of_ROSpec => spec.running = Some(true),
+            ROSpecEventType::End_Of_ROSpec => spec.running = Some(false),
+            ROSpecEventType::Preemption_Of_ROSpec => {
+                // Restart the spec on the next poll if it is still within its window
+                spec.running = Some(false);
+                spec.started = None;
+            }
+        }
+    }
+
+    /// Forgets the state of the reader, e.g. after reconnecting, so that the next `poll` enables
+    /// every spec again and starts or stops it according to its schedule
+    pub fn reset(&mut self) {
+        for spec in self.specs.values_mut() {
+            *spec = ScheduledSpec {
+                schedule: spec.schedule,
+                enabled: false,
+                running: None,
+                started: None,
+            };
+        }
+    }
+}
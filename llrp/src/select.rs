@@ -0,0 +1,98 @@
+//! Finding and decoding selected parameters of a message without decoding the whole message
+
+use crate::{
+    layout, parse_param_type, parse_tlv_header, BinaryMessage, Decoder, Error, LLRPValue,
+    ParameterType, Result, TLV_HEADER_LEN,
+};
+
+/// A parameter found by `select_parameters`, borrowed from the message it was found in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectedParameter<'a> {
+    /// The type num of the parameter (the TV type num for TV parameters)
+    pub type_num: u16,
+
+    /// The encoded parameter, including its header
+    pub bytes: &'a [u8],
+}
+
+impl<'a> SelectedParameter<'a> {
+    /// Decodes the parameter as `T`, e.g. `EPC_96` or `EPCData`
+    pub fn decode<T: LLRPValue>(&self) -> Result<T> {
+        let mut decoder = Decoder::new(self.bytes);
+        let value = match self.type_num {
+            0..=127 => decoder.read_tv(self.type_num as u8)?,
+            _ => decoder.read()?,
+        };
+        decoder.validate_consumed()?;
+        Ok(value)
+    }
+}
+
+impl BinaryMessage {
+    /// Finds the parameters of the message with a type num in `selector`, see `select_parameters`
+    pub fn select_parameters(&self, selector: &[u16]) -> Result<Vec<SelectedParameter<'_>>> {
+        select_parameters(self.message_type, &self.value, selector)
+    }
+}
+
+/// Finds the parameters with a type num in `selector` (at any depth) in the payload of a message
+/// of type `message_type`, skipping over all other parameters using their lengths. Only the
+/// selected parameters need to be decoded, which is much cheaper than decoding the whole message
+/// when only a few values are needed (e.g. the EPC and antenna of each tag in a large report).
+///
+/// The sub-parameters of a selected parameter are not searched. Parameters are only searched for
+/// in messages and parameters where the fields before the parameters have a fixed size, so for
+/// example the contents of custom parameters are skipped.
+pub fn select_parameters<'a>(
+    message_type: u16,
+    payload: &'a [u8],
+    selector: &[u16],
+) -> Result<Vec<SelectedParameter<'a>>> {
+    let fields_len = match layout::message_fields_len(message_type) {
+        Some(len) => len,
+        None if crate::messages::Message::is_known_type(message_type) => return Ok(vec![]),
+        None => return Err(Error::UnknownMessageId(message_type as u32)),
+    };
+    if payload.len() < fields_len {
+        return Err(Error::InsufficientData { needed: fields_len, remaining: payload.len() });
+    }
+
+    let mut selected = vec![];
+    select_in(&payload[fields_len..], selector, &mut selected)?;
+    Ok(selected)
+}
+
+fn select_in<'a>(
+    mut bytes: &'a [u8],
+    selector: &[u16],
+    selected: &mut Vec<SelectedParameter<'a>>,
+) -> Result<()> {
+    while !bytes.is_empty() {
+        let (type_num, len) = match parse_param_type(bytes)? {
+            ParameterType::Tv(id) => {
+                (id as u16, layout::tv_param_len(id).ok_or(Error::InvalidType(id as u16))?)
+            }
+            ParameterType::Tlv(_) => {
+                let (type_num, len) = parse_tlv_header(bytes)?;
+                (type_num, len as usize)
+            }
+        };
+        if bytes.len() < len {
+            return Err(Error::InsufficientData { needed: len, remaining: bytes.len() });
+        }
+
+        let (param, rest) = bytes.split_at(len);
+        if selector.contains(&type_num) {
+            selected.push(SelectedParameter { type_num, bytes: param });
+        } else if let Some(fields_len) = layout::parameter_fields_len(type_num) {
+            let start = TLV_HEADER_LEN + fields_len;
+            if param.len() < start {
+                return Err(Error::InsufficientData { needed: start, remaining: param.len() });
+            }
+            select_in(&param[start..], selector, selected)?;
+        }
+
+        bytes = rest;
+    }
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! Allocation of ROSpec and AccessSpec identifiers
+
+use std::collections::BTreeSet;
+
+use crate::messages::{GetAccessspecsResponse, GetRospecsResponse};
+
+/// Assigns ROSpec or AccessSpec ids that are not already in use on a reader.
+///
+/// The allocator is seeded with the specs that are currently present on the reader (from a
+/// `GET_ROSPECS` or `GET_ACCESSSPECS` response), so that ids added by other clients are not
+/// reused. Id 0 is never allocated since it refers to all specs in the LLRP specification.
+#[derive(Debug, Clone, Default)]
+pub struct SpecIdAllocator {
+    used: BTreeSet<u32>,
+}
+
+impl SpecIdAllocator {
+    /// Creates an allocator that assumes that no ids are in use
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an allocator for ROSpec ids, excluding the ROSpecs currently on the reader
+    pub fn from_ro_specs(response: &GetRospecsResponse) -> Self {
+        Self { used: response.ro_spec.iter().map(|spec| spec.ro_spec_id).collect() }
+    }
+
+    /// Creates an allocator for AccessSpec ids, excluding the AccessSpecs currently on the reader
+    pub fn from_access_specs(response: &GetAccessspecsResponse) -> Self {
+        Self { used: response.access_spec.iter().map(|spec| spec.access_spec_id).collect() }
+    }
+
+    /// Returns the lowest unused id and marks it as used, or `None` if all ids are in use
+    pub fn allocate(&mut self) -> Option<u32> {
+        let mut id = 1;
+        for &used in self.used.range(1..) {
+            if used != id {
+                break;
+            }
+            id = id.checked_add(1)?;
+        }
+        self.used.insert(id);
+        Some(id)
+    }
+
+    /// Marks `id` as used, returning `false` if it was already in use
+    pub fn reserve(&mut self, id: u32) -> bool {
+        self.used.insert(id)
+    }
+
+    /// Marks `id` as unused after the spec has been deleted from the reader
+    pub fn release(&mut self, id: u32) {
+        self.used.remove(&id);
+    }
+
+    /// Returns whether `id` is currently in use
+    pub fn is_used(&self, id: u32) -> bool {
+        self.used.contains(&id)
+    }
+}
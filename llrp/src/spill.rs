@@ -0,0 +1,192 @@
+//! Buffering of received messages that spills to disk during bursts
+
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    binary::{read_message, write_message, LLRP_HEADER_LENGTH},
+    units, BinaryMessage,
+};
+
+/// The length of the receive time stored before each frame in the spill file
+const TIME_LENGTH: usize = 8;
+
+/// A FIFO queue of messages (e.g. `RO_ACCESS_REPORT`s) that keeps up to a fixed number of bytes
+/// in memory and writes the rest to a file, so that a consumer that stalls during a burst of
+/// reports does not cause tags to be dropped.
+///
+/// Once any message has been spilled, later messages are also written to the file until it has
+/// been drained, so messages are always returned in the order they were pushed. The file is
+/// created when the first message is spilled, truncated whenever it has been drained and removed
+/// when the buffer is dropped.
+#[derive(Debug)]
+pub struct SpillBuffer {
+    path: PathBuf,
+    file: Option<File>,
+    max_memory_bytes: usize,
+    max_disk_bytes: u64,
+    max_age: Option<Duration>,
+    memory: VecDeque<(SystemTime, BinaryMessage)>,
+    memory_bytes: usize,
+    read_offset: u64,
+    write_offset: u64,
+    spilled: usize,
+    dropped: u64,
+}
+
+impl SpillBuffer {
+    /// Creates a buffer that keeps up to `max_memory_bytes` of messages in memory before spilling
+    /// to the file at `path`
+    pub fn new(path: impl Into<PathBuf>, max_memory_bytes: usize) -> Self {
+        Self {
+            path: path.into(),
+            file: None,
+            max_memory_bytes,
+            max_disk_bytes: u64::MAX,
+            max_age: None,
+            memory: VecDeque::new(),
+            memory_bytes: 0,
+            read_offset: 0,
+            write_offset: 0,
+            spilled: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Limits the number of bytes of messages waiting in the file. Messages pushed while the file
+    /// is full are dropped.
+    pub fn max_disk_bytes(mut self, max_disk_bytes: u64) -> Self {
+        self.max_disk_bytes = max_disk_bytes;
+        self
+    }
+
+    /// Drops messages that have been waiting for longer than `max_age` instead of returning them
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Adds a message received at time `now`, returning `false` if it was dropped because the
+    /// file is full
+    pub fn push(&mut self, message: BinaryMessage, now: SystemTime) -> io::Result<bool> {
+        let length = LLRP_HEADER_LENGTH + message.value.len();
+        if self.spilled == 0 && self.memory_bytes + length <= self.max_memory_bytes {
+            self.memory_bytes += length;
+            self.memory.push_back((now, message));
+            return Ok(true);
+        }
+
+        let record_length = (TIME_LENGTH + length) as u64;
+        if self.write_offset - self.read_offset + record_length > self.max_disk_bytes {
+            self.dropped += 1;
+            return Ok(false);
+        }
+
+        let mut record = Vec::with_capacity(record_length as usize);
+        record.extend_from_slice(&units::time_to_micros(now).to_be_bytes());
+        write_message(&mut record, message)?;
+
+        let write_offset = self.write_offset;
+        let file = self.file()?;
+        file.seek(SeekFrom::Start(write_offset))?;
+        file.write_all(&record)?;
+
+        self.write_offset += record_length;
+        self.spilled += 1;
+        Ok(true)
+    }
+
+    /// Removes the oldest message, skipping messages older than the maximum age at time `now`
+    pub fn pop(&mut self, now: SystemTime) -> io::Result<Option<BinaryMessage>> {
+        loop {
+            let (received, message) = match self.memory.pop_front() {
+                Some(entry) => {
+                    self.memory_bytes -= LLRP_HEADER_LENGTH + entry.1.value.len();
+                    entry
+                }
+                None => match self.read_spilled()? {
+                    Some(entry) => entry,
+                    None => return Ok(None),
+                },
+            };
+
+            let expired = self
+                .max_age
+                .map(|max_age| now.duration_since(received).unwrap_or_default() > max_age);
+            if expired == Some(true) {
+                self.dropped += 1;
+                continue;
+            }
+            return Ok(Some(message));
+        }
+    }
+
+    /// Returns the number of messages in the buffer
+    pub fn len(&self) -> usize {
+        self.memory.len() + self.spilled
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of messages currently in the file
+    pub fn num_spilled(&self) -> usize {
+        self.spilled
+    }
+
+    /// Returns the number of messages dropped because the file was full or they were too old
+    pub fn num_dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    fn file(&mut self) -> io::Result<&mut File> {
+        if self.file.is_none() {
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&self.path)?;
+            self.file = Some(file);
+        }
+        Ok(self.file.as_mut().unwrap())
+    }
+
+    fn read_spilled(&mut self) -> io::Result<Option<(SystemTime, BinaryMessage)>> {
+        if self.spilled == 0 {
+            return Ok(None);
+        }
+
+        let read_offset = self.read_offset;
+        let file = self.file()?;
+        file.seek(SeekFrom::Start(read_offset))?;
+        let mut time = [0; TIME_LENGTH];
+        file.read_exact(&mut time)?;
+        let message = read_message(&mut *file)?;
+
+        self.read_offset += (TIME_LENGTH + LLRP_HEADER_LENGTH + message.value.len()) as u64;
+        self.spilled -= 1;
+        if self.spilled == 0 {
+            self.file()?.set_len(0)?;
+            self.read_offset = 0;
+            self.write_offset = 0;
+        }
+
+        let received = units::micros_to_time(u64::from_be_bytes(time));
+        Ok(Some((received, message)))
+    }
+}
+
+impl Drop for SpillBuffer {
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
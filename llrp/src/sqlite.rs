@@ -0,0 +1,183 @@
+//! Persisting tag observations to an SQLite database
+//!
+//! Each EPC is stored as a single row, which is updated whenever the tag is seen again:
+//!
+//! ```sql
+//! CREATE TABLE tags (
+//!     epc TEXT PRIMARY KEY,   -- lowercase hex
+//!     antenna_id INTEGER,     -- the antenna the tag was last seen on
+//!     peak_rssi INTEGER,      -- the RSSI of the last read in dBm
+//!     first_seen INTEGER,     -- microseconds since the UNIX epoch
+//!     last_seen INTEGER,      -- microseconds since the UNIX epoch
+//!     read_count INTEGER      -- the total number of reads
+//! )
+//! ```
+
+use std::{path::Path, time::SystemTime};
+
+use rusqlite::{params, Connection, OptionalExtension, Result, Row};
+
+use crate::{report::TagObservation, units};
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS tags (
+    epc TEXT PRIMARY KEY,
+    antenna_id INTEGER,
+    peak_rssi INTEGER,
+    first_seen INTEGER NOT NULL,
+    last_seen INTEGER NOT NULL,
+    read_count INTEGER NOT NULL
+)";
+
+const UPSERT: &str =
+    "INSERT INTO tags (epc, antenna_id, peak_rssi, first_seen, last_seen, read_count)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+ON CONFLICT (epc) DO UPDATE SET
+    antenna_id = coalesce(excluded.antenna_id, antenna_id),
+    peak_rssi = coalesce(excluded.peak_rssi, peak_rssi),
+    first_seen = min(first_seen, excluded.first_seen),
+    last_seen = max(last_seen, excluded.last_seen),
+    read_count = read_count + excluded.read_count";
+
+const SELECT: &str =
+    "SELECT epc, antenna_id, peak_rssi, first_seen, last_seen, read_count FROM tags";
+
+/// A tag stored in the database
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StoredTag {
+    /// The EPC of the tag as a lowercase hex string
+    pub epc: String,
+
+    /// The antenna the tag was last seen on
+    pub antenna_id: Option<u16>,
+
+    /// The RSSI of the last read of the tag that included one, in dBm
+    pub peak_rssi_dbm: Option<i8>,
+
+    pub first_seen: SystemTime,
+    pub last_seen: SystemTime,
+
+    /// The total number of times the tag was seen
+    pub read_count: u64,
+}
+
+/// Stores tag observations in an SQLite database, keeping one row per EPC.
+///
+/// Observations are written in batches, in a single transaction per batch. Call `flush` to write
+/// any remaining observations (e.g. when a report has been processed); they are also written when
+/// the sink is dropped.
+pub struct SqliteSink {
+    conn: Connection,
+    batch_size: usize,
+    batch: Vec<(TagObservation, SystemTime)>,
+}
+
+impl SqliteSink {
+    /// Opens (or creates) the database at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Creates a temporary database in memory
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Uses an existing connection, creating the `tags` table if it does not exist
+    pub fn from_connection(conn: Connection) -> Result<Self> {
+        conn.execute(CREATE_TABLE, [])?;
+        Ok(Self { conn, batch_size: 100, batch: vec![] })
+    }
+
+    /// Sets the number of observations written in each transaction (default 100)
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Adds an observation received at time `now`, which is used if the observation does not
+    /// include UTC timestamps
+    pub fn push(&mut self, observation: TagObservation, now: SystemTime) -> Result<()> {
+        self.batch.push((observation, now));
+        if self.batch.len() >= self.batch_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes any observations that have not been written yet
+    pub fn flush(&mut self) -> Result<()> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+
+        let tx = self.conn.transaction()?;
+        {
+            let mut upsert = tx.prepare_cached(UPSERT)?;
+            for (observation, now) in &self.batch {
+                let first_seen = observation.first_seen.or(observation.last_seen).unwrap_or(*now);
+                let last_seen = observation.last_seen.unwrap_or(first_seen);
+                upsert.execute(params![
+                    observation.epc,
+                    observation.antenna_id,
+                    observation.peak_rssi_dbm,
+                    micros(first_seen),
+                    micros(last_seen),
+                    observation.read_count.unwrap_or(1).max(1),
+                ])?;
+            }
+        }
+        tx.commit()?;
+
+        self.batch.clear();
+        Ok(())
+    }
+
+    /// Returns the stored tag with an EPC (as a hex string)
+    pub fn get(&self, epc: &str) -> Result<Option<StoredTag>> {
+        let sql = format!("{} WHERE epc = ?1", SELECT);
+        self.conn.query_row(&sql, [epc.to_lowercase()], stored_tag).optional()
+    }
+
+    /// Returns the tags last seen at or after `since`, ordered by the time they were last seen
+    pub fn seen_since(&self, since: SystemTime) -> Result<Vec<StoredTag>> {
+        let sql = format!("{} WHERE last_seen >= ?1 ORDER BY last_seen, epc", SELECT);
+        let mut statement = self.conn.prepare(&sql)?;
+        let rows = statement.query_map([micros(since)], stored_tag)?;
+        rows.collect()
+    }
+
+    /// Returns the number of distinct tags stored
+    pub fn count(&self) -> Result<u64> {
+        self.conn.query_row("SELECT count(*) FROM tags", [], |row| row.get(0))
+    }
+
+    /// Returns the underlying connection, e.g. for custom queries
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+impl Drop for SqliteSink {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+fn stored_tag(row: &Row) -> Result<StoredTag> {
+    Ok(StoredTag {
+        epc: row.get(0)?,
+        antenna_id: row.get(1)?,
+        peak_rssi_dbm: row.get(2)?,
+        first_seen: time(row.get(3)?),
+        last_seen: time(row.get(4)?),
+        read_count: row.get(5)?,
+    })
+}
+
+fn micros(time: SystemTime) -> i64 {
+    units::time_to_micros(time) as i64
+}
+
+fn time(micros: i64) -> SystemTime {
+    units::micros_to_time(micros.max(0) as u64)
+}
@@ -0,0 +1,155 @@
+//! Statistics about an inventory run, accumulated from a stream of tag reports
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    time::{Duration, SystemTime},
+};
+
+use crate::{messages::RoAccessReport, parameters::TagReportData, report::Epc};
+
+#[derive(Debug, Clone, Default)]
+struct AntennaStats {
+    reads: u64,
+    epcs: HashSet<Epc>,
+}
+
+/// Accumulates statistics about the tags read during an inventory run
+#[derive(Debug, Clone)]
+pub struct InventoryStats {
+    bucket_width: u8,
+    start: Option<SystemTime>,
+    end: Option<SystemTime>,
+    total_reads: u64,
+    epcs: HashSet<Epc>,
+    antennas: BTreeMap<u16, AntennaStats>,
+    rssi_histogram: BTreeMap<i8, u64>,
+}
+
+/// Statistics for the tags read on a single antenna
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AntennaSnapshot {
+    pub antenna_id: u16,
+    pub reads: u64,
+    pub unique_epcs: usize,
+}
+
+/// The statistics of an inventory run at a point in time
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsSnapshot {
+    /// The number of distinct EPCs that were read
+    pub unique_epcs: usize,
+
+    /// The total number of reads, including reads counted by the reader's `TagSeenCount`
+    pub total_reads: u64,
+
+    /// The time between the first and last read
+    pub duration: Duration,
+
+    /// The average number of reads per second over `duration`
+    pub reads_per_second: f64,
+
+    /// Statistics for each antenna, ordered by antenna id. Reads without an antenna id are not
+    /// included.
+    pub antennas: Vec<AntennaSnapshot>,
+
+    /// The number of reads in each RSSI bucket, keyed by the lowest RSSI (in dBm) in the bucket
+    /// and ordered by RSSI. Reads without an RSSI are not included.
+    pub rssi_histogram: Vec<(i8, u64)>,
+}
+
+impl Default for InventoryStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InventoryStats {
+    pub fn new() -> Self {
+        Self {
+            bucket_width: 5,
+            start: None,
+            end: None,
+            total_reads: 0,
+            epcs: HashSet::new(),
+            antennas: BTreeMap::new(),
+            rssi_histogram: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the width of the buckets in the RSSI histogram in dBm (default 5)
+    pub fn rssi_bucket_width(mut self, width: u8) -> Self {
+        self.bucket_width = width.max(1);
+        self
+    }
+
+    /// Records a read of a tag at time `now`
+    pub fn observe(&mut self, data: &TagReportData, now: SystemTime) {
+        let reads = data.tag_seen_count.map_or(1, |count| count.max(1) as u64);
+        let epc = data.epc();
+
+        self.start = Some(self.start.map_or(now, |start| start.min(now)));
+        self.end = Some(self.end.map_or(now, |end| end.max(now)));
+        self.total_reads += reads;
+
+        if let Some(antenna_id) = data.antenna_id {
+            let antenna = self.antennas.entry(antenna_id).or_default();
+            antenna.reads += reads;
+            if !antenna.epcs.contains(&epc) {
+                antenna.epcs.insert(epc.clone());
+            }
+        }
+
+        if let Some(rssi) = data.peak_rssi {
+            let width = self.bucket_width as i16;
+            let bucket = (rssi as i16).div_euclid(width) * width;
+            *self.rssi_histogram.entry(bucket.max(i8::MIN as i16) as i8).or_default() += reads;
+        }
+
+        self.epcs.insert(epc);
+    }
+
+    /// Records all of the tags in a report
+    pub fn observe_report(&mut self, report: &RoAccessReport, now: SystemTime) {
+        for data in &report.tag_report_data {
+            self.observe(data, now);
+        }
+    }
+
+    /// Returns the statistics accumulated so far
+    pub fn snapshot(&self) -> StatsSnapshot {
+        let duration = match (self.start, self.end) {
+            (Some(start), Some(end)) => end.duration_since(start).unwrap_or_default(),
+            _ => Duration::from_secs(0),
+        };
+        let reads_per_second = match duration.as_secs_f64() {
+            secs if secs > 0.0 => self.total_reads as f64 / secs,
+            _ => 0.0,
+        };
+
+        StatsSnapshot {
+            unique_epcs: self.epcs.len(),
+            total_reads: self.total_reads,
+            duration,
+            reads_per_second,
+            antennas: self
+                .antennas
+                .iter()
+                .map(|(&antenna_id, stats)| AntennaSnapshot {
+                    antenna_id,
+                    reads: stats.reads,
+                    unique_epcs: stats.epcs.len(),
+                })
+                .collect(),
+            rssi_histogram: self
+                .rssi_histogram
+                .iter()
+                .map(|(&rssi, &reads)| (rssi, reads))
+                .collect(),
+        }
+    }
+
+    /// Clears the statistics, e.g. at the start of a new inventory run
+    pub fn reset(&mut self) {
+        *self = Self { bucket_width: self.bucket_width, ..Self::new() };
+    }
+}
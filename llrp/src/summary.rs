@@ -0,0 +1,78 @@
+//! Compact single line descriptions of messages for logging
+
+use std::fmt::Write;
+
+use crate::{messages::Message, BinaryMessage};
+
+impl Message {
+    /// Returns a single line description of the message containing the message type and its key
+    /// fields, e.g. `ADD_ROSPEC ro_spec_id=1` or `RO_ACCESS_REPORT tags=12`
+    pub fn summary(&self) -> String {
+        let mut output = self.name().to_string();
+        self.write_details(&mut output);
+        output
+    }
+
+    fn write_details(&self, output: &mut String) {
+        let _ = match self {
+            Message::AddRospec(msg) => write!(output, " ro_spec_id={}", msg.ro_spec.ro_spec_id),
+            Message::DeleteRospec(msg) => write!(output, " ro_spec_id={}", msg.ro_spec_id),
+            Message::StartRospec(msg) => write!(output, " ro_spec_id={}", msg.ro_spec_id),
+            Message::StopRospec(msg) => write!(output, " ro_spec_id={}", msg.ro_spec_id),
+            Message::EnableRospec(msg) => write!(output, " ro_spec_id={}", msg.ro_spec_id),
+            Message::DisableRospec(msg) => write!(output, " ro_spec_id={}", msg.ro_spec_id),
+            Message::GetRospecsResponse(msg) => write!(output, " ro_specs={}", msg.ro_spec.len()),
+            Message::AddAccessspec(msg) => {
+                write!(output, " access_spec_id={}", msg.access_spec.access_spec_id)
+            }
+            Message::DeleteAccessspec(msg) => {
+                write!(output, " access_spec_id={}", msg.access_spec_id)
+            }
+            Message::EnableAccessspec(msg) => {
+                write!(output, " access_spec_id={}", msg.access_spec_id)
+            }
+            Message::DisableAccessspec(msg) => {
+                write!(output, " access_spec_id={}", msg.access_spec_id)
+            }
+            Message::GetAccessspecsResponse(msg) => {
+                write!(output, " access_specs={}", msg.access_spec.len())
+            }
+            Message::GetReaderConfig(msg) => {
+                write!(output, " requested_data={:?}", msg.requested_data)
+            }
+            Message::RoAccessReport(msg) => write!(output, " tags={}", msg.tag_report_data.len()),
+            Message::CustomMessage(msg) => write!(
+                output,
+                " vendor_identifier={} message_subtype={}",
+                msg.vendor_identifier, msg.message_subtype
+            ),
+            _ => Ok(()),
+        };
+
+        if let Some(status) = self.status() {
+            let _ = write!(output, " status={:?}", status.status_code);
+        }
+    }
+}
+
+impl BinaryMessage {
+    /// Returns a single line description of the message including its message id, e.g.
+    /// `ADD_ROSPEC id=5 ro_spec_id=1`. Messages that fail to decode are described by their type
+    /// number and length.
+    pub fn summary(&self) -> String {
+        match self.to_dynamic_message() {
+            Ok(msg) => {
+                let mut output = format!("{} id={}", msg.name(), self.id);
+                msg.write_details(&mut output);
+                output
+            }
+            Err(e) => format!(
+                "type={} id={} len={} error=\"{}\"",
+                self.message_type,
+                self.id,
+                self.value.len(),
+                e
+            ),
+        }
+    }
+}
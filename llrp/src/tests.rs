@@ -29,6 +29,45 @@ fn check_roundtrip(bytes: &[u8])  {
     assert_eq!(encoded_bytes, bytes);
 }
 
+/// Round-trips every frame of the LLRP Toolkit's binary test vectors (e.g. `dx101_a_in.bin`) in the
+/// directory named by the `LLRP_TEST_VECTORS` environment variable. The XML half of each vector is
+/// not checked, since this crate has no XML encoding.
+#[test]
+fn ltk_test_vectors() {
+    let dir = match std::env::var_os("LLRP_TEST_VECTORS") {
+        Some(dir) => dir,
+        None => return,
+    };
+
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("bin") {
+            continue;
+        }
+
+        let data = std::fs::read(&path).unwrap();
+        let mut offset = 0;
+        while offset + 10 <= data.len() {
+            let len = u32::from_be_bytes([
+                data[offset + 2],
+                data[offset + 3],
+                data[offset + 4],
+                data[offset + 5],
+            ]) as usize;
+            assert!(
+                len >= 10 && offset + len <= data.len(),
+                "{}: invalid frame length {} at offset {}",
+                path.display(),
+                len,
+                offset
+            );
+            check_roundtrip(&data[offset..offset + len]);
+            offset += len;
+        }
+        assert_eq!(offset, data.len(), "{}: trailing bytes", path.display());
+    }
+}
+
 #[test]
 fn reader_event_notifications_conn_attempt() {
     let bytes = &[
@@ -55,6 +94,82 @@ fn reader_event_notifications_conn_attempt() {
     assert_eq!(conn_event.status, ConnectionAttemptStatusType::Success);
 }
 
+#[test]
+fn reader_event_notifications_all_events() {
+    let bytes: &[u8] = &[
+        0x04, 0x3f, 0x00, 0x00, 0x00, 0x84, 0x00, 0x00, 0x00, 0x10, 0x00, 0xf6, 0x00, 0x7a, 0x00,
+        0x80, 0x00, 0x0c, 0x00, 0x05, 0x88, 0x80, 0x11, 0x9f, 0x8e, 0xad, 0x00, 0xf7, 0x00, 0x08,
+        0x00, 0x01, 0x00, 0x05, 0x00, 0xf8, 0x00, 0x07, 0x00, 0x02, 0x80, 0x00, 0xf9, 0x00, 0x0d,
+        0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfa, 0x00, 0x05, 0x5a, 0x00,
+        0xfb, 0x00, 0x04, 0x00, 0xfc, 0x00, 0x13, 0x00, 0x05, 0x45, 0x72, 0x72, 0x6f, 0x72, 0x89,
+        0x00, 0x00, 0x00, 0x01, 0x91, 0x00, 0x6f, 0x00, 0xfd, 0x00, 0x0b, 0x00, 0x00, 0x00, 0x00,
+        0x01, 0x00, 0x01, 0x00, 0xfe, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x92,
+        0x00, 0x03, 0x00, 0x0a, 0x00, 0xff, 0x00, 0x07, 0x00, 0x00, 0x01, 0x01, 0x01, 0x00, 0x04,
+        0x01, 0x64, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x03,
+    ];
+    check_roundtrip(bytes);
+
+    let raw = read_message(Cursor::new(bytes)).unwrap();
+
+    assert_eq!(raw.ver, 1);
+    assert_eq!(raw.message_type, ReaderEventNotification::ID);
+    assert_eq!(raw.id, 16);
+    assert_eq!(raw.value.len(), 122);
+
+    let (msg, _) = ReaderEventNotification::decode(&raw.value).unwrap();
+    assert_eq!(encode(&msg), raw.value);
+
+    let expected = ReaderEventNotificationData {
+        timestamp: utc_timestamp(1557458516414125),
+        hopping_event: Some(HoppingEvent { hop_table_id: 1, next_channel_index: 5 }),
+        gpi_event: Some(GPIEvent { gpi_port_number: 2, gpi_event: true, reserved: 0 }),
+        ro_spec_event: Some(ROSpecEvent {
+            event_type: ROSpecEventType::End_Of_ROSpec,
+            ro_spec_id: 1,
+            preempting_ro_spec_id: 0,
+        }),
+        report_buffer_level_warning_event: Some(ReportBufferLevelWarningEvent {
+            report_buffer_percentage_full: 90,
+        }),
+        report_buffer_overflow_error_event: Some(ReportBufferOverflowErrorEvent {}),
+        reader_exception_event: Some(ReaderExceptionEvent {
+            message: "Error".into(),
+            ro_spec_id: Some(1),
+            spec_index: None,
+            inventory_parameter_spec_id: None,
+            antenna_id: None,
+            access_spec_id: None,
+            op_spec_id: Some(111),
+            custom: vec![],
+        }),
+        rf_survey_event: Some(RFSurveyEvent {
+            event_type: RFSurveyEventType::Start_Of_RFSurvey,
+            ro_spec_id: 1,
+            spec_index: 1,
+        }),
+        ai_spec_event: Some(AISpecEvent {
+            event_type: AISpecEventType::End_Of_AISpec,
+            ro_spec_id: 1,
+            spec_index: 1,
+            air_protocol_singulation_details: Some(
+                AirProtocolSingulationDetails::C1G2SingulationDetails(C1G2SingulationDetails {
+                    num_collision_slots: 3,
+                    num_empty_slots: 10,
+                }),
+            ),
+        }),
+        antenna_event: Some(AntennaEvent {
+            event_type: AntennaEventType::Antenna_Disconnected,
+            antenna_id: 1,
+        }),
+        connection_attempt_event: None,
+        connection_close_event: Some(ConnectionCloseEvent {}),
+        spec_loop_event: Some(SpecLoopEvent { ro_spec_id: 1, loop_count: 3 }),
+        custom: vec![],
+    };
+    assert_eq!(msg.reader_event_notification_data, expected);
+}
+
 #[test]
 fn enable_events_and_reports() {
     let bytes = &[0x04, 0x40, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x08];
@@ -118,6 +233,34 @@ fn delete_access_spec_result_error() {
         parameter_error: None,
     };
     assert_eq!(msg.status, expected);
+
+    use crate::LLRPResponse;
+    assert_eq!(msg.status(), &expected);
+    assert!(!msg.is_success());
+}
+
+#[test]
+fn request_response_types() {
+    use crate::{LLRPRequest, LLRPResponse};
+
+    fn response_id<T: LLRPRequest>() -> u16 {
+        <T::Response as LLRPMessage>::ID
+    }
+
+    assert_eq!(response_id::<AddRospec>(), AddRospecResponse::ID);
+    assert_eq!(response_id::<DeleteAccessspec>(), DeleteAccessspecResponse::ID);
+    assert_eq!(response_id::<GetReaderConfig>(), GetReaderConfigResponse::ID);
+    assert_eq!(response_id::<CloseConnection>(), CloseConnectionResponse::ID);
+
+    let response = ErrorMessage {
+        status: LLRPStatus {
+            status_code: StatusCode::M_Success,
+            error_description: "".into(),
+            field_error: None,
+            parameter_error: None,
+        },
+    };
+    assert!(response.is_success());
 }
 
 #[test]
@@ -223,6 +366,81 @@ fn add_ro_spec() {
     assert_eq!(msg.ro_spec, expected_spec);
 }
 
+#[test]
+fn duration_triggers() {
+    use std::time::Duration;
+
+    assert_eq!(
+        ROSpecStopTrigger::duration(Duration::from_secs(3)).unwrap(),
+        ROSpecStopTrigger {
+            ro_spec_stop_trigger_type: ROSpecStopTriggerType::Duration,
+            duration_trigger_value: 3000,
+            gpi_trigger_value: None,
+        }
+    );
+
+    let start_trigger =
+        ROSpecStartTrigger::periodic(Duration::from_millis(500), Duration::from_secs(60)).unwrap();
+    assert_eq!(start_trigger.ro_spec_start_trigger_type, ROSpecStartTriggerType::Periodic);
+    assert_eq!(
+        start_trigger.periodic_trigger_value,
+        Some(PeriodicTriggerValue { offset: 500, period: 60000, utc_timestamp: None })
+    );
+
+    let keepalive = KeepaliveSpec::periodic(Duration::from_micros(10_999)).unwrap();
+    assert_eq!(keepalive.periodic_trigger_value, 10);
+
+    let too_long = Duration::from_millis(u32::MAX as u64 + 1);
+    assert!(AISpecStopTrigger::duration(too_long).is_err());
+    assert!(AISpecStopTrigger::duration(too_long - Duration::from_millis(1)).is_ok());
+
+    let too_short = Duration::from_micros(999);
+    assert_eq!(
+        KeepaliveSpec::periodic(too_short).unwrap_err(),
+        crate::Error::DurationOutOfRange(too_short)
+    );
+    assert_eq!(AISpecStopTrigger::duration(Duration::from_millis(0)).unwrap().duration_trigger, 0);
+}
+
+#[test]
+fn gpi_triggers() {
+    use std::time::Duration;
+
+    let start = ROSpecStartTrigger::gpi(1, true);
+    assert_eq!(start.ro_spec_start_trigger_type, ROSpecStartTriggerType::GPI);
+    assert_eq!(
+        start.gpi_trigger_value,
+        Some(GPITriggerValue { gpi_port_num: 1, gpi_event: true, reserved: 0, timeout: 0 })
+    );
+
+    let stop = ROSpecStopTrigger::gpi_with_timeout(1, false, Duration::from_secs(30)).unwrap();
+    assert_eq!(stop.ro_spec_stop_trigger_type, ROSpecStopTriggerType::GPI_With_Timeout);
+    assert_eq!(stop.gpi_trigger_value.unwrap().timeout, 30000);
+
+    let too_long = Duration::from_millis(u32::MAX as u64 + 1);
+    assert!(AISpecStopTrigger::gpi_with_timeout(2, false, too_long).is_err());
+
+    let port = GPIPortCurrentState::enabled(1);
+    assert!(port.config);
+    assert_eq!(port.state, GPIPortState::Unknown);
+}
+
+#[cfg(feature = "accessors")]
+#[test]
+fn generated_accessors() {
+    let mut spec = KeepaliveSpec::new(KeepaliveTriggerType::Null, 0)
+        .with_keepalive_trigger_type(KeepaliveTriggerType::Periodic);
+    spec.set_periodic_trigger_value(1000);
+
+    assert_eq!(spec.keepalive_trigger_type(), &KeepaliveTriggerType::Periodic);
+    assert_eq!(*spec.periodic_trigger_value(), 1000);
+
+    // Optional parameters are not part of the constructor
+    let status = LLRPStatus::new(StatusCode::M_Success, "".into());
+    assert_eq!(status.field_error, None);
+    assert_eq!(status.parameter_error, None);
+}
+
 #[test]
 pub fn add_ro_spec_response() {
     let bytes = &[
@@ -371,6 +589,175 @@ fn ro_access_report_inventory() {
         custom: vec![],
     };
     assert_eq!(report_data, &expected_report_data);
+
+    let first_seen = report_data.first_seen_timestamp().unwrap();
+    assert_eq!(first_seen, utc_timestamp(1557458645133781));
+    assert_eq!(
+        first_seen.utc(),
+        Some(std::time::UNIX_EPOCH + std::time::Duration::from_micros(1557458645133781))
+    );
+    assert_eq!(first_seen.uptime_us(), None);
+    assert_eq!(report_data.last_seen_timestamp(), None);
+
+    let observation = crate::report::TagObservation::from(report_data);
+    assert_eq!(observation.epc, "0b7f00000000000000510238");
+    assert_eq!(observation.antenna_id, Some(1));
+    assert_eq!(observation.peak_rssi_dbm, Some(-68));
+    assert_eq!(observation.first_seen, first_seen.utc());
+    assert_eq!(observation.last_seen, None);
+    assert_eq!(observation.read_count, None);
+    assert!(observation.op_spec_results.is_empty());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_lines_writer() {
+    let bytes: &[u8] = &[
+        0x04, 0x3d, 0x00, 0x00, 0x00, 0x29, 0x3a, 0xfb, 0x30, 0xb6, 0x00, 0xf0, 0x00, 0x1f, 0x8d,
+        0x0b, 0x7f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x51, 0x02, 0x38, 0x81, 0x00, 0x01,
+        0x86, 0xbc, 0x82, 0x00, 0x05, 0x88, 0x80, 0x19, 0x4b, 0xa9, 0xd5,
+    ];
+    let raw = read_message(Cursor::new(bytes)).unwrap();
+
+    let mut writer =
+        crate::jsonl::JsonLinesWriter::new(vec![]).include_raw(true).reader("reader-1");
+    writer.write_message(&raw).unwrap();
+
+    let (msg, _) = RoAccessReport::decode(&raw.value).unwrap();
+    writer.write_tag_report(&msg.tag_report_data[0]).unwrap();
+
+    let output = String::from_utf8(writer.into_inner()).unwrap();
+    let lines: Vec<serde_json::Value> =
+        output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(lines.len(), 2);
+
+    assert_eq!(lines[0]["message_type"], 61);
+    assert_eq!(lines[0]["id"], 989540534);
+    assert_eq!(lines[0]["reader"], "reader-1");
+    assert_eq!(lines[0]["raw"], "00f0001f8d0b7f0000000000000051023881000186bc8200058880194ba9d5");
+    assert_eq!(lines[0]["message"]["RoAccessReport"]["tag_report_data"][0]["antenna_id"], 1);
+
+    assert_eq!(lines[1]["reader"], "reader-1");
+    assert_eq!(lines[1]["tag_report_data"]["peak_rssi"], -68);
+}
+
+#[test]
+fn unified_epc() {
+    use crate::report::Epc;
+
+    let bytes = [0x30, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01];
+    let epc = Epc::new(bytes.to_vec());
+    assert!(epc.is_epc_96());
+    assert_eq!(epc.bit_len(), 96);
+    assert_eq!(EPCParameter::from(&epc), EPCParameter::EPC_96(bytes));
+
+    let epc_data = EPCParameter::EPCData(EPCData { epc: BitArray::from_bytes(bytes.to_vec()) });
+    let epc = Epc::from(&epc_data);
+    assert_eq!(epc.bytes(), &bytes);
+    assert!(!epc.is_epc_96());
+    assert_eq!(EPCParameter::from(&epc), epc_data);
+
+    // The wire form does not affect equality or hashing
+    let epc_96 = Epc::from(&EPCParameter::EPC_96(bytes));
+    assert_eq!(epc, epc_96);
+    let set: std::collections::HashSet<_> = vec![epc.clone(), epc_96].into_iter().collect();
+    assert_eq!(set.len(), 1);
+
+    let mut buffer = vec![];
+    crate::Encoder::new(&mut buffer).write(&epc);
+    assert_eq!(&buffer[..6], &[0x00, 0xf1, 0x00, 0x12, 0x00, 0x60]);
+    assert_eq!(crate::Decoder::new(&buffer).read::<Epc>().unwrap(), epc);
+
+    let epc = Epc::new(vec![0xe2, 0x00, 0x34, 0x12]);
+    assert_eq!(epc.to_hex(), "e2003412");
+    assert_eq!(epc.bit_len(), 32);
+    assert!(!epc.is_epc_96());
+
+    assert!(Epc::from_hex(&"00".repeat(crate::report::MAX_EPC_BYTES)).is_some());
+    assert!(Epc::from_hex(&"00".repeat(crate::report::MAX_EPC_BYTES + 1)).is_none());
+}
+
+#[test]
+fn epc_cache() {
+    let mut cache = crate::report::EpcCache::new();
+
+    let epc_96 = EPCParameter::EPC_96([0x30, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01]);
+    let epc_data = EPCParameter::EPCData(EPCData {
+        epc: BitArray::from_bytes(vec![0x30, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01]),
+    });
+
+    let first = cache.intern_parameter(&epc_96);
+    let second = cache.intern_parameter(&epc_data);
+    assert!(std::sync::Arc::ptr_eq(&first, &second));
+    assert_eq!(cache.len(), 1);
+
+    let other = cache.intern(&[0xe2, 0x00]);
+    assert_eq!(&*other, &[0xe2, 0x00]);
+    assert_eq!(cache.len(), 2);
+
+    drop(other);
+    cache.remove_unused();
+    assert_eq!(cache.len(), 1);
+    assert!(cache.remove(&first));
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn timestamp_uptime() {
+    let timestamp = Timestamp::Uptime(Uptime { microseconds: 1_500_000 });
+    assert_eq!(timestamp.utc(), None);
+    assert_eq!(timestamp.uptime_us(), Some(1_500_000));
+    assert_eq!(timestamp.microseconds(), 1_500_000);
+
+    let report_data = TagReportData {
+        epc_parameter: EPCParameter::EPC_96([0; 12]),
+        ro_spec_id: None,
+        spec_index: None,
+        inventory_parameter_spec_id: None,
+        antenna_id: None,
+        peak_rssi: None,
+        channel_index: None,
+        first_seen_timestamp_utc: None,
+        first_seen_timestamp_uptime: Some(1_500_000),
+        last_seen_timestamp_utc: Some(10),
+        last_seen_timestamp_uptime: Some(20),
+        tag_seen_count: None,
+        air_protocol_tag_data: vec![],
+        access_spec_id: None,
+        access_command_op_spec_result: vec![],
+        custom: vec![],
+    };
+    assert_eq!(report_data.first_seen_timestamp(), Some(timestamp));
+    assert_eq!(report_data.last_seen_timestamp(), Some(utc_timestamp(10)));
+}
+
+#[test]
+fn clock_estimator() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let base = 1_557_458_645_000_000;
+    let local = |us: u64| UNIX_EPOCH + Duration::from_micros(us);
+
+    // The reader's clock is 2 seconds behind the local clock and runs 100 ppm slow
+    let mut clock = crate::clock::ClockEstimator::new(8);
+    assert_eq!(clock.offset_us(), None);
+    for t in (0..4).map(|i| i * 10_000_000) {
+        clock.add_sample(&utc_timestamp(base + t), local(base + t + 2_000_000 + t / 10_000));
+    }
+    assert_eq!(clock.num_samples(), 4);
+    assert_eq!(clock.offset_us(), Some(2_003_000));
+    assert!((clock.drift_ppm().unwrap() - 100.0).abs() < 1e-6);
+    assert_eq!(
+        clock.to_local(&utc_timestamp(base + 40_000_000)),
+        Some(local(base + 42_004_000))
+    );
+    assert_eq!(clock.to_local(&Timestamp::Uptime(Uptime { microseconds: 10 })), None);
+
+    // Switching to uptime timestamps starts a new estimate
+    clock.add_sample(&Timestamp::Uptime(Uptime { microseconds: 1_000 }), local(base));
+    assert_eq!(clock.num_samples(), 1);
+    assert_eq!(clock.drift_ppm(), None);
+    assert_eq!(clock.offset_us(), Some(base as i64 - 1_000));
 }
 
 #[test]
@@ -664,6 +1051,446 @@ fn ro_access_report_blockwrite() {
     assert_eq!(report_data, &expected_report_data);
 }
 
+#[test]
+fn write_epc_access_spec() {
+    use crate::access::{epc_pc_word, epc_write_succeeded, write_epc_access_spec};
+
+    let current_epc = [0x0b, 0x7f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x51, 0x02, 0x38];
+    let new_epc = [0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01];
+
+    // Only whole words, up to the length the PC word can describe, can be written
+    assert_eq!(epc_pc_word(12), Some(0x3000));
+    assert_eq!(epc_pc_word(62), Some(0xf800));
+    assert!(write_epc_access_spec(5, &current_epc, &[], true).is_none());
+    assert!(write_epc_access_spec(5, &current_epc, &new_epc[..11], true).is_none());
+    assert!(write_epc_access_spec(5, &current_epc, &[0; 64], true).is_none());
+
+    let spec = write_epc_access_spec(5, &current_epc, &new_epc, true).unwrap();
+    let msg = AddAccessspec { access_spec: spec.clone() };
+    let (decoded, _) = AddAccessspec::decode(&encode(&msg)).unwrap();
+    assert_eq!(decoded, msg);
+
+    let command = &spec.access_command;
+    assert_eq!(command.access_command_op_spec.len(), 2);
+    assert_eq!(
+        command.access_command_op_spec[0],
+        C1G2Write {
+            op_spec_id: 1,
+            access_password: 0,
+            mb: 1,
+            reserved: 0,
+            word_pointer: 1,
+            write_data: vec![0x3000, 0x3000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0001],
+        }
+        .into()
+    );
+
+    let mut data = TagReportData {
+        epc_parameter: EPCParameter::EPC_96(current_epc),
+        ro_spec_id: None,
+        spec_index: None,
+        inventory_parameter_spec_id: None,
+        antenna_id: None,
+        peak_rssi: None,
+        channel_index: None,
+        first_seen_timestamp_utc: None,
+        first_seen_timestamp_uptime: None,
+        last_seen_timestamp_utc: None,
+        last_seen_timestamp_uptime: None,
+        tag_seen_count: None,
+        air_protocol_tag_data: vec![],
+        access_spec_id: Some(5),
+        access_command_op_spec_result: vec![
+            C1G2WriteOpSpecResult {
+                result: C1G2WriteResultType::Success,
+                op_spec_id: 1,
+                num_words_written: 7,
+            }
+            .into(),
+            C1G2ReadOpSpecResult {
+                result: C1G2ReadResultType::Success,
+                op_spec_id: 2,
+                read_data: vec![0x3000, 0x0000, 0x0000, 0x0000, 0x0000, 0x0001],
+            }
+            .into(),
+        ],
+        custom: vec![],
+    };
+    assert!(epc_write_succeeded(&data, &new_epc));
+    assert!(!epc_write_succeeded(&data, &current_epc));
+
+    data.access_command_op_spec_result.remove(0);
+    assert!(!epc_write_succeeded(&data, &new_epc));
+}
+
+#[test]
+fn kill_access_spec() {
+    use crate::access::{kill_access_spec, kill_result, ConfirmKill};
+
+    let epc = [0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01];
+    assert_eq!(kill_access_spec(3, &epc, 0, ConfirmKill), None);
+
+    let spec = kill_access_spec(3, &epc, 0x12345678, ConfirmKill).unwrap();
+    assert_eq!(
+        spec.access_command.access_command_op_spec,
+        vec![C1G2Kill { op_spec_id: 1, kill_password: 0x12345678 }.into()]
+    );
+
+    let bytes = &[
+        0x00, 0xf0, 0x00, 0x1d, 0x8d, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x01, 0x90, 0x00, 0x00, 0x00, 0x03, 0x01, 0x5f, 0x00, 0x07, 0x01, 0x00, 0x01,
+    ];
+    let mut data: TagReportData = crate::Decoder::new(bytes).read().unwrap();
+    assert_eq!(kill_result(&data), Some(Err(C1G2KillResultType::Zero_Kill_Password_Error)));
+
+    data.access_command_op_spec_result =
+        vec![C1G2KillOpSpecResult { result: C1G2KillResultType::Success, op_spec_id: 1 }.into()];
+    assert_eq!(kill_result(&data), Some(Ok(())));
+
+    data.access_command_op_spec_result.clear();
+    assert_eq!(kill_result(&data), None);
+}
+
+#[test]
+fn lock_policy() {
+    use crate::access::LockPolicy;
+
+    let op_spec = LockPolicy::LockEpcAndAccessPassword.op_spec(4, 0x11223344);
+    let mut buffer = vec![];
+    crate::Encoder::new(&mut buffer).write(&op_spec);
+    assert_eq!(
+        buffer,
+        vec![
+            0x01, 0x58, 0x00, 0x1c, 0x00, 0x04, 0x11, 0x22, 0x33, 0x44, 0x01, 0x59, 0x00, 0x06,
+            0x00, 0x02, 0x01, 0x59, 0x00, 0x06, 0x00, 0x01, 0x01, 0x59, 0x00, 0x06, 0x03, 0x04,
+        ]
+    );
+
+    assert_eq!(
+        LockPolicy::PermalockKillPassword.payloads(),
+        vec![C1G2LockPayload {
+            privilege: C1G2LockPrivilege::Perma_Lock,
+            data_field: C1G2LockDataField::Kill_Password,
+        }]
+    );
+}
+
+#[test]
+fn block_permalock() {
+    use crate::access::{
+        block_permalock_op_spec, block_permalock_status_op_spec, permalocked_blocks,
+    };
+
+    let op_spec = block_permalock_op_spec(7, 0x11223344, 3, &[17, 16, 35]).unwrap();
+    assert_eq!(op_spec.block_pointer, 1);
+    assert_eq!(op_spec.block_mask_word_count, 2);
+    assert_eq!(op_spec.block_mask, vec![0xc000, 0x1000]);
+
+    let mut buffer = vec![];
+    crate::Encoder::new(&mut buffer).write(&op_spec);
+    assert_eq!(crate::Decoder::new(&buffer).read::<C1G2BlockPermalock>().unwrap(), op_spec);
+
+    let status_op_spec = block_permalock_status_op_spec(8, 0, 3, 20, 30);
+    assert_eq!(status_op_spec.block_pointer, 1);
+    assert_eq!(status_op_spec.block_range, 3);
+
+    assert!(block_permalock_op_spec(7, 0, 3, &[]).is_none());
+    let status_op_spec = block_permalock_status_op_spec(8, 0, 3, 65530, 100);
+    assert_eq!(status_op_spec.block_pointer, 4095);
+    assert_eq!(status_op_spec.block_range, 1);
+
+    assert_eq!(permalocked_blocks(1, &[0xc000, 0x0000, 0x0001]), vec![16, 17, 63]);
+    assert!(permalocked_blocks(0, &[0]).is_empty());
+    // The second word starts past the last addressable block
+    assert_eq!(permalocked_blocks(4095, &[0x8001, 0xffff]), vec![65520, 65535]);
+}
+
+#[derive(Debug, PartialEq)]
+struct ImpinjSetQTConfig {
+    op_spec_id: u16,
+    access_password: u32,
+    data_profile: u8,
+    access_range: u8,
+    persistence: u8,
+}
+
+impl crate::custom::CustomParameter for ImpinjSetQTConfig {
+    const VENDOR_ID: u32 = 25882;
+    const SUBTYPE: u32 = 30;
+
+    fn decode_data(data: &[u8]) -> crate::Result<Self> {
+        let mut decoder = crate::Decoder::new(data);
+        Ok(ImpinjSetQTConfig {
+            op_spec_id: decoder.read()?,
+            access_password: decoder.read()?,
+            data_profile: decoder.read()?,
+            access_range: decoder.read()?,
+            persistence: decoder.read()?,
+        })
+    }
+
+    fn encode_data(&self, buffer: &mut Vec<u8>) {
+        let mut encoder = crate::Encoder::new(buffer);
+        encoder.write(&self.op_spec_id);
+        encoder.write(&self.access_password);
+        encoder.write(&self.data_profile);
+        encoder.write(&self.access_range);
+        encoder.write(&self.persistence);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct ImpinjRFPhaseAngle {
+    phase_angle: u16,
+}
+
+impl crate::TlvParameter for ImpinjRFPhaseAngle {
+    const ID: u16 = 1023;
+}
+
+impl crate::LLRPValue for ImpinjRFPhaseAngle {
+    fn can_decode_type(type_num: u16) -> bool {
+        type_num == <Self as crate::TlvParameter>::ID
+    }
+
+    fn decode(decoder: &mut crate::Decoder) -> crate::Result<Self> {
+        decoder.tlv_param(<Self as crate::TlvParameter>::ID, |decoder| {
+            let header = decoder.read_bytes(8)?;
+            if header != [0x00, 0x00, 0x65, 0x1a, 0x00, 0x00, 0x00, 0x38] {
+                return Err(crate::Error::InvalidType(<Self as crate::TlvParameter>::ID));
+            }
+            let data = decoder.read_to_end();
+            Ok(ImpinjRFPhaseAngle { phase_angle: crate::Decoder::new(data).read()? })
+        })
+    }
+
+    fn encode(&self, encoder: &mut crate::Encoder) {
+        encoder.tlv_param(<Self as crate::TlvParameter>::ID, |encoder| {
+            encoder.write_bytes(&[0x00, 0x00, 0x65, 0x1a, 0x00, 0x00, 0x00, 0x38]);
+            encoder.write(&self.phase_angle);
+        })
+    }
+}
+
+#[test]
+fn external_tlv_parameter() {
+    let bytes = &[
+        0x03, 0xff, 0x00, 0x0e, 0x00, 0x00, 0x65, 0x1a, 0x00, 0x00, 0x00, 0x38, 0x07, 0xd0,
+    ];
+
+    let mut buffer = vec![];
+    crate::Encoder::new(&mut buffer).write(&ImpinjRFPhaseAngle { phase_angle: 2000 });
+    assert_eq!(buffer, bytes);
+
+    let custom: Custom = crate::Decoder::new(bytes).read().unwrap();
+    assert_eq!(
+        custom,
+        Custom { vendor_identifier: 25882, parameter_subtype: 56, data: vec![0x07, 0xd0] }
+    );
+
+    let mut decoder = crate::Decoder::new(bytes);
+    let value: Option<ImpinjRFPhaseAngle> = decoder.read().unwrap();
+    assert_eq!(value, Some(ImpinjRFPhaseAngle { phase_angle: 2000 }));
+    decoder.validate_consumed().unwrap();
+}
+
+#[test]
+fn custom_op_spec() {
+    let op_spec = ImpinjSetQTConfig {
+        op_spec_id: 3,
+        access_password: 0x12345678,
+        data_profile: 1,
+        access_range: 0,
+        persistence: 1,
+    };
+
+    let value = AccessCommandOpSpec::custom(&op_spec);
+    let mut buffer = vec![];
+    crate::Encoder::new(&mut buffer).write(&value);
+    assert_eq!(
+        buffer,
+        vec![
+            0x03, 0xff, 0x00, 0x15, 0x00, 0x00, 0x65, 0x1a, 0x00, 0x00, 0x00, 0x1e, 0x00, 0x03,
+            0x12, 0x34, 0x56, 0x78, 0x01, 0x00, 0x01
+        ]
+    );
+
+    let decoded: AccessCommandOpSpec = crate::Decoder::new(&buffer).read().unwrap();
+    match decoded {
+        AccessCommandOpSpec::Custom(custom) => {
+            assert!(custom.is::<ImpinjSetQTConfig>());
+            assert_eq!(custom.to_parameter::<ImpinjSetQTConfig>().unwrap().unwrap(), op_spec);
+        }
+        other => panic!("Unexpected OpSpec: {:?}", other),
+    }
+
+    let result = AccessCommandOpSpecResult::Custom(Custom {
+        vendor_identifier: 25882,
+        parameter_subtype: 31,
+        data: vec![0x00, 0x03],
+    });
+    assert!(result.to_custom::<ImpinjSetQTConfig>().is_none());
+}
+
+#[derive(Debug, PartialEq)]
+struct ImpinjEnableExtensions {
+    reserved: u32,
+}
+
+impl crate::custom::CustomMessageType for ImpinjEnableExtensions {
+    const VENDOR_ID: u32 = 25882;
+    const SUBTYPE: u8 = 21;
+
+    fn decode_data(data: &[u8]) -> crate::Result<Self> {
+        Ok(ImpinjEnableExtensions { reserved: crate::Decoder::new(data).read()? })
+    }
+
+    fn encode_data(&self, buffer: &mut Vec<u8>) {
+        crate::Encoder::new(buffer).write(&self.reserved);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum VendorMessage {
+    EnableExtensions(ImpinjEnableExtensions),
+    EnableExtensionsResponse(Vec<u8>),
+}
+
+#[test]
+fn custom_message_registry() {
+    let message = CustomMessage::from_custom(&ImpinjEnableExtensions { reserved: 0 });
+    let binary = BinaryMessage::from_message(1, message.clone()).unwrap();
+    assert_eq!(binary.value, vec![0x00, 0x00, 0x65, 0x1a, 0x15, 0x00, 0x00, 0x00, 0x00]);
+    assert!(message.is::<ImpinjEnableExtensions>());
+    assert_eq!(message.to_custom().unwrap(), Ok(ImpinjEnableExtensions { reserved: 0 }));
+
+    let registry = crate::custom::CustomMessageRegistry::new()
+        .register(VendorMessage::EnableExtensions)
+        .register_fn(25882, 22, |data| Ok(VendorMessage::EnableExtensionsResponse(data.to_vec())));
+    assert!(registry.contains(25882, 21));
+    assert!(!registry.contains(25882, 23));
+
+    assert_eq!(
+        registry.decode_message(&binary).unwrap(),
+        Ok(VendorMessage::EnableExtensions(ImpinjEnableExtensions { reserved: 0 }))
+    );
+
+    let response = CustomMessage { vendor_identifier: 25882, message_subtype: 22, data: vec![1] };
+    assert_eq!(
+        registry.decode(&response).unwrap(),
+        Ok(VendorMessage::EnableExtensionsResponse(vec![1]))
+    );
+
+    // Messages from unregistered vendors, and other message types, are not decoded
+    let other = CustomMessage { vendor_identifier: 1, message_subtype: 21, data: vec![] };
+    assert!(registry.decode(&other).is_none());
+    let keepalive = BinaryMessage::from_message(2, Keepalive {}).unwrap();
+    assert!(registry.decode_message(&keepalive).is_none());
+
+    // Malformed data is reported as an error
+    let truncated = CustomMessage { data: vec![0x00], ..message };
+    assert!(registry.decode(&truncated).unwrap().is_err());
+}
+#[test]
+fn validate_required_parameters() {
+    let mut msg = AddAccessspec {
+        access_spec: crate::access::write_epc_access_spec(1, &[0; 12], &[1; 12], false).unwrap(),
+    };
+    assert!(msg.validate().is_ok());
+    assert!(BinaryMessage::from_message(1, msg.clone()).is_ok());
+
+    msg.access_spec.access_command.access_command_op_spec.clear();
+    match BinaryMessage::from_message(1, msg.clone()) {
+        Err(crate::Error::MissingParameter { parent, missing }) => {
+            assert_eq!((parent, missing), ("AccessCommand", "AccessCommandOpSpec"))
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+    assert!(Message::from(msg).validate().is_err());
+}
+
+#[test]
+fn decode_missing_parameter() {
+    match AddRospec::decode(&[]) {
+        Err(crate::Error::MissingParameter { parent, missing }) => {
+            assert_eq!((parent, missing), ("AddRospec", "ROSpec"))
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+
+    // A `TagReportData` parameter containing only a `ROSpecID`
+    let bytes = &[0x00, 0xf0, 0x00, 0x09, 0x81, 0x00, 0x01, 0x86, 0xbc];
+    match crate::Decoder::new(bytes).read::<TagReportData>() {
+        Err(crate::Error::MissingParameter { parent, missing }) => {
+            assert_eq!((parent, missing), ("TagReportData", "EPCParameter"))
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn structured_errors() {
+    // An `LLRPStatus` parameter with the type num of a `FieldError` parameter
+    let bytes = &[0x01, 0x20, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00];
+    let err = crate::Decoder::new(bytes).read::<LLRPStatus>().unwrap_err();
+    assert_eq!(err, crate::Error::UnexpectedType { expected: 287, found: 288 });
+    assert_eq!(err.clone(), err);
+
+    // An `LLRPStatus` parameter with an error description that is not valid UTF-8
+    let bytes = &[0x01, 0x1f, 0x00, 0x09, 0x00, 0x00, 0x00, 0x01, 0xff];
+    match crate::Decoder::new(bytes).read::<LLRPStatus>() {
+        Err(crate::Error::InvalidString(e)) => assert_eq!(e.valid_up_to(), 0),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+
+    let err = crate::Error::from(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "eof"));
+    assert_eq!(err.clone(), crate::Error::IoError(std::io::ErrorKind::UnexpectedEof.into()));
+    assert_ne!(err, crate::Error::TrailingBytes(1));
+}
+
+#[test]
+fn tv_parameters() {
+    // A `TagReportData` parameter containing every TV parameter that it allows
+    let bytes = &[
+        0x00, 0xf0, 0x00, 0x5c, 0x8d, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x01, 0x89, 0x00, 0x00, 0x00, 0x01, 0x8e, 0x00, 0x02, 0x8a, 0x00, 0x03, 0x81, 0x00,
+        0x04, 0x86, 0xc4, 0x87, 0x00, 0x05, 0x82, 0x00, 0x05, 0x88, 0x80, 0x19, 0x4b, 0xa9, 0xd5,
+        0x83, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x84, 0x00, 0x05, 0x88, 0x80, 0x19,
+        0x4b, 0xa9, 0xd6, 0x85, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x01, 0x88, 0x00, 0x06,
+        0x8c, 0x30, 0x00, 0x8b, 0x12, 0x34, 0x93, 0x00, 0x01, 0x94, 0x00, 0x02, 0x90, 0x00, 0x00,
+        0x00, 0x07,
+    ];
+
+    let data = TagReportData {
+        epc_parameter: EPCParameter::EPC_96([0x30, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01]),
+        ro_spec_id: Some(1),
+        spec_index: Some(2),
+        inventory_parameter_spec_id: Some(3),
+        antenna_id: Some(4),
+        peak_rssi: Some(-60),
+        channel_index: Some(5),
+        first_seen_timestamp_utc: Some(1_557_458_645_133_781),
+        first_seen_timestamp_uptime: Some(4096),
+        last_seen_timestamp_utc: Some(1_557_458_645_133_782),
+        last_seen_timestamp_uptime: Some(4097),
+        tag_seen_count: Some(6),
+        air_protocol_tag_data: vec![
+            AirProtocolTagData::C1G2_PC(0x3000),
+            AirProtocolTagData::C1G2_CRC(0x1234),
+            AirProtocolTagData::C1G2_XPCW1(1),
+            AirProtocolTagData::C1G2_XPCW2(2),
+        ],
+        access_spec_id: Some(7),
+        access_command_op_spec_result: vec![],
+        custom: vec![],
+    };
+
+    let mut buffer = vec![];
+    crate::Encoder::new(&mut buffer).write(&data);
+    assert_eq!(buffer, &bytes[..]);
+    assert_eq!(crate::Decoder::new(bytes).read::<TagReportData>().unwrap(), data);
+}
+
 #[test]
 fn get_reader_capabilities() {
     let bytes = &[0x04, 0x01, 0x00, 0x00, 0x00, 0x0b, 0x00, 0x00, 0x00, 0x00, 0x00];
@@ -899,6 +1726,23 @@ fn get_reader_capabilities_response() {
         max_tari_value: 6250,
         step_tari_value: 0,
     });
+    assert_eq!(uhf_mode_table.c1g2uhfrf_mode_table_entry[2], C1G2UHFRFModeTableEntry {
+        mode_identifier: 2,
+        dr_value: C1G2DRValue::DRV_64_3,
+        epchagtc_conformance: false,
+        reserved: 0,
+        m_value: C1G2MValue::MV_4,
+        forward_link_modulation: C1G2ForwardLinkModulation::PR_ASK,
+        spectral_mask_indicator: C1G2SpectralMaskIndicator::DI,
+        bdr_value: 274000,
+        pie_value: 2000,
+        min_tari_value: 20000,
+        max_tari_value: 20000,
+        step_tari_value: 0,
+    });
+    let mode_ids: Vec<_> =
+        uhf_mode_table.c1g2uhfrf_mode_table_entry.iter().map(|x| x.mode_identifier).collect();
+    assert_eq!(mode_ids, vec![0, 1, 2, 3, 4, 1000, 1001, 1002]);
 
     let air_protocol = msg.air_protocol_llrp_capabilities.unwrap();
     assert_eq!(
@@ -918,12 +1762,737 @@ fn get_reader_capabilities_response() {
 }
 
 #[test]
-fn custom_message() {
-    let bytes = &[
-        0x07, 0xff, 0x00, 0x00, 0x00, 0x13, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x65, 0x1a, 0x15,
-        0x00, 0x00, 0x00, 0x00,
+fn regulatory_capabilities_fixed_frequency() {
+    let bytes: &[u8] = &[
+        0x00, 0x8f, 0x00, 0x4b, 0x00, 0x24, 0x00, 0x05, 0x00, 0x90, 0x00, 0x43, 0x00, 0x91, 0x00,
+        0x08, 0x00, 0x01, 0x03, 0xe8, 0x00, 0x92, 0x00, 0x13, 0x00, 0x00, 0x94, 0x00, 0x0e, 0x00,
+        0x02, 0x00, 0x0e, 0x0a, 0xba, 0x00, 0x0e, 0x0c, 0xae, 0x01, 0x48, 0x00, 0x24, 0x01, 0x49,
+        0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x02, 0x02, 0x00, 0x09, 0xc4, 0x00, 0x00,
+        0x00, 0x05, 0xdc, 0x00, 0x00, 0x18, 0x6a, 0x00, 0x00, 0x18, 0x6a, 0x00, 0x00, 0x00, 0x00,
     ];
-    check_roundtrip(bytes);
+
+    let expected = RegulatoryCapabilities {
+        country_code: 36,
+        communications_standard: CommunicationsStandard::Australia_LIPD_4W,
+        uhf_band_capabilities: Some(UHFBandCapabilities {
+            transmit_power_level_table_entry: vec![TransmitPowerLevelTableEntry {
+                index: 1,
+                transmit_power_value: 1000,
+            }],
+            frequency_information: FrequencyInformation {
+                hopping: false,
+                reserved: 0,
+                frequency_hop_table: vec![],
+                fixed_frequency_table: Some(FixedFrequencyTable { frequency: vec![920250, 920750] }),
+            },
+            air_protocol_uhfrf_mode_table: vec![C1G2UHFRFModeTable {
+                c1g2uhfrf_mode_table_entry: vec![C1G2UHFRFModeTableEntry {
+                    mode_identifier: 0,
+                    dr_value: C1G2DRValue::DRV_64_3,
+                    epchagtc_conformance: false,
+                    reserved: 0,
+                    m_value: C1G2MValue::MV_FM0,
+                    forward_link_modulation: C1G2ForwardLinkModulation::DSB_ASK,
+                    spectral_mask_indicator: C1G2SpectralMaskIndicator::MI,
+                    bdr_value: 640000,
+                    pie_value: 1500,
+                    min_tari_value: 6250,
+                    max_tari_value: 6250,
+                    step_tari_value: 0,
+                }],
+            }
+            .into()],
+        }),
+        custom: vec![],
+    };
+
+    let mut decoder = crate::Decoder::new(bytes);
+    assert_eq!(decoder.read::<RegulatoryCapabilities>().unwrap(), expected);
+    assert!(decoder.peek_param_type().is_err());
+
+    let mut buffer = vec![];
+    crate::Encoder::new(&mut buffer).write(&expected);
+    assert_eq!(buffer, bytes);
+}
+
+#[test]
+fn general_device_capabilities_sensitivity_range() {
+    let capabilities = GeneralDeviceCapabilities {
+        max_number_of_antenna_supported: 2,
+        can_set_antenna_properties: true,
+        has_utc_clock_capability: true,
+        reserved: 0,
+        device_manufacturer_name: 25882,
+        model_name: 2001002,
+        reader_firmware_version: "5.2.1.240".into(),
+        receive_sensitivity_table_entry: vec![ReceiveSensitivityTableEntry {
+            index: 1,
+            receive_sensitivity_value: 0,
+        }],
+        per_antenna_receive_sensitivity_range: vec![
+            PerAntennaReceiveSensitivityRange {
+                antenna_id: 1,
+                receive_sensitivity_index_min: 1,
+                receive_sensitivity_index_max: 42,
+            },
+            PerAntennaReceiveSensitivityRange {
+                antenna_id: 2,
+                receive_sensitivity_index_min: 1,
+                receive_sensitivity_index_max: 20,
+            },
+        ],
+        gpio_capabilities: GPIOCapabilities { num_gpis: 0, num_gpos: 0 },
+        per_antenna_air_protocol: vec![PerAntennaAirProtocol {
+            antenna_id: 1,
+            protocol_id: vec![AirProtocols::EPCGlobalClass1Gen2],
+        }],
+    };
+
+    let mut buffer = vec![];
+    crate::Encoder::new(&mut buffer).write(&capabilities);
+
+    // PerAntennaReceiveSensitivityRange parameters are encoded directly after the receive
+    // sensitivity table
+    let range_offset = 4 + 12 + (2 + 9) + 8;
+    assert_eq!(&buffer[range_offset..range_offset + 20], &[
+        0x00, 0x95, 0x00, 0x0a, 0x00, 0x01, 0x00, 0x01, 0x00, 0x2a, 0x00, 0x95, 0x00, 0x0a, 0x00,
+        0x02, 0x00, 0x01, 0x00, 0x14,
+    ]);
+
+    let mut decoder = crate::Decoder::new(&buffer);
+    assert_eq!(decoder.read::<GeneralDeviceCapabilities>().unwrap(), capabilities);
+}
+
+#[test]
+fn c1g2_filter_actions() {
+    let bytes: &[u8] = &[
+        0x01, 0x4b, 0x00, 0x16, 0x40, 0x01, 0x4c, 0x00, 0x0b, 0x40, 0x00, 0x20, 0x00, 0x10, 0x30,
+        0x14, 0x01, 0x4d, 0x00, 0x06, 0x02, 0x00, 0x01, 0x4b, 0x00, 0x14, 0x00, 0x01, 0x4c, 0x00,
+        0x0a, 0xc0, 0x00, 0x00, 0x00, 0x08, 0xff, 0x01, 0x4e, 0x00, 0x05, 0x01,
+    ];
+
+    let expected = vec![
+        C1G2Filter {
+            t: C1G2TruncateAction::Do_Not_Truncate,
+            reserved: 0,
+            c1g2_tag_inventory_mask: C1G2TagInventoryMask {
+                mb: 1,
+                reserved: 0,
+                pointer: 0x20,
+                tag_mask: BitArray::from_bytes(vec![0x30, 0x14]),
+            },
+            c1g2_tag_inventory_state_aware_filter_action: Some(
+                C1G2TagInventoryStateAwareFilterAction {
+                    target: C1G2StateAwareTarget::Inventoried_State_For_Session_S1,
+                    action: C1G2StateAwareAction::AssertSLOrA_DeassertSLOrB,
+                },
+            ),
+            c1g2_tag_inventory_state_unaware_filter_action: None,
+        },
+        C1G2Filter {
+            t: C1G2TruncateAction::Unspecified,
+            reserved: 0,
+            c1g2_tag_inventory_mask: C1G2TagInventoryMask {
+                mb: 3,
+                reserved: 0,
+                pointer: 0,
+                tag_mask: BitArray::from_bytes(vec![0xff]),
+            },
+            c1g2_tag_inventory_state_aware_filter_action: None,
+            c1g2_tag_inventory_state_unaware_filter_action: Some(
+                C1G2TagInventoryStateUnawareFilterAction {
+                    action: C1G2StateUnawareAction::Select_DoNothing,
+                },
+            ),
+        },
+    ];
+
+    let mut decoder = crate::Decoder::new(bytes);
+    assert_eq!(decoder.read::<Vec<C1G2Filter>>().unwrap(), expected);
+    assert!(decoder.peek_param_type().is_err());
+
+    let mut buffer = vec![];
+    crate::Encoder::new(&mut buffer).write(&expected);
+    assert_eq!(buffer, bytes);
+}
+
+#[test]
+fn decoder_rollback() {
+    let bytes = &[0x00, 0x80, 0x00, 0x0c, 0x00, 0x05, 0x88, 0x80, 0x19, 0x4b, 0xa9, 0xd5];
+    let mut decoder = crate::Decoder::new(bytes);
+
+    // A failed attempt must leave the decoder where it started, even if bytes were consumed
+    let result = decoder.try_decode(|decoder| {
+        decoder.read::<u16>()?;
+        decoder.read::<EPCData>()
+    });
+    assert!(result.is_err());
+    assert_eq!(decoder.remaining(), bytes.len());
+
+    let checkpoint = decoder.checkpoint();
+    assert_eq!(
+        decoder.try_decode(|decoder| decoder.read::<UTCTimestamp>()).unwrap(),
+        UTCTimestamp { microseconds: 1557458645133781 }
+    );
+    assert_eq!(decoder.remaining(), 0);
+
+    decoder.rollback(checkpoint);
+    assert_eq!(decoder.remaining(), bytes.len());
+    assert_eq!(decoder.read::<UTCTimestamp>().unwrap().microseconds, 1557458645133781);
+}
+
+#[test]
+fn bit_fields_roundtrip() {
+    // Simple xorshift generator, so that the test is deterministic
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+
+    for _ in 0..1000 {
+        let fields: Vec<(u8, u32)> = (0..next() % 16 + 1)
+            .map(|_| {
+                let num_bits = (next() % crate::MAX_BITS as u64) as u8 + 1;
+                let value = next() as u32 & (u32::MAX >> (32 - num_bits));
+                (num_bits, value)
+            })
+            .collect();
+        let padding = (8 - fields.iter().map(|&(n, _)| n as usize).sum::<usize>() % 8) % 8;
+
+        let mut buffer = vec![];
+        let mut encoder = crate::Encoder::new(&mut buffer);
+        for (num_bits, value) in &fields {
+            encoder.write_bits(value, *num_bits);
+        }
+        encoder.write_bits(&0u8, padding as u8);
+
+        let mut decoder = crate::Decoder::new(&buffer);
+        for &(num_bits, value) in &fields {
+            assert_eq!(decoder.read_bits::<u32>(num_bits).unwrap(), value, "{:?}", fields);
+        }
+        assert_eq!(decoder.read_bits::<u8>(padding as u8).unwrap(), 0);
+        decoder.validate_consumed().unwrap();
+    }
+
+    // Bits outside of the width of a field must not affect the neighbouring fields
+    let mut buffer = vec![];
+    let mut encoder = crate::Encoder::new(&mut buffer);
+    encoder.write_bits(&0u8, 4);
+    encoder.write_bits(&0xffu8, 4);
+    assert_eq!(buffer, [0x0f]);
+}
+
+#[test]
+fn parameter_headers() {
+    use crate::{parse_param_type, parse_tlv_header, ParameterType};
+
+    let bytes = &[0x00, 0x80, 0x00, 0x0c, 0x00, 0x05, 0x88, 0x80, 0x19, 0x4b, 0xa9, 0xd5];
+    assert_eq!(parse_param_type(bytes).unwrap(), ParameterType::Tlv(128));
+    assert_eq!(parse_tlv_header(bytes).unwrap(), (128, 12));
+    assert!(parse_tlv_header(&[0x00, 0x80, 0x00, 0x02]).is_err());
+    assert!(parse_tlv_header(&[0x81, 0x00, 0x01, 0x00]).is_err());
+
+    let mut buffer = vec![];
+    crate::write_tlv_header(&mut buffer, 128, 12);
+    assert_eq!(buffer, &bytes[..4]);
+
+    let bytes = &[0x86, 0x00, 0x05, 0x88, 0x80, 0x19, 0x4b, 0xa9, 0xd5];
+    assert_eq!(parse_param_type(bytes).unwrap(), ParameterType::Tv(6));
+
+    let mut buffer = vec![];
+    crate::write_tv_type(&mut buffer, 6);
+    assert_eq!(buffer, &bytes[..1]);
+}
+
+#[test]
+fn read_message_into() {
+    let get_reader_capabilities =
+        [0x04, 0x01, 0x00, 0x00, 0x00, 0x0b, 0x00, 0x00, 0x00, 0x08, 0x00];
+    let keepalive = [0x04, 0x3e, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x07];
+
+    let mut stream = get_reader_capabilities.to_vec();
+    stream.extend_from_slice(&keepalive);
+    let mut reader = Cursor::new(&stream);
+
+    let mut buffer = Vec::with_capacity(64);
+    let ptr = buffer.as_ptr();
+
+    let header = crate::read_message_into(&mut reader, &mut buffer).unwrap();
+    assert_eq!(
+        header,
+        crate::MessageHeader { ver: 1, message_type: GetReaderCapabilities::ID, id: 8 }
+    );
+    assert_eq!(buffer, [0x00]);
+
+    let header = crate::read_message_into(&mut reader, &mut buffer).unwrap();
+    assert_eq!((header.message_type, header.id), (Keepalive::ID, 7));
+    assert!(buffer.is_empty());
+    assert_eq!(buffer.as_ptr(), ptr);
+}
+
+#[test]
+fn version_policy() {
+    use crate::VersionPolicy;
+
+    // A `KEEPALIVE` message with version 2
+    let bytes = [0x08, 0x3e, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x07];
+    let raw = read_message(Cursor::new(&bytes)).unwrap();
+
+    assert_eq!(raw.check_version(2, VersionPolicy::Strict), Ok(()));
+    assert_eq!(
+        raw.check_version(1, VersionPolicy::Strict),
+        Err(crate::Error::VersionMismatch { expected: 1, found: 2 })
+    );
+    assert_eq!(raw.check_version(1, VersionPolicy::Warn), Ok(()));
+    assert_eq!(raw.check_version(1, VersionPolicy::Accept), Ok(()));
+}
+
+#[test]
+fn version_check() {
+    use crate::{VersionCheck, VersionPolicy};
+    use std::io::BufReader;
+
+    // `KEEPALIVE` messages with version 2 and version 1
+    let v2 = [0x08, 0x3e, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x07];
+    let v1 = [0x04, 0x3e, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x08];
+    let stream = [&v2[..], &v1[..]].concat();
+    let mismatch = crate::Error::VersionMismatch { expected: 1, found: 2 };
+
+    let check = VersionCheck::new(1, VersionPolicy::Strict);
+
+    // The rejected frame is consumed, so the next read returns the following frame
+    let mut reader = Cursor::new(&stream);
+    assert_eq!(check.read_message(&mut reader).unwrap_err(), mismatch);
+    assert_eq!(check.read_message(&mut reader).unwrap().id, 8);
+
+    let mut reader = Cursor::new(&stream);
+    let mut buffer = vec![];
+    assert_eq!(check.read_message_into(&mut reader, &mut buffer).unwrap_err(), mismatch);
+    assert_eq!(check.read_message_into(&mut reader, &mut buffer).unwrap().id, 8);
+
+    let mut reader = Cursor::new(&stream);
+    assert_eq!(check.read_raw_message(&mut reader).unwrap_err(), mismatch);
+    assert_eq!(check.read_raw_message(&mut reader).unwrap().header().id, 8);
+
+    // Frames that are fully buffered and frames that are split across reads
+    for &capacity in &[1024, 4] {
+        let mut reader = BufReader::with_capacity(capacity, Cursor::new(&stream));
+        assert_eq!(check.read_dynamic_message(&mut reader).unwrap_err(), mismatch);
+        let (header, msg) = check.read_dynamic_message(&mut reader).unwrap();
+        assert_eq!(header.id, 8);
+        assert_eq!(msg, Keepalive {}.into());
+    }
+
+    let accept = VersionCheck::new(1, VersionPolicy::Accept);
+    assert_eq!(accept.read_message(Cursor::new(&v2)).unwrap().ver, 2);
+}
+
+#[test]
+fn read_dynamic_message() {
+    use std::io::BufReader;
+
+    let keepalive = [0x04, 0x3e, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x07];
+    let get_reader_capabilities =
+        [0x04, 0x01, 0x00, 0x00, 0x00, 0x0b, 0x00, 0x00, 0x00, 0x08, 0x00];
+    let mut stream = keepalive.to_vec();
+    stream.extend_from_slice(&[0x04, 0x01, 0x00, 0x00, 0x00, 0x0b, 0x00, 0x00, 0x00, 0x09, 0x07]);
+    stream.extend_from_slice(&get_reader_capabilities);
+
+    // Frames that are fully buffered and frames that are split across reads
+    for &capacity in &[1024, 16] {
+        let mut reader = BufReader::with_capacity(capacity, Cursor::new(&stream));
+
+        let (header, msg) = crate::read_dynamic_message(&mut reader).unwrap();
+        assert_eq!(header.id, 7);
+        assert_eq!(msg, Keepalive {}.into());
+
+        // A message that fails to decode is still consumed
+        assert!(crate::read_dynamic_message(&mut reader).is_err());
+
+        let (header, msg) = crate::read_dynamic_message(&mut reader).unwrap();
+        assert_eq!(header.id, 8);
+        assert_eq!(msg.message_type(), GetReaderCapabilities::ID);
+    }
+}
+
+#[test]
+fn read_message_resync() {
+    let keepalive = [0x04, 0x3e, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x07];
+    let get_reader_capabilities =
+        [0x04, 0x01, 0x00, 0x00, 0x00, 0x0b, 0x00, 0x00, 0x00, 0x08, 0x00];
+
+    // The end of a corrupted frame, followed by two complete frames
+    let mut stream = vec![0x3a, 0xfb, 0xff, 0x00, 0x01, 0x86, 0xbc];
+    stream.extend_from_slice(&keepalive);
+    stream.extend_from_slice(&get_reader_capabilities);
+    let mut reader = Cursor::new(&stream);
+
+    let (msg, skipped) = crate::read_message_resync(&mut reader, 1024).unwrap();
+    assert_eq!(skipped, 7);
+    assert_eq!((msg.message_type, msg.id), (Keepalive::ID, 7));
+
+    let (msg, skipped) = crate::read_message_resync(&mut reader, 1024).unwrap();
+    assert_eq!(skipped, 0);
+    assert_eq!((msg.message_type, msg.id), (GetReaderCapabilities::ID, 8));
+
+    // Frames larger than `max_len` are skipped
+    let mut reader = Cursor::new(&get_reader_capabilities);
+    assert!(crate::read_message_resync(&mut reader, 10).is_err());
+}
+
+#[test]
+fn lenient_decode() {
+    use crate::DecodeWarning;
+
+    // An `ERROR_MESSAGE` where the `LLRPStatus` contains an unknown parameter, followed by a byte
+    // of padding
+    let bytes = &[
+        0x04, 0x64, 0x00, 0x00, 0x00, 0x17, 0x00, 0x00, 0x00, 0x01, 0x01, 0x1f, 0x00, 0x0c, 0x00,
+        0x00, 0x00, 0x00, 0x03, 0xe7, 0x00, 0x04, 0x00,
+    ];
+    let raw = read_message(Cursor::new(bytes)).unwrap();
+    assert_eq!(raw.to_dynamic_message().unwrap_err(), crate::Error::TrailingBytes(4));
+
+    let (msg, warnings) = raw.to_dynamic_message_lenient().unwrap();
+    assert_eq!(msg.status().unwrap().status_code, StatusCode::M_Success);
+    assert_eq!(
+        warnings,
+        vec![
+            DecodeWarning::UnexpectedParameter { parent: 287, type_num: 999, len: 4 },
+            DecodeWarning::TrailingBytes { parent: ErrorMessage::ID, len: 1 },
+        ]
+    );
+
+    // Lenient decoding is a property of the decoder, so it also works on other threads, and
+    // `to_dynamic_message` remains strict
+    let (_, warnings) =
+        std::thread::spawn(move || raw.to_dynamic_message_lenient().unwrap()).join().unwrap();
+    assert_eq!(warnings.len(), 2);
+
+    // Decoders, including lenient ones, can be used from other threads
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+    assert_send::<crate::Decoder>();
+    assert_sync::<crate::Decoder>();
+
+    // A `TagReportData` with a vendor `Custom` parameter before `AntennaID` and `PeakRSSI`
+    let bytes = &[
+        0x04, 0x3d, 0x00, 0x00, 0x00, 0x2d, 0x00, 0x00, 0x00, 0x02, 0x00, 0xf0, 0x00, 0x23, 0x8d,
+        0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x03, 0xff, 0x00,
+        0x0d, 0x00, 0x00, 0x65, 0x1a, 0x00, 0x00, 0x00, 0x38, 0x07, 0x81, 0x00, 0x02, 0x86, 0xc0,
+    ];
+    let raw = read_message(Cursor::new(bytes)).unwrap();
+    assert!(raw.to_dynamic_message().is_err());
+
+    let (msg, warnings) = raw.to_dynamic_message_lenient().unwrap();
+    let report = match msg {
+        Message::RoAccessReport(report) => report,
+        other => panic!("Unexpected message: {:?}", other),
+    };
+    assert_eq!(report.tag_report_data[0].antenna_id, Some(2));
+    assert_eq!(report.tag_report_data[0].peak_rssi, Some(-64));
+    assert_eq!(
+        warnings,
+        vec![DecodeWarning::UnexpectedParameter { parent: 240, type_num: 1023, len: 13 }]
+    );
+
+    // A `TagReportData` with a `C1G2ReadOpSpecResult` that has an unknown result code
+    let bytes = &[
+        0x04, 0x3d, 0x00, 0x00, 0x00, 0x26, 0x00, 0x00, 0x00, 0x03, 0x00, 0xf0, 0x00, 0x1c, 0x8d,
+        0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x86, 0xc0, 0x01,
+        0x5d, 0x00, 0x09, 0xc8, 0x00, 0x01, 0x00, 0x00,
+    ];
+    let raw = read_message(Cursor::new(bytes)).unwrap();
+    assert_eq!(raw.to_dynamic_message().unwrap_err(), crate::Error::InvalidVariant(200));
+
+    let (msg, warnings) = raw.to_dynamic_message_lenient().unwrap();
+    let report = match msg {
+        Message::RoAccessReport(report) => report,
+        other => panic!("Unexpected message: {:?}", other),
+    };
+    assert_eq!(report.tag_report_data[0].peak_rssi, Some(-64));
+    assert!(report.tag_report_data[0].access_command_op_spec_result.is_empty());
+    assert_eq!(
+        warnings,
+        vec![DecodeWarning::UnknownEnumValue { parent: 240, type_num: 349, value: 200 }]
+    );
+
+    // A `SET_READER_CONFIG` with a reserved bit set
+    let bytes = &[0x04, 0x03, 0x00, 0x00, 0x00, 0x0b, 0x00, 0x00, 0x00, 0x04, 0x81];
+    let raw = read_message(Cursor::new(bytes)).unwrap();
+    assert!(raw.to_dynamic_message().is_ok());
+    let (_, warnings) = raw.to_dynamic_message_lenient().unwrap();
+    assert_eq!(
+        warnings,
+        vec![DecodeWarning::ReservedBitsSet { parent: SetReaderConfig::ID, value: 1 }]
+    );
+}
+
+#[test]
+fn encode_checked() {
+    use crate::Error;
+
+    fn check(message: impl Into<Message>) -> crate::Result<usize> {
+        message.into().encode_checked(&mut vec![])
+    }
+
+    let error_message = |error_description: &str| ErrorMessage {
+        status: LLRPStatus {
+            status_code: StatusCode::M_Success,
+            error_description: error_description.into(),
+            field_error: None,
+            parameter_error: None,
+        },
+    };
+    assert_eq!(check(error_message("abc")), Ok(11));
+    assert_eq!(check(error_message(&"a".repeat(70000))), Err(Error::StringTooLong(70000)));
+
+    let write_access_spec = |word_pointer, len| {
+        let mut spec = crate::access::write_epc_access_spec(1, &[0; 12], &[0; 12], false).unwrap();
+        spec.access_command.access_command_op_spec = vec![C1G2Write {
+            op_spec_id: 1,
+            access_password: 0,
+            mb: 3,
+            reserved: 0,
+            word_pointer,
+            write_data: vec![0; len],
+        }
+        .into()];
+        AddAccessspec { access_spec: spec }
+    };
+    assert_eq!(check(write_access_spec(0, 70000)), Err(Error::ArrayTooLong(70000)));
+    assert_eq!(check(write_access_spec(0, 40000)), Err(Error::ParameterTooLong(80015)));
+
+    let mut msg = write_access_spec(0, 1);
+    if let AccessCommandOpSpec::C1G2Write(op_spec) =
+        &mut msg.access_spec.access_command.access_command_op_spec[0]
+    {
+        op_spec.mb = 4;
+    }
+    assert_eq!(check(msg.clone()), Err(Error::ValueOutOfRange { value: 4, num_bits: 2 }));
+    assert!(BinaryMessage::from_message(1, msg).is_err());
+}
+
+#[test]
+fn encode_fixed_buffer() {
+    let bytes: &[u8] = &[
+        0x04, 0x3d, 0x00, 0x00, 0x00, 0x29, 0x3a, 0xfb, 0x30, 0xb6, 0x00, 0xf0, 0x00, 0x1f, 0x8d,
+        0x0b, 0x7f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x51, 0x02, 0x38, 0x81, 0x00, 0x01,
+        0x86, 0xbc, 0x82, 0x00, 0x05, 0x88, 0x80, 0x19, 0x4b, 0xa9, 0xd5,
+    ];
+    let raw = read_message(Cursor::new(bytes)).unwrap();
+    let msg: RoAccessReport = raw.to_message().unwrap();
+
+    let mut buffer = [0; 64];
+    assert_eq!(msg.encode_to_slice(&mut buffer).unwrap(), raw.value.len());
+    assert_eq!(&buffer[..raw.value.len()], &raw.value[..]);
+
+    let len = crate::encode_message(raw.id, &msg, &mut buffer).unwrap();
+    assert_eq!(&buffer[..len], bytes);
+
+    for size in &[0, 4, 10, 30, 40] {
+        match crate::encode_message(raw.id, &msg, &mut buffer[..*size]) {
+            Err(crate::Error::BufferTooSmall { needed, available }) => {
+                assert_eq!(needed, bytes.len());
+                assert_eq!(available, *size);
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn message_summary() {
+    let bytes: &[u8] = &[
+        0x04, 0x3d, 0x00, 0x00, 0x00, 0x29, 0x3a, 0xfb, 0x30, 0xb6, 0x00, 0xf0, 0x00, 0x1f, 0x8d,
+        0x0b, 0x7f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x51, 0x02, 0x38, 0x81, 0x00, 0x01,
+        0x86, 0xbc, 0x82, 0x00, 0x05, 0x88, 0x80, 0x19, 0x4b, 0xa9, 0xd5,
+    ];
+    let raw = read_message(Cursor::new(bytes)).unwrap();
+    assert_eq!(raw.summary(), "RO_ACCESS_REPORT id=989540534 tags=1");
+
+    let msg = Message::DeleteRospec(DeleteRospec { ro_spec_id: 1 });
+    assert_eq!(msg.summary(), "DELETE_ROSPEC ro_spec_id=1");
+
+    let msg = Message::CloseConnectionResponse(CloseConnectionResponse {
+        status: LLRPStatus {
+            status_code: StatusCode::M_Success,
+            error_description: "".into(),
+            field_error: None,
+            parameter_error: None,
+        },
+    });
+    assert_eq!(msg.summary(), "CLOSE_CONNECTION_RESPONSE status=M_Success");
+
+    let raw = BinaryMessage { ver: 1, message_type: 30, id: 7, value: vec![0x00] };
+    assert!(raw.summary().starts_with("type=30 id=7 len=1 error="));
+}
+
+#[test]
+fn spec_id_allocator() {
+    use crate::spec_id::SpecIdAllocator;
+
+    let epc = [0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01];
+    let response = GetAccessspecsResponse {
+        status: LLRPStatus {
+            status_code: StatusCode::M_Success,
+            error_description: "".into(),
+            field_error: None,
+            parameter_error: None,
+        },
+        access_spec: vec![
+            crate::access::write_epc_access_spec(1, &epc, &epc, false).unwrap(),
+            crate::access::write_epc_access_spec(2, &epc, &epc, false).unwrap(),
+            crate::access::write_epc_access_spec(4, &epc, &epc, false).unwrap(),
+        ],
+    };
+
+    let mut ids = SpecIdAllocator::from_access_specs(&response);
+    assert!(ids.is_used(4));
+    assert_eq!(ids.allocate(), Some(3));
+    assert_eq!(ids.allocate(), Some(5));
+
+    ids.release(2);
+    assert_eq!(ids.allocate(), Some(2));
+    assert!(!ids.reserve(5));
+    assert!(ids.reserve(6));
+    assert_eq!(ids.allocate(), Some(7));
+
+    let mut ids = SpecIdAllocator::new();
+    ids.reserve(0);
+    assert_eq!(ids.allocate(), Some(1));
+}
+
+#[test]
+fn llrp_string() {
+    let bytes = &[0x01, 0x1f, 0x00, 0x0b, 0x00, 0x00, 0x00, 0x03, 0x61, 0x62, 0x63];
+    let status: LLRPStatus = crate::Decoder::new(bytes).read().unwrap();
+    assert_eq!(status.error_description, "abc");
+    assert_eq!(status.error_description, String::from("abc"));
+    assert_eq!(status.error_description.len(), 3);
+    assert_eq!(
+        format!("{} {:?}", status.error_description, status.error_description),
+        "abc \"abc\""
+    );
+    assert_eq!(String::from(status.error_description.clone()), "abc");
+    assert_eq!(status.error_description, crate::LLRPString::from(String::from("abc")));
+}
+#[cfg(feature = "intern_strings")]
+#[test]
+fn interned_strings() {
+    let bytes = &[0x01, 0x1f, 0x00, 0x0b, 0x00, 0x00, 0x00, 0x03, 0x61, 0x62, 0x63];
+    let first: LLRPStatus = crate::Decoder::new(bytes).read().unwrap();
+    let second: LLRPStatus = crate::Decoder::new(bytes).read().unwrap();
+    assert_eq!(first.error_description, "abc");
+    assert_eq!(first.error_description.as_ptr(), second.error_description.as_ptr());
+}
+
+#[cfg(feature = "decode_profile")]
+#[test]
+fn decode_profile() {
+    use crate::profile::{decode_stats, reset_decode_stats};
+
+    reset_decode_stats();
+    let bytes = &[0x01, 0x1f, 0x00, 0x0b, 0x00, 0x00, 0x00, 0x03, 0x61, 0x62, 0x63];
+    for _ in 0..3 {
+        let _: LLRPStatus = crate::Decoder::new(bytes).read().unwrap();
+    }
+
+    let stats = decode_stats();
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].0, crate::ParameterType::Tlv(287));
+    assert_eq!(stats[0].1.count, 3);
+
+    reset_decode_stats();
+    assert!(decode_stats().is_empty());
+}
+
+#[test]
+fn choice_helpers() {
+    use std::convert::TryFrom;
+
+    let kill = C1G2Kill { op_spec_id: 1, kill_password: 0x1234 };
+    let op_spec = AccessCommandOpSpec::from(kill.clone());
+    assert_eq!(op_spec.type_num(), <C1G2Kill as crate::TlvParameter>::ID);
+    assert_eq!(op_spec.as_c1g2_kill(), Some(&kill));
+    assert_eq!(op_spec.as_c1g2_read(), None);
+    assert_eq!(C1G2Kill::try_from(op_spec.clone()).unwrap(), kill);
+    assert_eq!(C1G2Read::try_from(op_spec.clone()), Err(op_spec.clone()));
+    assert_eq!(op_spec.into_c1g2_kill(), Some(kill));
+
+    let epc = EPCParameter::EPC_96([0; 12]);
+    assert_eq!(epc.type_num(), 13);
+    assert_eq!(epc.as_epc_96(), Some(&[0; 12]));
+    assert_eq!(epc.into_epc_data(), None);
+}
+
+#[test]
+fn format_helpers() {
+    let target = C1G2TargetTag {
+        mb: 1,
+        match_: true,
+        reserved: 0,
+        pointer: 0x20,
+        tag_mask: BitArray::from_bytes(vec![0xff, 0xf0]),
+        tag_data: BitArray::from_bytes(vec![0x0b, 0x70]),
+    };
+    assert_eq!(target.tag_mask_hex(), "fff0");
+    assert_eq!(target.tag_data_hex(), "0b70");
+
+    let write = C1G2Write {
+        op_spec_id: 1,
+        access_password: 0,
+        mb: 3,
+        reserved: 0,
+        word_pointer: 0,
+        write_data: vec![0x1234, 0x00ab],
+    };
+    assert_eq!(write.write_data_hex(), "123400ab");
+
+    let timestamp = UTCTimestamp { microseconds: 1557458645133781 };
+    assert_eq!(
+        timestamp.microseconds_time(),
+        std::time::UNIX_EPOCH + std::time::Duration::from_micros(1557458645133781)
+    );
+}
+
+#[test]
+fn format_display() {
+    let write = C1G2Write {
+        op_spec_id: 1,
+        access_password: 0,
+        mb: 3,
+        reserved: 0,
+        word_pointer: 0,
+        write_data: vec![0x1234, 0x00ab],
+    };
+    assert_eq!(
+        write.to_string(),
+        "C1G2Write { op_spec_id: 1, access_password: 0, mb: 3, reserved: 0, word_pointer: 0, write_data: 123400ab }"
+    );
+
+    let timestamp = UTCTimestamp { microseconds: 1557458645133781 };
+    assert_eq!(timestamp.to_string(), "UTCTimestamp { microseconds: 2019-05-10T03:24:05.133781Z }");
+
+    assert_eq!(crate::format_datetime(0), "1970-01-01T00:00:00.000000Z");
+    assert_eq!(crate::format_datetime(951_782_400_000_001), "2000-02-29T00:00:00.000001Z");
+}
+
+#[test]
+fn namespaces() {
+    assert_eq!(crate::namespaces::LLRP, "http://www.llrp.org/ltk/schema/core/encoding/xml/1.0");
+}
+
+#[test]
+fn custom_message() {
+    let bytes = &[
+        0x07, 0xff, 0x00, 0x00, 0x00, 0x13, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x65, 0x1a, 0x15,
+        0x00, 0x00, 0x00, 0x00,
+    ];
+    check_roundtrip(bytes);
 
     let raw = read_message(Cursor::new(bytes)).unwrap();
 
@@ -939,3 +2508,923 @@ fn custom_message() {
     assert_eq!(msg.message_subtype, 21);
     assert_eq!(msg.data, vec![0; 4]);
 }
+
+#[test]
+fn visit_ro_access_report() {
+    use crate::visit::{Visitor, VisitorMut, Walk};
+
+    #[derive(Default)]
+    struct Collector {
+        epcs: Vec<[u8; 12]>,
+        read_results: usize,
+    }
+
+    impl<'ast> Visitor<'ast> for Collector {
+        fn visit_epc_96(&mut self, value: &EPC_96) {
+            self.epcs.push(*value);
+        }
+
+        fn visit_c1g2_read_op_spec_result(&mut self, _: &C1G2ReadOpSpecResult) {
+            self.read_results += 1;
+        }
+    }
+
+    struct Anonymizer;
+
+    impl VisitorMut for Anonymizer {
+        fn visit_epc_96_mut(&mut self, value: &mut EPC_96) {
+            *value = [0; 12];
+        }
+    }
+
+    let bytes: &[u8] = &[
+        0x04, 0x3d, 0x00, 0x00, 0x00, 0x32, 0x3a, 0xfb, 0x37, 0x05, 0x00, 0xf0, 0x00, 0x28, 0x8d,
+        0x0b, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x51, 0x02, 0x38, 0x81, 0x00, 0x01,
+        0x86, 0xbc, 0x82, 0x00, 0x05, 0x88, 0x80, 0x19, 0x83, 0x92, 0xa9, 0x01, 0x5d, 0x00, 0x09,
+        0x02, 0x00, 0x6f, 0x00, 0x00,
+    ];
+    let mut msg = read_message(Cursor::new(bytes)).unwrap().to_dynamic_message().unwrap();
+
+    let mut collector = Collector::default();
+    msg.walk(&mut collector);
+    assert_eq!(collector.epcs, vec![[
+        0x0b, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x51, 0x02, 0x38
+    ]]);
+    assert_eq!(collector.read_results, 1);
+
+    msg.walk_mut(&mut Anonymizer);
+
+    let mut collector = Collector::default();
+    msg.walk(&mut collector);
+    assert_eq!(collector.epcs, vec![[0; 12]]);
+}
+
+#[test]
+fn find_all_parameters() {
+    use crate::visit::find_all;
+
+    let bytes: &[u8] = &[
+        0x04, 0x3d, 0x00, 0x00, 0x00, 0x52, 0x3a, 0xfb, 0x37, 0x06, 0x00, 0xf0, 0x00, 0x48, 0x8d,
+        0x0b, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x51, 0x02, 0x38, 0x81, 0x00, 0x01,
+        0x86, 0xbc, 0x82, 0x00, 0x05, 0x88, 0x80, 0x19, 0x83, 0xab, 0x7e, 0x01, 0x5d, 0x00, 0x29,
+        0x00, 0x00, 0x6f, 0x00, 0x10, 0x9d, 0x22, 0x03, 0x8a, 0x4b, 0x44, 0xa2, 0xe4, 0xd3, 0xa6,
+        0x62, 0x34, 0x84, 0xae, 0x99, 0x9c, 0x21, 0x48, 0x71, 0x58, 0x6d, 0x7e, 0xc4, 0xfc, 0xc3,
+        0x2a, 0x29, 0x87, 0xfa, 0x6b, 0x52, 0xab,
+    ];
+    let msg = read_message(Cursor::new(bytes)).unwrap().to_dynamic_message().unwrap();
+
+    let results = find_all::<C1G2ReadOpSpecResult>(&msg);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].result, C1G2ReadResultType::Success);
+    assert_eq!(results[0].op_spec_id, 111);
+
+    assert_eq!(find_all::<TagReportData>(&msg).len(), 1);
+    assert!(find_all::<C1G2WriteOpSpecResult>(&msg).is_empty());
+}
+
+#[test]
+fn diff_reader_config() {
+    use crate::{config::diff_config, diff::Diff};
+
+    let antenna = |antenna_id, antenna_gain| AntennaProperties {
+        antenna_connected: true,
+        reserved: 0,
+        antenna_id,
+        antenna_gain,
+    };
+
+    let current = GetReaderConfigResponse {
+        status: LLRPStatus {
+            status_code: StatusCode::M_Success,
+            error_description: "".into(),
+            field_error: None,
+            parameter_error: None,
+        },
+        identification: None,
+        antenna_properties: vec![antenna(1, 0), antenna(2, 0)],
+        antenna_configuration: vec![],
+        reader_event_notification_spec: None,
+        ro_report_spec: None,
+        access_report_spec: None,
+        llrp_configuration_state_value: None,
+        keepalive_spec: Some(KeepaliveSpec {
+            keepalive_trigger_type: KeepaliveTriggerType::Null,
+            periodic_trigger_value: 0,
+        }),
+        gpi_port_current_state: vec![],
+        gpo_write_data: vec![],
+        events_and_reports: None,
+        custom: vec![],
+    };
+    assert!(current.diff(&current).is_empty());
+
+    let mut other = current.clone();
+    other.antenna_properties.pop();
+    other.keepalive_spec.as_mut().unwrap().periodic_trigger_value = 1000;
+    assert_eq!(current.diff(&other), vec![
+        crate::diff::Difference {
+            path: "antenna_properties[1]".into(),
+            left: Some(format!("{:?}", antenna(2, 0))),
+            right: None,
+        },
+        crate::diff::Difference {
+            path: "keepalive_spec.periodic_trigger_value".into(),
+            left: Some("0".into()),
+            right: Some("1000".into()),
+        },
+    ]);
+
+    let desired = SetReaderConfig {
+        reset_to_factory_default: false,
+        reserved: 0,
+        reader_event_notification_spec: None,
+        antenna_properties: vec![antenna(2, 0), antenna(3, 0)],
+        antenna_configuration: vec![],
+        ro_report_spec: None,
+        access_report_spec: None,
+        keepalive_spec: Some(KeepaliveSpec {
+            keepalive_trigger_type: KeepaliveTriggerType::Periodic,
+            periodic_trigger_value: 0,
+        }),
+        gpo_write_data: vec![],
+        gpi_port_current_state: vec![],
+        events_and_reports: None,
+        custom: vec![],
+    };
+    assert_eq!(diff_config(&current, &desired), vec![
+        crate::diff::Difference {
+            path: "keepalive_spec.keepalive_trigger_type".into(),
+            left: Some("Null".into()),
+            right: Some("Periodic".into()),
+        },
+        crate::diff::Difference {
+            path: "antenna_properties[3]".into(),
+            left: None,
+            right: Some(format!("{:?}", antenna(3, 0))),
+        },
+    ]);
+
+    let restore = crate::config::restore_config(&current);
+    assert!(diff_config(&current, &restore).is_empty());
+    assert_eq!(restore.antenna_properties, current.antenna_properties);
+    assert_eq!(restore.keepalive_spec, current.keepalive_spec);
+
+    assert!(crate::config::config_update(&current, &restore).is_none());
+
+    let update = crate::config::config_update(&current, &desired).unwrap();
+    assert_eq!(update.antenna_properties, vec![antenna(3, 0)]);
+    assert_eq!(update.keepalive_spec, desired.keepalive_spec);
+    assert_eq!(update.reader_event_notification_spec, None);
+}
+
+#[test]
+fn snapshot_request() {
+    let request = crate::config::snapshot_request();
+    assert_eq!(encode(&request), vec![0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+}
+
+#[test]
+fn hexdump() {
+    // A `SET_READER_CONFIG` message with a `KeepaliveSpec` parameter
+    let bytes = [
+        0x04, 0x03, 0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0xdc, 0x00, 0x09,
+        0x01, 0x00, 0x00, 0x07, 0xd0,
+    ];
+    let raw = read_message(Cursor::new(&bytes)).unwrap();
+
+    assert_eq!(
+        raw.hexdump(),
+        "00000000  04 03 00 00 00 14 00 00  00 01 00 00 dc 00 09 01  |................|\n\
+         00000010  00 00 07 d0                                       |....|\n"
+    );
+    assert_eq!(
+        raw.annotated_hexdump(),
+        "; header: version 1, message type 3 (SET_READER_CONFIG), length 20, id 1\n\
+         00000000  04 03 00 00 00 14 00 00  00 01                    |..........|\n\
+         ; fields\n\
+         0000000a  00                                                |.|\n\
+         ; parameter type 220 (KeepaliveSpec), length 9\n\
+         0000000b  00 dc 00 09 01 00 00 07  d0                       |.........|\n"
+    );
+
+    // A `DELETE_ROSPEC` message, where the ROSpecID looks like the header of an empty parameter
+    let raw = BinaryMessage::from_message(2, DeleteRospec { ro_spec_id: 4 }).unwrap();
+    assert_eq!(
+        raw.annotated_hexdump(),
+        "; header: version 1, message type 21 (DELETE_ROSPEC), length 14, id 2\n\
+         00000000  04 15 00 00 00 0e 00 00  00 02                    |..........|\n\
+         ; fields\n\
+         0000000a  00 00 00 04                                       |....|\n"
+    );
+
+    // A message without a body
+    let raw = BinaryMessage::from_message(3, Keepalive {}).unwrap();
+    assert_eq!(
+        raw.annotated_hexdump(),
+        "; header: version 1, message type 62 (KEEPALIVE), length 10, id 3\n\
+         00000000  04 3e 00 00 00 0a 00 00  00 03                    |.>........|\n"
+    );
+}
+
+#[test]
+fn names() {
+    use crate::names::{message_name, message_type, parameter_name, parameter_type};
+
+    assert_eq!(message_name(AddRospec::ID), Some("ADD_ROSPEC"));
+    assert_eq!(message_type("ADD_ROSPEC"), Some(AddRospec::ID));
+    assert_eq!(message_name(999), None);
+    assert_eq!(message_type("AddRospec"), None);
+
+    assert_eq!(parameter_name(177), Some("ROSpec"));
+    assert_eq!(parameter_type("ROSpec"), Some(177));
+    assert_eq!(parameter_name(13), Some("EPC_96"));
+    assert_eq!(parameter_type("EPC_96"), Some(13));
+    assert_eq!(parameter_name(2047), None);
+
+    for &(type_num, name) in crate::names::PARAMETERS {
+        assert_eq!(parameter_type(name), Some(type_num));
+    }
+    for &(type_num, name) in crate::names::MESSAGES {
+        assert_eq!(message_type(name), Some(type_num));
+        assert!(Message::is_known_type(type_num));
+    }
+}
+
+#[test]
+fn raw_message() {
+    let keepalive = [0x04, 0x3e, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x07];
+    let get_reader_capabilities =
+        [0x04, 0x01, 0x00, 0x00, 0x00, 0x0b, 0x00, 0x00, 0x00, 0x08, 0x00];
+    let mut stream = keepalive.to_vec();
+    stream.extend_from_slice(&get_reader_capabilities);
+    let mut reader = Cursor::new(&stream);
+
+    let raw = crate::read_raw_message(&mut reader).unwrap();
+    assert_eq!(raw.header().id, 7);
+    assert_eq!(raw.message(), &Keepalive {}.into());
+    assert_eq!(raw.frame(), &keepalive[..]);
+    assert!(raw.payload().is_empty());
+
+    let raw = crate::read_raw_message(&mut reader).unwrap();
+    assert_eq!(raw.message().message_type(), GetReaderCapabilities::ID);
+    assert_eq!(raw.payload(), &[0x00]);
+    assert_eq!(raw.into_frame(), get_reader_capabilities);
+
+    // The length in the header must match the frame
+    assert!(crate::RawMessage::from_frame(keepalive[..9].to_vec()).is_err());
+    assert!(crate::RawMessage::from_frame(get_reader_capabilities[..10].to_vec()).is_err());
+}
+
+#[test]
+fn select_parameters() {
+    let bytes: &[u8] = &[
+        0x04, 0x3d, 0x00, 0x00, 0x00, 0x52, 0x3a, 0xfb, 0x37, 0x06, 0x00, 0xf0, 0x00, 0x48, 0x8d,
+        0x0b, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x51, 0x02, 0x38, 0x81, 0x00, 0x01,
+        0x86, 0xbc, 0x82, 0x00, 0x05, 0x88, 0x80, 0x19, 0x83, 0xab, 0x7e, 0x01, 0x5d, 0x00, 0x29,
+        0x00, 0x00, 0x6f, 0x00, 0x10, 0x9d, 0x22, 0x03, 0x8a, 0x4b, 0x44, 0xa2, 0xe4, 0xd3, 0xa6,
+        0x62, 0x34, 0x84, 0xae, 0x99, 0x9c, 0x21, 0x48, 0x71, 0x58, 0x6d, 0x7e, 0xc4, 0xfc, 0xc3,
+        0x2a, 0x29, 0x87, 0xfa, 0x6b, 0x52, 0xab,
+    ];
+    let raw = read_message(Cursor::new(bytes)).unwrap();
+
+    let selected = raw.select_parameters(&[13, 1, 349]).unwrap();
+    let type_nums: Vec<_> = selected.iter().map(|x| x.type_num).collect();
+    assert_eq!(type_nums, vec![13, 1, 349]);
+
+    assert_eq!(
+        selected[0].decode::<EPC_96>().unwrap(),
+        [0x0b, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x51, 0x02, 0x38]
+    );
+    assert_eq!(selected[1].decode::<AntennaID>().unwrap(), 1);
+    let result = selected[2].decode::<C1G2ReadOpSpecResult>().unwrap();
+    assert_eq!(result.op_spec_id, 111);
+    assert_eq!(result.read_data.len(), 16);
+
+    // The sub-parameters of selected parameters are not searched
+    let selected = raw.select_parameters(&[240, 1]).unwrap();
+    assert_eq!(selected.len(), 1);
+    assert_eq!(selected[0].bytes, &bytes[10..]);
+
+    // Truncated parameters are reported
+    let err = crate::select::select_parameters(raw.message_type, &raw.value[..40], &[1]);
+    assert!(matches!(err, Err(crate::Error::InsufficientData { .. })));
+}
+
+#[test]
+fn fixtures() {
+    use crate::fixtures;
+
+    let report = fixtures::ro_access_report(3);
+    let frame = fixtures::frame(1, report.clone());
+    let raw = read_message(Cursor::new(&frame)).unwrap();
+    assert_eq!(raw.to_message::<RoAccessReport>().unwrap(), report);
+    assert_eq!(raw.select_parameters(&[13]).unwrap().len(), 3);
+    assert_eq!(
+        fixtures::epcs(3)[2],
+        crate::report::Epc::from(report.tag_report_data[2].epc_parameter.clone())
+    );
+
+    let add_rospec = fixtures::add_rospec(1, 4);
+    let raw = BinaryMessage::from_message(1, add_rospec.clone()).unwrap();
+    assert_eq!(raw.to_message::<AddRospec>().unwrap(), add_rospec);
+}
+
+#[test]
+fn hex() {
+    use crate::{parse_hex, report::Epc, FormatHex};
+
+    let bytes: Vec<u8> = (0..=255).collect();
+    let hex = bytes.to_hex();
+    assert_eq!(&hex[..8], "00010203");
+    assert_eq!(&hex[hex.len() - 4..], "feff");
+    assert_eq!(parse_hex(&hex).unwrap(), bytes);
+    assert_eq!(parse_hex(&hex.to_uppercase()).unwrap(), bytes);
+
+    assert_eq!(parse_hex(""), Some(vec![]));
+    assert_eq!(parse_hex("abc"), None);
+    assert_eq!(parse_hex("0g"), None);
+
+    assert_eq!([0x9d22_u16, 0x038a][..].to_hex(), "9d22038a");
+
+    let epc = Epc::from_hex("E2003412").unwrap();
+    assert_eq!(epc.bytes(), &[0xe2, 0x00, 0x34, 0x12]);
+    assert_eq!(epc.to_hex(), "e2003412");
+}
+
+#[test]
+fn presence_tracker() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::{
+        fixtures::tag_report_data,
+        presence::{PresenceEvent, PresenceTracker},
+    };
+
+    let at = |ms: u64| UNIX_EPOCH + Duration::from_millis(ms);
+    let read = |index: u32, antenna_id: u16| TagReportData {
+        antenna_id: Some(antenna_id),
+        tag_seen_count: None,
+        ..tag_report_data(index)
+    };
+
+    let mut tracker = PresenceTracker::new(Duration::from_secs(1)).min_reads(2);
+    let epc = read(0, 1).epc();
+
+    // A single stray read does not count as an arrival
+    assert_eq!(tracker.observe(&read(0, 1), at(0)), None);
+    assert_eq!(tracker.observe(&read(0, 1), at(2000)), None);
+    assert_eq!(
+        tracker.observe(&read(0, 2), at(2500)),
+        Some(PresenceEvent::TagArrived { epc: epc.clone(), antenna_id: None, first_seen: at(2000) })
+    );
+    assert_eq!(tracker.observe(&read(0, 1), at(2600)), None);
+    assert!(tracker.is_present(&epc, None));
+
+    assert_eq!(tracker.observe(&read(1, 1), at(2700)), None);
+    assert_eq!(tracker.expire(at(3000)), vec![]);
+    assert_eq!(
+        tracker.expire(at(3800)),
+        vec![PresenceEvent::TagDeparted { epc: epc.clone(), antenna_id: None, last_seen: at(2600) }]
+    );
+    assert!(!tracker.is_present(&epc, None));
+    assert_eq!(tracker.present().count(), 0);
+
+    // Tracking per antenna
+    let mut tracker = PresenceTracker::new(Duration::from_secs(1)).per_antenna(true);
+    assert!(tracker.observe(&read(0, 1), at(0)).is_some());
+    assert!(tracker.observe(&read(0, 2), at(100)).is_some());
+    assert!(tracker.observe(&read(0, 1), at(900)).is_none());
+    assert!(tracker.is_present(&epc, Some(2)));
+    assert_eq!(
+        tracker.expire(at(1500)),
+        vec![PresenceEvent::TagDeparted { epc: epc.clone(), antenna_id: Some(2), last_seen: at(100) }]
+    );
+    assert_eq!(tracker.present().collect::<Vec<_>>(), vec![(&epc, Some(1))]);
+}
+
+#[test]
+fn portal_direction() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::{
+        fixtures::tag_report_data,
+        portal::{Direction, DirectionDetector, PortalEvent, Side},
+    };
+
+    let at = |ms: u64| UNIX_EPOCH + Duration::from_millis(ms);
+    let read = |index: u32, antenna_id: u16, rssi: i8| TagReportData {
+        antenna_id: Some(antenna_id),
+        peak_rssi: Some(rssi),
+        ..tag_report_data(index)
+    };
+
+    let mut detector = DirectionDetector::new(Duration::from_secs(2))
+        .antenna(1, Side::Outside)
+        .antenna(2, Side::Inside);
+
+    // Tag 0 moves from the outside to the inside, tag 1 in the opposite direction
+    for (time, rssi_1, rssi_2) in &[(0, -50, -70), (500, -45, -60), (1000, -60, -45), (1500, -70, -50)]
+    {
+        detector.observe(&read(0, 1, *rssi_1), at(*time));
+        detector.observe(&read(0, 2, *rssi_2), at(*time));
+        detector.observe(&read(1, 1, *rssi_2), at(*time + 100));
+        detector.observe(&read(1, 2, *rssi_1), at(*time + 100));
+    }
+
+    // Tag 2 is only seen on one side, and reads from unmapped antennas are ignored
+    detector.observe(&read(2, 1, -50), at(0));
+    detector.observe(&read(2, 3, -50), at(200));
+
+    assert_eq!(detector.num_passing(), 3);
+    assert_eq!(detector.expire(at(3000)), vec![]);
+    assert_eq!(
+        detector.expire(at(3700)),
+        vec![
+            PortalEvent {
+                epc: read(0, 1, 0).epc(),
+                direction: Direction::In,
+                first_seen: at(0),
+                last_seen: at(1500),
+            },
+            PortalEvent {
+                epc: read(1, 1, 0).epc(),
+                direction: Direction::Out,
+                first_seen: at(100),
+                last_seen: at(1600),
+            },
+        ]
+    );
+    assert_eq!(detector.num_passing(), 0);
+}
+
+#[test]
+fn inventory_stats() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::{
+        fixtures::tag_report_data,
+        stats::{AntennaSnapshot, InventoryStats},
+    };
+
+    let at = |ms: u64| UNIX_EPOCH + Duration::from_millis(ms);
+    let read = |index: u32, antenna_id: u16, rssi: i8, count: u16| TagReportData {
+        antenna_id: Some(antenna_id),
+        peak_rssi: Some(rssi),
+        tag_seen_count: Some(count),
+        ..tag_report_data(index)
+    };
+
+    let mut stats = InventoryStats::new();
+    stats.observe(&read(0, 1, -41, 1), at(1000));
+    stats.observe(&read(1, 1, -45, 2), at(1500));
+    stats.observe(&read(0, 2, -60, 3), at(2000));
+    stats.observe(&TagReportData { antenna_id: None, peak_rssi: None, ..read(2, 1, 0, 4) }, at(3000));
+
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.unique_epcs, 3);
+    assert_eq!(snapshot.total_reads, 10);
+    assert_eq!(snapshot.duration, Duration::from_secs(2));
+    assert!((snapshot.reads_per_second - 5.0).abs() < 1e-9);
+    assert_eq!(
+        snapshot.antennas,
+        vec![
+            AntennaSnapshot { antenna_id: 1, reads: 3, unique_epcs: 2 },
+            AntennaSnapshot { antenna_id: 2, reads: 3, unique_epcs: 1 },
+        ]
+    );
+    assert_eq!(snapshot.rssi_histogram, vec![(-60, 3), (-45, 3)]);
+
+    stats.reset();
+    assert_eq!(stats.snapshot().total_reads, 0);
+    assert_eq!(stats.snapshot().reads_per_second, 0.0);
+}
+
+#[test]
+fn tag_filters() {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::{
+        filter::{Antennas, EpcMask, MinRssi, RateLimit, TagFilter},
+        fixtures::tag_report_data,
+    };
+
+    let at = |ms: u64| UNIX_EPOCH + Duration::from_millis(ms);
+    let read = |index: u32, antenna_id: u16, rssi: i8| TagReportData {
+        antenna_id: Some(antenna_id),
+        peak_rssi: Some(rssi),
+        ..tag_report_data(index)
+    };
+
+    // The fixture EPCs start with e2801160 and end with the index
+    assert!(EpcMask::prefix(&[0xe2, 0x80]).accept(&read(0, 1, -50), at(0)));
+    assert!(!EpcMask::prefix(&[0xe2, 0x81]).accept(&read(0, 1, -50), at(0)));
+    assert!(!EpcMask::prefix(&[0; 13]).matches(&[0; 12]));
+    assert!(EpcMask::new(&[0xe0, 0x00, 0x10], &[0xf0, 0x00, 0xf0]).matches(&[0xe2, 0x80, 0x11]));
+
+    assert!(Antennas::new(&[1, 2]).accept(&read(0, 2, -50), at(0)));
+    assert!(!Antennas::new(&[1, 2]).accept(&read(0, 3, -50), at(0)));
+    assert!(MinRssi(-60).accept(&read(0, 1, -60), at(0)));
+    assert!(!MinRssi(-60).accept(&read(0, 1, -61), at(0)));
+
+    let mut rate_limit = RateLimit::new(Duration::from_secs(1));
+    assert!(rate_limit.accept(&read(0, 1, -50), at(0)));
+    assert!(!rate_limit.accept(&read(0, 1, -50), at(500)));
+    assert!(rate_limit.accept(&read(1, 1, -50), at(500)));
+    assert!(rate_limit.accept(&read(0, 1, -50), at(1000)));
+    rate_limit.remove_expired(at(1200));
+    assert!(!rate_limit.accept(&read(1, 1, -50), at(1200)));
+    assert!(rate_limit.accept(&read(1, 1, -50), at(1500)));
+
+    // Rejected reads do not count towards the rate limit of later filters
+    let mut filter = MinRssi(-60)
+        .and(|data: &TagReportData, _| data.antenna_id != Some(4))
+        .and(RateLimit::new(Duration::from_secs(1)));
+    let tags = vec![read(0, 1, -70), read(0, 4, -50), read(0, 1, -50), read(0, 2, -50)];
+    let accepted = filter.filter(&tags, at(0));
+    assert_eq!(accepted, vec![&tags[2]]);
+}
+
+#[test]
+fn rssi_smoothing() {
+    use crate::{
+        report::Epc,
+        rssi::{RssiSmoother, Smoothing},
+    };
+
+    let epc = Epc::new(vec![0xe2, 0x00]);
+    let other = Epc::new(vec![0xe2, 0x01]);
+
+    let mut ema = RssiSmoother::new(Smoothing::Ema { alpha: 0.5 });
+    assert_eq!(ema.update(&epc, -60), -60.0);
+    assert_eq!(ema.update(&epc, -50), -55.0);
+    assert_eq!(ema.update(&other, -40), -40.0);
+    assert_eq!(ema.update(&epc, -51), -53.0);
+    assert_eq!(ema.get(&epc), Some(-53.0));
+    assert!(ema.remove(&epc));
+    assert_eq!(ema.get(&epc), None);
+
+    let mut window = RssiSmoother::new(Smoothing::Window { size: 3 }).reject_outliers(10.0);
+    assert_eq!(window.update(&epc, -60), -60.0);
+    assert_eq!(window.update(&epc, -58), -59.0);
+    assert_eq!(window.update(&epc, -56), -58.0);
+    assert_eq!(window.update(&epc, -54), -56.0);
+
+    // Single outliers are ignored, but repeated outliers restart the smoothing
+    assert_eq!(window.update(&epc, -30), -56.0);
+    assert_eq!(window.update(&epc, -55), -55.0);
+    assert_eq!(window.update(&epc, -30), -55.0);
+    assert_eq!(window.update(&epc, -31), -55.0);
+    assert_eq!(window.update(&epc, -32), -32.0);
+    assert_eq!(window.update(&epc, -34), -33.0);
+}
+
+#[test]
+fn rospec_scheduler() {
+    use crate::schedule::{RoSpecScheduler, Schedule, ScheduleAction, Weekday};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    // 1970-01-05 was a Monday
+    let monday = UNIX_EPOCH + Duration::from_secs(4 * 86400);
+    let at = |secs: u64| monday + Duration::from_secs(secs);
+
+    let schedule = Schedule::every(Duration::from_secs(300), Duration::from_secs(30))
+        .weekdays(&[Weekday::Monday]);
+    assert_eq!(schedule.window_start(at(310)), Some(at(300)));
+    assert!(!schedule.is_active(at(330)));
+    assert_eq!(schedule.next_transition(at(310)), Some(at(330)));
+    assert_eq!(schedule.next_start(at(330)), Some(at(600)));
+    // The last window on Monday is followed by the first window on the next Monday
+    assert_eq!(schedule.next_start(at(86100)), Some(at(7 * 86400)));
+    assert!(!schedule.is_active(at(86400 + 10)));
+
+    let mut scheduler = RoSpecScheduler::new().spec(1, schedule).spec(
+        2,
+        Schedule::every(Duration::from_secs(60), Duration::from_secs(10))
+            .offset(Duration::from_secs(30)),
+    );
+    assert_eq!(
+        scheduler.poll(at(0)),
+        vec![ScheduleAction::Enable(1), ScheduleAction::Start(1), ScheduleAction::Enable(2)]
+    );
+    assert_eq!(scheduler.poll(at(10)), vec![]);
+    assert_eq!(scheduler.next_poll(at(10)), Some(at(30)));
+    assert_eq!(scheduler.poll(at(30)), vec![ScheduleAction::Stop(1), ScheduleAction::Start(2)]);
+
+    // A spec that ends by itself is not restarted until its next window, but a preempted spec is
+    let event =
+        |event_type, ro_spec_id| ROSpecEvent { event_type, ro_spec_id, preempting_ro_spec_id: 0 };
+    scheduler.handle_event(&event(ROSpecEventType::End_Of_ROSpec, 2));
+    assert_eq!(scheduler.poll(at(35)), vec![]);
+    assert_eq!(scheduler.poll(at(40)), vec![]);
+    assert_eq!(scheduler.poll(at(90)), vec![ScheduleAction::Start(2)]);
+    scheduler.handle_event(&event(ROSpecEventType::Preemption_Of_ROSpec, 2));
+    assert_eq!(scheduler.poll(at(91)), vec![ScheduleAction::Start(2)]);
+
+    // After reconnecting every spec is enabled again and brought in line with its schedule
+    scheduler.reset();
+    assert_eq!(
+        scheduler.poll(at(95)),
+        vec![
+            ScheduleAction::Enable(1),
+            ScheduleAction::Stop(1),
+            ScheduleAction::Enable(2),
+            ScheduleAction::Start(2)
+        ]
+    );
+    assert_eq!(
+        ScheduleAction::Start(2).to_message(),
+        Message::StartRospec(StartRospec { ro_spec_id: 2 })
+    );
+}
+
+#[test]
+fn spill_buffer() {
+    use crate::spill::SpillBuffer;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let at = |secs: u64| UNIX_EPOCH + Duration::from_secs(1_600_000_000 + secs);
+    let message =
+        |id: u32| BinaryMessage { ver: 1, message_type: 61, id, value: vec![id as u8; 10] };
+
+    let path = std::env::temp_dir().join(format!("llrp-spill-test-{}", std::process::id()));
+    let mut buffer =
+        SpillBuffer::new(&path, 40).max_disk_bytes(3 * 28).max_age(Duration::from_secs(60));
+
+    // Two messages fit in memory, the next three are spilled and the last one is dropped
+    for id in 0..6 {
+        assert_eq!(buffer.push(message(id), at(0)).unwrap(), id < 5);
+    }
+    assert_eq!((buffer.len(), buffer.num_spilled(), buffer.num_dropped()), (5, 3, 1));
+    assert!(path.exists());
+
+    // Messages are returned in order, and new messages are spilled until the file is drained
+    assert_eq!(buffer.pop(at(1)).unwrap().unwrap().id, 0);
+    assert_eq!(buffer.pop(at(1)).unwrap().unwrap().id, 1);
+    assert_eq!(buffer.pop(at(1)).unwrap().unwrap().id, 2);
+    assert!(buffer.push(message(6), at(2)).unwrap());
+    assert_eq!(buffer.num_spilled(), 3);
+
+    let popped = buffer.pop(at(3)).unwrap().unwrap();
+    assert_eq!((popped.id, popped.value), (3, vec![3; 10]));
+
+    // Messages older than the maximum age are dropped
+    assert_eq!(buffer.pop(at(61)).unwrap().unwrap().id, 6);
+    assert_eq!(buffer.num_dropped(), 2);
+    assert!(buffer.pop(at(61)).unwrap().is_none());
+    assert!(buffer.is_empty());
+
+    assert!(buffer.push(message(7), at(62)).unwrap());
+    assert_eq!(buffer.num_spilled(), 0);
+
+    drop(buffer);
+    assert!(!path.exists());
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_sink() {
+    use crate::{report::TagObservation, sqlite::SqliteSink};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let at = |secs: u64| UNIX_EPOCH + Duration::from_secs(1_600_000_000 + secs);
+    let observation = |epc: &str, antenna_id, peak_rssi_dbm, read_count| TagObservation {
+        epc: epc.into(),
+        antenna_id: Some(antenna_id),
+        peak_rssi_dbm,
+        first_seen: None,
+        last_seen: None,
+        read_count,
+        op_spec_results: vec![],
+    };
+
+    let mut sink = SqliteSink::open_in_memory().unwrap().batch_size(2);
+    sink.push(observation("e200", 1, Some(-60), Some(3)), at(0)).unwrap();
+    assert_eq!(sink.count().unwrap(), 0);
+    sink.push(observation("e201", 1, None, None), at(1)).unwrap();
+    assert_eq!(sink.count().unwrap(), 2);
+
+    // Observations of the same EPC update the existing row
+    sink.push(observation("e200", 2, None, Some(2)), at(5)).unwrap();
+    sink.flush().unwrap();
+    let tag = sink.get("E200").unwrap().unwrap();
+    assert_eq!((tag.antenna_id, tag.peak_rssi_dbm, tag.read_count), (Some(2), Some(-60), 5));
+    assert_eq!((tag.first_seen, tag.last_seen), (at(0), at(5)));
+    assert_eq!(sink.get("e202").unwrap(), None);
+
+    let epcs: Vec<_> = sink.seen_since(at(1)).unwrap().into_iter().map(|tag| tag.epc).collect();
+    assert_eq!(epcs, vec!["e201", "e200"]);
+}
+
+#[test]
+fn dissect() {
+    // A `SET_READER_CONFIG` message with a `KeepaliveSpec` parameter
+    let bytes = [
+        0x04, 0x03, 0x00, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0xdc, 0x00, 0x09,
+        0x01, 0x00, 0x00, 0x07, 0xd0,
+    ];
+    let dissection = read_message(Cursor::new(&bytes)).unwrap().dissect().unwrap();
+    assert_eq!(
+        dissection.to_string(),
+        "    0    20  SET_READER_CONFIG\n\
+        \x20   0     1    version (bits 3..6) = 1 [04]\n\
+        \x20   0     2    message_type (bits 6..16) = 3 [0403]\n\
+        \x20   2     4    length = 20 [00000014]\n\
+        \x20   6     4    id = 1 [00000001]\n\
+        \x20  10     1    reset_to_factory_default (bits 0..1) = false [00]\n\
+        \x20  10     1    reserved (bits 1..8) = 0 [00]\n\
+        \x20  11     9    KeepaliveSpec\n\
+        \x20  11     2      type (bits 6..16) = 220 [00dc]\n\
+        \x20  13     2      length = 9 [0009]\n\
+        \x20  15     1      keepalive_trigger_type = Periodic (1) [01]\n\
+        \x20  16     4      periodic_trigger_value = 2000 [000007d0]\n"
+    );
+
+    let pdml = dissection.to_pdml();
+    assert!(pdml.contains(r#"<proto name="llrp" showname="SET_READER_CONFIG" pos="0" size="20">"#));
+    assert!(pdml.contains(
+        r#"<field name="KeepaliveSpec" pos="11" size="9" show="" value="00dc000901000007d0">"#
+    ));
+    assert!(pdml.contains(
+        r#"<field name="keepalive_trigger_type" pos="15" size="1" show="Periodic (1)" value="01"/>"#
+    ));
+
+    // TV parameters nested in TLV parameters
+    let frame = crate::fixtures::frame(1, crate::fixtures::ro_access_report(2));
+    let dissection = crate::dissect::Dissection::new(frame).unwrap();
+    let reports = &dissection.root().children[4..];
+    assert_eq!(reports.len(), 2);
+    assert_eq!(
+        (reports[1].name.as_str(), reports[1].offset, reports[1].len),
+        ("TagReportData", 58, 48)
+    );
+
+    let epc = &reports[1].children[2];
+    assert_eq!((epc.name.as_str(), epc.offset, epc.len), ("EPC_96", 62, 13));
+    assert_eq!(epc.children[1].value, "e28011600000000000000001");
+    assert_eq!(dissection.raw(&epc.children[0]), &[0x8d]);
+
+    // Truncated frames are rejected
+    assert!(crate::dissect::Dissection::new(bytes[..19].to_vec()).is_err());
+}
+
+#[test]
+fn version_negotiation() {
+    use crate::version::{NegotiationStep, VersionNegotiator, LLRP_1_0_1, LLRP_1_1};
+
+    let status = |status_code| LLRPStatus {
+        status_code,
+        error_description: "".into(),
+        field_error: None,
+        parameter_error: None,
+    };
+    let reply = |message| BinaryMessage::from_dynamic_message(1, &message).unwrap();
+
+    // A 1.1 reader currently using 1.0.1
+    let mut negotiator = VersionNegotiator::new(LLRP_1_1);
+    let request = negotiator.start(1);
+    assert_eq!((request.ver, request.message_type), (LLRP_1_1, GetSupportedVersion::ID));
+
+    let supported = Message::GetSupportedVersionResponse(GetSupportedVersionResponse {
+        current_version: LLRP_1_0_1,
+        supported_version: LLRP_1_1,
+        status: status(StatusCode::M_Success),
+    });
+    let request = match negotiator.handle_reply(&reply(supported), 2).unwrap() {
+        NegotiationStep::Send(request) => request,
+        step => panic!("unexpected step: {:?}", step),
+    };
+    assert_eq!(request.to_message::<SetProtocolVersion>().unwrap().protocol_version, LLRP_1_1);
+    assert_eq!(negotiator.version(), None);
+
+    let set = Message::SetProtocolVersionResponse(SetProtocolVersionResponse {
+        status: status(StatusCode::M_Success),
+    });
+    assert!(matches!(negotiator.handle_reply(&reply(set), 3), Ok(NegotiationStep::Done(2))));
+    assert_eq!(negotiator.version(), Some(LLRP_1_1));
+
+    // A 1.0.1 reader that does not support negotiation
+    let unsupported =
+        Message::ErrorMessage(ErrorMessage { status: status(StatusCode::M_UnsupportedMessage) });
+    let mut negotiator = VersionNegotiator::new(LLRP_1_1);
+    negotiator.start(1);
+    assert!(matches!(
+        negotiator.handle_reply(&reply(unsupported), 2),
+        Ok(NegotiationStep::Done(LLRP_1_0_1))
+    ));
+    assert_eq!(negotiator.version(), Some(LLRP_1_0_1));
+
+    // Other errors are reported
+    let other = Message::ErrorMessage(ErrorMessage { status: status(StatusCode::R_DeviceError) });
+    let mut negotiator = VersionNegotiator::new(LLRP_1_1);
+    negotiator.start(1);
+    assert_eq!(
+        negotiator.handle_reply(&reply(other), 2).unwrap_err(),
+        crate::Error::ErrorStatus(StatusCode::R_DeviceError)
+    );
+}
+
+#[test]
+fn compliance_checker() {
+    use crate::compliance::{ComplianceChecker, Violation};
+
+    let success = || LLRPStatus {
+        status_code: StatusCode::M_Success,
+        error_description: "".into(),
+        field_error: None,
+        parameter_error: None,
+    };
+    let msg = |id, message: Message| BinaryMessage::from_dynamic_message(id, &message).unwrap();
+
+    let mut checker = ComplianceChecker::new().hold_events_and_reports(true);
+
+    // Reports are held until events and reports are enabled
+    let report = msg(0, crate::fixtures::ro_access_report(1).into());
+    assert_eq!(
+        checker.observe_received(&report),
+        vec![Violation::ReportBeforeEnable { message_type: RoAccessReport::ID, id: 0 }]
+    );
+    assert_eq!(checker.observe_sent(&msg(1, EnableEventsAndReports {}.into())), vec![]);
+    assert_eq!(checker.observe_received(&report), vec![]);
+
+    // ROSpecs are added disabled, and can only be started once enabled
+    let add = msg(2, crate::fixtures::add_rospec(7, 1).into());
+    assert_eq!(checker.observe_sent(&add), vec![]);
+    let added = AddRospecResponse { status: success() };
+    assert_eq!(checker.observe_received(&msg(2, added.into())), vec![]);
+    assert_eq!(checker.ro_spec_enabled(7), Some(false));
+
+    assert_eq!(
+        checker.observe_sent(&msg(3, StartRospec { ro_spec_id: 7 }.into())),
+        vec![Violation::StartDisabledRoSpec { ro_spec_id: 7, id: 3 }]
+    );
+    let started = StartRospecResponse { status: success() };
+    assert_eq!(checker.observe_received(&msg(3, started.clone().into())), vec![]);
+
+    assert_eq!(checker.observe_sent(&msg(4, EnableRospec { ro_spec_id: 0 }.into())), vec![]);
+    let enabled = EnableRospecResponse { status: success() };
+    assert_eq!(checker.observe_received(&msg(4, enabled.into())), vec![]);
+    assert_eq!(checker.ro_spec_enabled(7), Some(true));
+    assert_eq!(checker.observe_sent(&msg(5, StartRospec { ro_spec_id: 7 }.into())), vec![]);
+
+    // Responses must answer an outstanding request of the matching type
+    assert_eq!(
+        checker.observe_sent(&msg(5, StopRospec { ro_spec_id: 7 }.into())),
+        vec![Violation::DuplicateMessageId { message_type: StopRospec::ID, id: 5 }]
+    );
+    assert_eq!(
+        checker.observe_received(&msg(5, started.clone().into())),
+        vec![Violation::UnexpectedResponse {
+            request_type: StopRospec::ID,
+            response_type: StartRospecResponse::ID,
+            id: 5
+        }]
+    );
+    assert_eq!(
+        checker.observe_received(&msg(6, started.into())),
+        vec![Violation::UnsolicitedResponse { message_type: StartRospecResponse::ID, id: 6 }]
+    );
+    assert_eq!(checker.num_pending(), 0);
+
+    let violation = Violation::StartDisabledRoSpec { ro_spec_id: 7, id: 3 };
+    assert_eq!(violation.to_string(), "START_ROSPEC (id 3) for disabled ROSpec 7");
+}
+#[test]
+fn unit_conversions() {
+    use crate::units;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    assert_eq!(units::rssi_to_dbm(-61), -61.0);
+    assert_eq!(units::power_to_dbm(3025), 30.25);
+    assert_eq!(units::dbm_to_power(-1.5), -150);
+    assert_eq!(units::dbm_to_power(1000.0), i16::MAX);
+    assert_eq!(units::khz_to_mhz(902_750), 902.75);
+    assert_eq!(units::mhz_to_khz(927.25), 927_250);
+    assert_eq!(units::micros_to_duration(1_500_000), Duration::from_millis(1500));
+    assert_eq!(units::duration_to_micros(Duration::from_secs(u64::MAX)), u64::MAX);
+    assert_eq!(units::micros_to_time(2_000_000), UNIX_EPOCH + Duration::from_secs(2));
+    assert_eq!(units::time_to_micros(UNIX_EPOCH - Duration::from_secs(1)), 0);
+
+    let entry =
+        |index, transmit_power_value| TransmitPowerLevelTableEntry { index, transmit_power_value };
+    let capabilities = UHFBandCapabilities {
+        transmit_power_level_table_entry: vec![entry(1, 1000), entry(2, 1025), entry(3, 1050)],
+        frequency_information: FrequencyInformation {
+            hopping: true,
+            reserved: 0,
+            frequency_hop_table: vec![FrequencyHopTable {
+                hop_table_id: 1,
+                reserved: 0,
+                frequency: vec![902_750, 903_250],
+            }],
+            fixed_frequency_table: None,
+        },
+        air_protocol_uhfrf_mode_table: vec![],
+    };
+    assert_eq!(capabilities.transmit_power_dbm(2), Some(10.25));
+    assert_eq!(capabilities.transmit_power_dbm(4), None);
+    assert_eq!(capabilities.transmit_power_index(10.4), Some(3));
+    assert_eq!(
+        capabilities.frequency_information.frequency_hop_table[0].frequencies_mhz(),
+        vec![902.75, 903.25]
+    );
+}
@@ -0,0 +1,57 @@
+//! Accessors for the timestamps attached to reports and events
+
+use std::time::SystemTime;
+
+use crate::{
+    choices::Timestamp,
+    parameters::{TagReportData, UTCTimestamp, Uptime},
+    units,
+};
+
+impl Timestamp {
+    /// Returns the absolute time of the timestamp, or `None` if the timestamp is measured from
+    /// when the reader was started
+    pub fn utc(&self) -> Option<SystemTime> {
+        match self {
+            Timestamp::UTCTimestamp(value) => Some(units::micros_to_time(value.microseconds)),
+            Timestamp::Uptime(_) => None,
+        }
+    }
+
+    /// Returns the number of microseconds since the reader was started, or `None` if the
+    /// timestamp is measured in UTC
+    pub fn uptime_us(&self) -> Option<u64> {
+        match self {
+            Timestamp::UTCTimestamp(_) => None,
+            Timestamp::Uptime(value) => Some(value.microseconds),
+        }
+    }
+
+    /// Returns the raw number of microseconds stored in the timestamp
+    pub fn microseconds(&self) -> u64 {
+        match self {
+            Timestamp::UTCTimestamp(value) => value.microseconds,
+            Timestamp::Uptime(value) => value.microseconds,
+        }
+    }
+}
+
+impl TagReportData {
+    /// The time the tag was first seen, preferring the UTC timestamp if the reader reported both
+    pub fn first_seen_timestamp(&self) -> Option<Timestamp> {
+        timestamp(self.first_seen_timestamp_utc, self.first_seen_timestamp_uptime)
+    }
+
+    /// The time the tag was last seen, preferring the UTC timestamp if the reader reported both
+    pub fn last_seen_timestamp(&self) -> Option<Timestamp> {
+        timestamp(self.last_seen_timestamp_utc, self.last_seen_timestamp_uptime)
+    }
+}
+
+fn timestamp(utc: Option<u64>, uptime: Option<u64>) -> Option<Timestamp> {
+    match (utc, uptime) {
+        (Some(microseconds), _) => Some(UTCTimestamp { microseconds }.into()),
+        (None, Some(microseconds)) => Some(Uptime { microseconds }.into()),
+        (None, None) => None,
+    }
+}
@@ -0,0 +1,154 @@
+//! Constructors for trigger parameters from `Duration` values and GPI events
+
+use std::{convert::TryFrom, time::Duration};
+
+use crate::{
+    enumerations::{
+        AISpecStopTriggerType, GPIPortState, KeepaliveTriggerType, RFSurveySpecStopTriggerType,
+        ROSpecStartTriggerType, ROSpecStopTriggerType,
+    },
+    parameters::{
+        AISpecStopTrigger, GPIPortCurrentState, GPITriggerValue, KeepaliveSpec,
+        PeriodicTriggerValue, RFSurveySpecStopTrigger, ROSpecStartTrigger, ROSpecStopTrigger,
+    },
+    Error, Result,
+};
+
+/// Converts `duration` to a whole number of milliseconds (rounded down), returning an error if it
+/// does not fit in a 32-bit field. A non-zero duration shorter than a millisecond is also an error,
+/// since a value of 0 disables most triggers.
+fn millis(duration: Duration) -> Result<u32> {
+    match u32::try_from(duration.as_millis()) {
+        Ok(0) if duration != Duration::from_millis(0) => Err(Error::DurationOutOfRange(duration)),
+        Ok(millis) => Ok(millis),
+        Err(_) => Err(Error::DurationOutOfRange(duration)),
+    }
+}
+
+impl ROSpecStopTrigger {
+    /// A trigger that stops the ROSpec after `duration` has elapsed
+    pub fn duration(duration: Duration) -> Result<Self> {
+        Ok(ROSpecStopTrigger {
+            ro_spec_stop_trigger_type: ROSpecStopTriggerType::Duration,
+            duration_trigger_value: millis(duration)?,
+            gpi_trigger_value: None,
+        })
+    }
+
+    /// A trigger that stops the ROSpec when GPI port `gpi_port_num` changes to `gpi_event`
+    /// (`true` for high), or after `timeout` if the event does not occur first (a zero timeout
+    /// waits for the event indefinitely).
+    ///
+    /// Together with `ROSpecStartTrigger::gpi` this runs an inventory while e.g. a motion sensor is
+    /// active. The GPI port must also be enabled with a `GPIPortCurrentState` parameter in
+    /// `SET_READER_CONFIG`.
+    pub fn gpi_with_timeout(gpi_port_num: u16, gpi_event: bool, timeout: Duration) -> Result<Self> {
+        Ok(ROSpecStopTrigger {
+            ro_spec_stop_trigger_type: ROSpecStopTriggerType::GPI_With_Timeout,
+            duration_trigger_value: 0,
+            gpi_trigger_value: Some(GPITriggerValue::gpi(gpi_port_num, gpi_event, timeout)?),
+        })
+    }
+}
+
+impl ROSpecStartTrigger {
+    /// A trigger that starts the ROSpec every `period`, with the first start `offset` after the
+    /// ROSpec is enabled
+    pub fn periodic(offset: Duration, period: Duration) -> Result<Self> {
+        Ok(ROSpecStartTrigger {
+            ro_spec_start_trigger_type: ROSpecStartTriggerType::Periodic,
+            periodic_trigger_value: Some(PeriodicTriggerValue::periodic(offset, period)?),
+            gpi_trigger_value: None,
+        })
+    }
+
+    /// A trigger that starts the ROSpec when GPI port `gpi_port_num` changes to `gpi_event`
+    /// (`true` for high)
+    pub fn gpi(gpi_port_num: u16, gpi_event: bool) -> Self {
+        ROSpecStartTrigger {
+            ro_spec_start_trigger_type: ROSpecStartTriggerType::GPI,
+            periodic_trigger_value: None,
+            gpi_trigger_value: Some(GPITriggerValue {
+                gpi_port_num,
+                gpi_event,
+                reserved: 0,
+                timeout: 0,
+            }),
+        }
+    }
+}
+
+impl GPITriggerValue {
+    /// Triggers when GPI port `gpi_port_num` changes to `gpi_event` (`true` for high), or after
+    /// `timeout` if it is non-zero
+    pub fn gpi(gpi_port_num: u16, gpi_event: bool, timeout: Duration) -> Result<Self> {
+        Ok(GPITriggerValue { gpi_port_num, gpi_event, reserved: 0, timeout: millis(timeout)? })
+    }
+}
+
+impl GPIPortCurrentState {
+    /// Enables GPI port `gpi_port_num`, which is required before it can trigger a spec
+    pub fn enabled(gpi_port_num: u16) -> Self {
+        GPIPortCurrentState {
+            gpi_port_num,
+            config: true,
+            reserved: 0,
+            state: GPIPortState::Unknown,
+        }
+    }
+}
+
+impl PeriodicTriggerValue {
+    /// Triggers every `period`, with the first trigger `offset` after the spec is enabled
+    pub fn periodic(offset: Duration, period: Duration) -> Result<Self> {
+        Ok(PeriodicTriggerValue {
+            offset: millis(offset)?,
+            period: millis(period)?,
+            utc_timestamp: None,
+        })
+    }
+}
+
+impl AISpecStopTrigger {
+    /// A trigger that stops the AISpec after `duration` has elapsed
+    pub fn duration(duration: Duration) -> Result<Self> {
+        Ok(AISpecStopTrigger {
+            ai_spec_stop_trigger_type: AISpecStopTriggerType::Duration,
+            duration_trigger: millis(duration)?,
+            gpi_trigger_value: None,
+            tag_observation_trigger: None,
+        })
+    }
+
+    /// A trigger that stops the AISpec when GPI port `gpi_port_num` changes to `gpi_event`
+    /// (`true` for high), or after `timeout` if the event does not occur first
+    pub fn gpi_with_timeout(gpi_port_num: u16, gpi_event: bool, timeout: Duration) -> Result<Self> {
+        Ok(AISpecStopTrigger {
+            ai_spec_stop_trigger_type: AISpecStopTriggerType::GPI_With_Timeout,
+            duration_trigger: 0,
+            gpi_trigger_value: Some(GPITriggerValue::gpi(gpi_port_num, gpi_event, timeout)?),
+            tag_observation_trigger: None,
+        })
+    }
+}
+
+impl RFSurveySpecStopTrigger {
+    /// A trigger that stops the RFSurveySpec after `duration` has elapsed
+    pub fn duration(duration: Duration) -> Result<Self> {
+        Ok(RFSurveySpecStopTrigger {
+            stop_trigger_type: RFSurveySpecStopTriggerType::Duration,
+            duration_period: millis(duration)?,
+            n: 0,
+        })
+    }
+}
+
+impl KeepaliveSpec {
+    /// Configures the reader to send a keepalive message every `period`
+    pub fn periodic(period: Duration) -> Result<Self> {
+        Ok(KeepaliveSpec {
+            keepalive_trigger_type: KeepaliveTriggerType::Periodic,
+            periodic_trigger_value: millis(period)?,
+        })
+    }
+}
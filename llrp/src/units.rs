@@ -0,0 +1,105 @@
+//! Conversions between the units used by LLRP and conventional units
+//!
+//! | Quantity             | LLRP encoding                       | Converted to              |
+//! |----------------------|-------------------------------------|---------------------------|
+//! | RSSI (`PeakRSSI`)    | signed dBm                          | dBm                       |
+//! | Transmit power table | signed hundredths of a dBm          | dBm                       |
+//! | Frequencies          | kHz                                 | MHz                       |
+//! | Timestamps           | microseconds (since the UNIX epoch) | `Duration` / `SystemTime` |
+
+use std::{
+    convert::TryFrom,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::parameters::{
+    FixedFrequencyTable, FrequencyHopTable, TransmitPowerLevelTableEntry, UHFBandCapabilities,
+};
+
+/// Converts a `PeakRSSI` value to dBm
+pub fn rssi_to_dbm(rssi: i8) -> f64 {
+    rssi as f64
+}
+
+/// Converts a transmit power table value (in hundredths of a dBm) to dBm
+pub fn power_to_dbm(value: i16) -> f64 {
+    value as f64 / 100.0
+}
+
+/// Converts a power in dBm to a transmit power table value, saturating at the limits of the
+/// encoding
+pub fn dbm_to_power(dbm: f64) -> i16 {
+    (dbm * 100.0).round().max(i16::MIN as f64).min(i16::MAX as f64) as i16
+}
+
+/// Converts a frequency in kHz to MHz
+pub fn khz_to_mhz(khz: u32) -> f64 {
+    khz as f64 / 1000.0
+}
+
+/// Converts a frequency in MHz to kHz, rounding to the nearest kHz
+pub fn mhz_to_khz(mhz: f64) -> u32 {
+    (mhz * 1000.0).round().max(0.0).min(u32::MAX as f64) as u32
+}
+
+/// Converts a number of microseconds (e.g. an `Uptime` timestamp) to a `Duration`
+pub fn micros_to_duration(micros: u64) -> Duration {
+    Duration::from_micros(micros)
+}
+
+/// Converts a `Duration` to a number of microseconds, saturating if it does not fit
+pub fn duration_to_micros(duration: Duration) -> u64 {
+    u64::try_from(duration.as_micros()).unwrap_or(u64::MAX)
+}
+
+/// Converts a `UTCTimestamp` value (microseconds since the UNIX epoch) to a `SystemTime`
+pub fn micros_to_time(micros: u64) -> SystemTime {
+    UNIX_EPOCH + micros_to_duration(micros)
+}
+
+/// Converts a `SystemTime` to microseconds since the UNIX epoch. Times before the epoch are
+/// converted to 0.
+pub fn time_to_micros(time: SystemTime) -> u64 {
+    duration_to_micros(time.duration_since(UNIX_EPOCH).unwrap_or_default())
+}
+
+impl TransmitPowerLevelTableEntry {
+    /// Returns the transmit power of the entry in dBm
+    pub fn transmit_power_dbm(&self) -> f64 {
+        power_to_dbm(self.transmit_power_value)
+    }
+}
+
+impl UHFBandCapabilities {
+    /// Returns the transmit power in dBm of an index into the transmit power table (e.g.
+    /// `RFTransmitter::transmit_power`), or `None` if the table has no such entry
+    pub fn transmit_power_dbm(&self, index: u16) -> Option<f64> {
+        self.transmit_power_level_table_entry
+            .iter()
+            .find(|entry| entry.index == index)
+            .map(|entry| entry.transmit_power_dbm())
+    }
+
+    /// Returns the index of the entry in the transmit power table closest to `dbm`
+    pub fn transmit_power_index(&self, dbm: f64) -> Option<u16> {
+        let value = dbm_to_power(dbm) as i32;
+        self.transmit_power_level_table_entry
+            .iter()
+            .min_by_key(|entry| (entry.transmit_power_value as i32 - value).abs())
+            .map(|entry| entry.index)
+    }
+}
+
+impl FrequencyHopTable {
+    /// Returns the frequencies of the hop table in MHz
+    pub fn frequencies_mhz(&self) -> Vec<f64> {
+        self.frequency.iter().map(|&khz| khz_to_mhz(khz)).collect()
+    }
+}
+
+impl FixedFrequencyTable {
+    /// Returns the frequencies of the table in MHz
+    pub fn frequencies_mhz(&self) -> Vec<f64> {
+        self.frequency.iter().map(|&khz| khz_to_mhz(khz)).collect()
+    }
+}
@@ -0,0 +1,166 @@
+//! Negotiation of the protocol version used with a reader
+//!
+//! LLRP 1.1 readers report the versions they support in response to `GET_SUPPORTED_VERSION`, and
+//! switch versions with `SET_PROTOCOL_VERSION`. LLRP 1.0.1 readers do not know either message and
+//! reply with an `ERROR_MESSAGE`, in which case version 1.0.1 is used without reporting an error.
+//!
+//! ```
+//! use llrp::version::{NegotiationStep, VersionNegotiator, LLRP_1_1};
+//!
+//! let mut negotiator = VersionNegotiator::new(LLRP_1_1);
+//! let request = negotiator.start(1);
+//! // ... send `request` and read the reply, then:
+//! // match negotiator.handle_reply(&reply, 2)? {
+//! //     NegotiationStep::Send(request) => { /* send `request` and handle its reply */ }
+//! //     NegotiationStep::Done(version) => { /* use `version`, e.g. in a `VersionCheck` */ }
+//! // }
+//! ```
+
+use crate::{
+    enumerations::StatusCode,
+    messages::{
+        ErrorMessage, GetSupportedVersion, GetSupportedVersionResponse, SetProtocolVersion,
+        SetProtocolVersionResponse,
+    },
+    parameters::LLRPStatus,
+    BinaryMessage, Error, LLRPMessage, Result,
+};
+
+/// The protocol version number of LLRP 1.0.1
+pub const LLRP_1_0_1: u8 = 1;
+
+/// The protocol version number of LLRP 1.1
+pub const LLRP_1_1: u8 = 2;
+
+/// What to do after handling a reply during negotiation
+#[derive(Debug, Clone)]
+pub enum NegotiationStep {
+    /// Send a message to the reader and pass its reply to `VersionNegotiator::handle_reply`
+    Send(BinaryMessage),
+
+    /// Negotiation has finished, and the given version should be used
+    Done(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    GettingSupported,
+    Setting(u8),
+    Done(u8),
+}
+
+/// Negotiates the protocol version with a reader, falling back to LLRP 1.0.1 if the reader does
+/// not support version negotiation.
+#[derive(Debug, Clone)]
+pub struct VersionNegotiator {
+    preferred: u8,
+    state: State,
+}
+
+impl VersionNegotiator {
+    /// Creates a negotiator that uses `preferred` if the reader supports it, or the highest
+    /// version the reader supports otherwise
+    pub fn new(preferred: u8) -> Self {
+        Self { preferred: preferred.max(LLRP_1_0_1), state: State::Idle }
+    }
+
+    /// Returns the `GET_SUPPORTED_VERSION` message that starts the negotiation
+    pub fn start(&mut self, id: u32) -> BinaryMessage {
+        self.state = State::GettingSupported;
+        request(id, GetSupportedVersion {})
+    }
+
+    /// Handles the reply to the last message sent, using `next_id` as the message id if another
+    /// message needs to be sent.
+    ///
+    /// Returns `Error::ErrorStatus` if the reader rejects negotiation for any reason other than
+    /// not supporting it, and `Error::UnexpectedType` if the reply is not a response to the last
+    /// message.
+    pub fn handle_reply(&mut self, reply: &BinaryMessage, next_id: u32) -> Result<NegotiationStep> {
+        let step = match self.state {
+            State::GettingSupported => {
+                if let Some(step) = self.check_unsupported(reply)? {
+                    return Ok(step);
+                }
+                let response = expect::<GetSupportedVersionResponse>(reply)?;
+                check_status(&response.status)?;
+
+                let version = self.preferred.min(response.supported_version);
+                if version == response.current_version {
+                    self.state = State::Done(version);
+                    NegotiationStep::Done(version)
+                } else {
+                    self.state = State::Setting(version);
+                    NegotiationStep::Send(request(
+                        next_id,
+                        SetProtocolVersion { protocol_version: version },
+                    ))
+                }
+            }
+            State::Setting(version) => {
+                if let Some(step) = self.check_unsupported(reply)? {
+                    return Ok(step);
+                }
+                let response = expect::<SetProtocolVersionResponse>(reply)?;
+                check_status(&response.status)?;
+
+                self.state = State::Done(version);
+                NegotiationStep::Done(version)
+            }
+            State::Idle | State::Done(_) => {
+                return Err(Error::UnexpectedType {
+                    expected: GetSupportedVersion::ID,
+                    found: reply.message_type,
+                })
+            }
+        };
+        Ok(step)
+    }
+
+    /// Returns the negotiated version, once negotiation has finished
+    pub fn version(&self) -> Option<u8> {
+        match self.state {
+            State::Done(version) => Some(version),
+            _ => None,
+        }
+    }
+
+    /// Falls back to LLRP 1.0.1 if the reply is an `ERROR_MESSAGE` indicating that the reader
+    /// does not support version negotiation
+    fn check_unsupported(&mut self, reply: &BinaryMessage) -> Result<Option<NegotiationStep>> {
+        if reply.message_type != ErrorMessage::ID {
+            return Ok(None);
+        }
+
+        let status = reply.to_message::<ErrorMessage>()?.status;
+        match status.status_code {
+            StatusCode::M_UnsupportedMessage | StatusCode::M_UnsupportedVersion => {
+                self.state = State::Done(LLRP_1_0_1);
+                Ok(Some(NegotiationStep::Done(LLRP_1_0_1)))
+            }
+            status_code => Err(Error::ErrorStatus(status_code)),
+        }
+    }
+}
+
+/// Encodes a negotiation message, which are only defined in LLRP 1.1
+fn request<T: LLRPMessage>(id: u32, message: T) -> BinaryMessage {
+    let mut request = BinaryMessage::from_message(id, message).unwrap();
+    request.ver = LLRP_1_1;
+    request
+}
+
+fn expect<T: LLRPMessage>(reply: &BinaryMessage) -> Result<T> {
+    if reply.message_type != T::ID {
+        return Err(Error::UnexpectedType { expected: T::ID, found: reply.message_type });
+    }
+    reply.to_message()
+}
+
+fn check_status(status: &LLRPStatus) -> Result<()> {
+    match status.status_code {
+        StatusCode::M_Success => Ok(()),
+        ref status_code => Err(Error::ErrorStatus(status_code.clone())),
+    }
+}